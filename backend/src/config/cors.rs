@@ -12,5 +12,7 @@ pub fn create_cors() -> Cors {
         .allowed_header(http::header::CONTENT_TYPE)
         .allowed_header(http::header::ACCESS_CONTROL_ALLOW_HEADERS)
         .allowed_header(http::header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        .allowed_header(http::header::IF_MATCH)
+        .expose_headers(vec![http::header::ETAG])
         .max_age(3600)
 }
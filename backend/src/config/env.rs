@@ -1,8 +1,26 @@
-use std::env;
+use std::{env, net::IpAddr};
 extern crate dotenv;
 
 lazy_static! {
     pub static ref MONGO_URL: String = env::var("MONGO_URL").unwrap().to_string();
     pub static ref REDIS_URL: String = env::var("REDIS_URL").unwrap().to_string();
     pub static ref APP_URL: String = env::var("APP_URL").unwrap().to_string();
+    /// comma separated emails of the users allowed to read every audit event
+    pub static ref ADMIN_EMAILS: Vec<String> = env::var("ADMIN_EMAILS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|it| it.trim().to_lowercase())
+        .filter(|it| !it.is_empty())
+        .collect();
+    /// audit events older than this are deleted by mongodb, 90 days when not set
+    pub static ref AUDIT_RETENTION_DAYS: u64 = env::var("AUDIT_RETENTION_DAYS")
+        .ok()
+        .and_then(|it| it.parse().ok())
+        .unwrap_or(90);
+    /// comma separated addresses of the proxies whose forwarded headers are trusted
+    pub static ref TRUSTED_PROXIES: Vec<IpAddr> = env::var("TRUSTED_PROXIES")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|it| it.trim().parse().ok())
+        .collect();
 }
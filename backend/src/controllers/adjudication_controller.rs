@@ -0,0 +1,259 @@
+use std::str::FromStr;
+
+use actix_web::{http::StatusCode, web::Json};
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, DateTime},
+    options::{FindOneAndUpdateOptions, ReturnDocument},
+};
+
+use crate::{
+    controllers::text_annotation_controller::{
+        ensure_editable, ensure_version, find_version_conflict,
+    },
+    database::mongodb::DB,
+    helpers::{
+        adjudication_helpers::merge_layers,
+        annotation_helpers::{get_change_events, validate_token_span},
+        audit_helpers::record_realtime_audit_event,
+        realtime_helpers::publish_annotation_event,
+        webhook_helpers::queue_realtime_webhook_event,
+    },
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        adjudication_model::{
+            AdjudicateBody, Adjudication, AdjudicationConflict, AdjudicationSpan,
+            ConflictResolution,
+        },
+        text_annotation_model::{AnnotationRole, TextAnnotation, Token},
+    },
+    object::error::ApiError,
+    policies::annotation_policy::authorize_annotation,
+};
+
+pub struct AdjudicationController;
+
+impl AdjudicationController {
+    /// merges the annotator layers, listing the spans they agree on and the conflicts
+    /// left to the reviewer.
+    pub fn get(id: String, auth: Option<UserAuthContext>) -> Result<Adjudication, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to adjudicate this annotation"));
+        }
+
+        let annotation = find_adjudicated_annotation(id, &auth.unwrap())?;
+
+        let (agreed, conflicts) = merge_layers(&annotation.layers);
+
+        Ok(Adjudication {
+            annotation_id: annotation._id.unwrap(),
+            annotators: annotation.layers.iter().map(|it| it.user_id).collect(),
+            agreed,
+            conflicts,
+        })
+    }
+
+    /// replaces the document's tokens with the agreed spans and the resolution of
+    /// every conflict, they become its gold tokens.
+    pub fn resolve(
+        id: String,
+        body: Json<AdjudicateBody>,
+        version: Option<i64>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<TextAnnotation, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to adjudicate this annotation"));
+        }
+
+        let auth = auth.unwrap();
+
+        let annotation = find_adjudicated_annotation(id, &auth)?;
+
+        ensure_version(&annotation, version, AnnotationRole::Editor, auth.user_id)?;
+
+        ensure_editable(&annotation)?;
+
+        let (agreed, conflicts) = merge_layers(&annotation.layers);
+
+        // the layers may have changed since the conflicts were listed
+        let is_stale = body
+            .resolutions
+            .iter()
+            .any(|resolution| !conflicts.iter().any(|it| it.id == resolution.conflict_id));
+
+        if is_stale {
+            return Err(ApiError::new(StatusCode::CONFLICT)
+                .set_msg("some resolutions do not match a conflict of the annotation"));
+        }
+
+        let mut spans = agreed;
+
+        for conflict in conflicts.iter() {
+            let resolution = body
+                .resolutions
+                .iter()
+                .find(|it| it.conflict_id == conflict.id);
+
+            if resolution.is_none() {
+                return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                    .set_msg(format!("conflict {} needs a resolution", conflict.id).as_str()));
+            }
+
+            spans.extend(resolve_conflict(
+                &annotation,
+                conflict,
+                resolution.unwrap(),
+            )?);
+        }
+
+        spans.sort_by_key(|it| it.start);
+
+        // spans are added one by one so they are checked against each other
+        let mut gold = annotation.clone();
+        gold.tokens = vec![];
+
+        for span in spans {
+            let span_validation = validate_token_span(span.start, span.end, &gold);
+
+            if span_validation.is_err() {
+                return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                    .set_msg(span_validation.err().unwrap().description.as_str()));
+            }
+
+            gold.tokens.push(Token {
+                _id: Some(ObjectId::new()),
+                start: span.start,
+                end: span.end,
+                label: span.label,
+                created_at: Some(DateTime::now()),
+                created_by: Some(auth.user_id),
+            });
+        }
+
+        let update_result = DB.text_annotation_collection.find_one_and_update(
+            doc! {"_id": annotation._id.unwrap(), "version": annotation.version},
+            doc! {
+              "$set": {
+                "tokens": to_bson(&gold.tokens).unwrap(),
+                "updated_at": DateTime::now(),
+              },
+              "$inc": {"version": 1},
+            },
+            FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build(),
+        );
+
+        if update_result.as_ref().is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to save the adjudicated tokens"));
+        }
+
+        // the layers were changed while adjudicating
+        if update_result.as_ref().unwrap().is_none() {
+            return Err(find_version_conflict(
+                annotation._id.unwrap(),
+                AnnotationRole::Editor,
+                auth.user_id,
+            ));
+        }
+
+        let updated_annotation = update_result.unwrap().unwrap();
+
+        for event in get_change_events(&annotation, &updated_annotation) {
+            record_realtime_audit_event(&auth, &updated_annotation, &event);
+            queue_realtime_webhook_event(&updated_annotation, &event);
+
+            publish_annotation_event(
+                updated_annotation._id.unwrap(),
+                Some(auth.user_id),
+                Some(updated_annotation.version),
+                event,
+            );
+        }
+
+        Ok(updated_annotation)
+    }
+}
+
+/// finds an annotation with at least two layers the user can adjudicate.
+fn find_adjudicated_annotation(
+    id: String,
+    auth: &UserAuthContext,
+) -> Result<TextAnnotation, ApiError> {
+    let (annotation, _) = authorize_annotation(id, auth, AnnotationRole::Editor)?;
+
+    if annotation.layers.len() < 2 {
+        return Err(ApiError::new(StatusCode::CONFLICT)
+            .set_msg("adjudication needs at least two annotator layers"));
+    }
+
+    Ok(annotation)
+}
+
+fn resolve_conflict(
+    annotation: &TextAnnotation,
+    conflict: &AdjudicationConflict,
+    resolution: &ConflictResolution,
+) -> Result<Vec<AdjudicationSpan>, ApiError> {
+    if let Some(user_id) = resolution.user_id.as_ref() {
+        let user_oid = ObjectId::from_str(user_id.as_str());
+
+        if user_oid.is_err() {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                .set_msg("unable to convert user id to object id"));
+        }
+
+        let version = conflict
+            .versions
+            .iter()
+            .find(|it| it.annotators.contains(user_oid.as_ref().unwrap()));
+
+        if version.is_none() {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                .set_msg("the user is not an annotator of this conflict"));
+        }
+
+        return Ok(version.unwrap().spans.clone());
+    }
+
+    if resolution.spans.is_none() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("a resolution needs an annotator or custom spans"));
+    }
+
+    let mut spans: Vec<AdjudicationSpan> = vec![];
+
+    for item in resolution.spans.as_ref().unwrap().iter() {
+        let label_oid = ObjectId::from_str(item.label.as_str());
+
+        if label_oid.is_err() {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                .set_msg("unable to convert label id to object id"));
+        }
+
+        let label_oid = label_oid.unwrap();
+
+        if !annotation.labels.iter().any(|it| it._id == Some(label_oid)) {
+            return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("label not found"));
+        }
+
+        // spans outside the conflict would overwrite the agreed ones or other conflicts
+        let range = conflict.start..=conflict.end;
+
+        if !range.contains(&item.start) || !range.contains(&item.end) {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY).set_msg(
+                format!("spans of conflict {} should stay inside it", conflict.id).as_str(),
+            ));
+        }
+
+        spans.push(AdjudicationSpan {
+            start: item.start,
+            end: item.end,
+            label: label_oid,
+        });
+    }
+
+    Ok(spans)
+}
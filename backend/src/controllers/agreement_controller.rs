@@ -0,0 +1,262 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use actix_web::{http::StatusCode, web};
+use mongodb::bson::{doc, oid::ObjectId};
+
+use crate::{
+    controllers::project_controller::{find_member_project, find_member_role},
+    database::mongodb::DB,
+    helpers::{
+        agreement_helpers::{binarize_tag, cohen_kappa, fleiss_kappa, spans_to_word_labels},
+        annotation_helpers::{get_labeled_spans, get_layer_view},
+        metrics_helpers::{
+            compute_partial_span_metrics, compute_span_metrics, LabelMetrics, LabeledSpan,
+        },
+        tagger_helpers::split_words,
+    },
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        agreement_model::{
+            AgreementQueryParams, AgreementReport, AgreementScores, DocumentAgreement,
+            LabelAgreement, PairAgreement,
+        },
+        text_annotation_model::TextAnnotation,
+    },
+    object::error::ApiError,
+};
+
+pub struct AgreementController;
+
+/// what two annotators produced on the documents they both annotated.
+#[derive(Default)]
+struct PairSamples {
+    documents: i64,
+    tags: Vec<(String, String)>,
+    spans: Vec<(Vec<LabeledSpan>, Vec<LabeledSpan>)>,
+}
+
+/// the word tags and spans of one annotator on a document.
+struct AnnotatorLayer {
+    user_id: ObjectId,
+    tags: Vec<String>,
+    spans: Vec<LabeledSpan>,
+}
+
+impl AgreementController {
+    /// compares the annotator layers of the project documents, a document needs
+    /// at least two layers to be part of the report.
+    pub fn get_project_agreement(
+        project_id: String,
+        query_params: web::Query<AgreementQueryParams>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<AgreementReport, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to see the project agreement"));
+        }
+
+        let auth = auth.unwrap();
+
+        let project = find_member_project(project_id, &auth)?;
+
+        if !find_member_role(&project, auth.user_id).is_some_and(|it| it.can_review()) {
+            return Err(ApiError::new(StatusCode::FORBIDDEN)
+                .set_msg("only a project reviewer can see the agreement"));
+        }
+
+        let lowest = query_params.lowest.unwrap_or(5).clamp(0, 50) as usize;
+
+        let fetch_result = DB.text_annotation_collection.find(
+            doc! {"project_id": project._id.unwrap(), "layers.1": {"$exists": true}},
+            None,
+        );
+
+        if fetch_result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to fetch the project documents")
+                .set_error(fetch_result.err().unwrap().to_string().as_str()));
+        }
+
+        let annotations: Vec<TextAnnotation> =
+            fetch_result.unwrap().filter_map(|it| it.ok()).collect();
+
+        let mut annotators: BTreeSet<ObjectId> = BTreeSet::new();
+        let mut labels: BTreeSet<String> = BTreeSet::new();
+        let mut pairs: BTreeMap<(ObjectId, ObjectId), PairSamples> = BTreeMap::new();
+        let mut items: Vec<Vec<String>> = vec![];
+        let mut documents: Vec<DocumentAgreement> = vec![];
+
+        for annotation in annotations.iter() {
+            let layers = get_annotator_layers(annotation);
+
+            let mut kappas: Vec<f64> = vec![];
+            let mut spans: Vec<(Vec<LabeledSpan>, Vec<LabeledSpan>)> = vec![];
+
+            for (i, a) in layers.iter().enumerate() {
+                annotators.insert(a.user_id);
+                labels.extend(a.spans.iter().map(|it| it.label.clone()));
+
+                for b in layers[i + 1..].iter() {
+                    let (first, second) = if a.user_id < b.user_id {
+                        (a, b)
+                    } else {
+                        (b, a)
+                    };
+
+                    let tags: Vec<(String, String)> = first
+                        .tags
+                        .iter()
+                        .cloned()
+                        .zip(second.tags.iter().cloned())
+                        .collect();
+
+                    kappas.push(cohen_kappa(&tags));
+                    spans.push((first.spans.clone(), second.spans.clone()));
+
+                    let samples = pairs.entry((first.user_id, second.user_id)).or_default();
+
+                    samples.documents += 1;
+                    samples.tags.extend(tags);
+                    samples
+                        .spans
+                        .push((first.spans.clone(), second.spans.clone()));
+                }
+            }
+
+            let document_items = get_word_items(&layers);
+
+            documents.push(DocumentAgreement {
+                annotation_id: annotation._id.unwrap(),
+                title: annotation.title.clone(),
+                annotators: layers.len() as i64,
+                cohen_kappa: mean(&kappas),
+                fleiss_kappa: fleiss_kappa(&document_items),
+                exact_f1: compute_span_metrics(&spans).overall.f1,
+            });
+
+            items.extend(document_items);
+        }
+
+        let all_spans: Vec<(Vec<LabeledSpan>, Vec<LabeledSpan>)> = pairs
+            .values()
+            .flat_map(|it| it.spans.iter().cloned())
+            .collect();
+
+        let exact = compute_span_metrics(&all_spans);
+        let partial = compute_partial_span_metrics(&all_spans);
+
+        let overall = AgreementScores {
+            cohen_kappa: mean(
+                &pairs
+                    .values()
+                    .map(|it| cohen_kappa(&it.tags))
+                    .collect::<Vec<f64>>(),
+            ),
+            fleiss_kappa: fleiss_kappa(&items),
+            exact_f1: exact.overall.f1,
+            partial_f1: partial.overall.f1,
+        };
+
+        let label_agreements: Vec<LabelAgreement> = labels
+            .iter()
+            .map(|label| {
+                let kappas: Vec<f64> = pairs
+                    .values()
+                    .map(|it| {
+                        let tags: Vec<(String, String)> = it
+                            .tags
+                            .iter()
+                            .map(|(a, b)| (binarize_tag(a, label), binarize_tag(b, label)))
+                            .collect();
+
+                        cohen_kappa(&tags)
+                    })
+                    .collect();
+
+                let label_items: Vec<Vec<String>> = items
+                    .iter()
+                    .map(|item| item.iter().map(|it| binarize_tag(it, label)).collect())
+                    .collect();
+
+                let f1_of = |metrics: &[LabelMetrics]| {
+                    metrics
+                        .iter()
+                        .find(|it| &it.label == label)
+                        .map(|it| it.f1)
+                        .unwrap_or(0.0)
+                };
+
+                LabelAgreement {
+                    label: label.clone(),
+                    scores: AgreementScores {
+                        cohen_kappa: mean(&kappas),
+                        fleiss_kappa: fleiss_kappa(&label_items),
+                        exact_f1: f1_of(&exact.labels),
+                        partial_f1: f1_of(&partial.labels),
+                    },
+                }
+            })
+            .collect();
+
+        let pair_agreements: Vec<PairAgreement> = pairs
+            .iter()
+            .map(|((first, second), samples)| PairAgreement {
+                first: *first,
+                second: *second,
+                documents: samples.documents,
+                cohen_kappa: cohen_kappa(&samples.tags),
+                exact_f1: compute_span_metrics(&samples.spans).overall.f1,
+                partial_f1: compute_partial_span_metrics(&samples.spans).overall.f1,
+            })
+            .collect();
+
+        let documents_count = documents.len() as i64;
+
+        documents.sort_by(|a, b| a.cohen_kappa.total_cmp(&b.cohen_kappa));
+        documents.truncate(lowest);
+
+        Ok(AgreementReport {
+            documents: documents_count,
+            annotators: annotators.into_iter().collect(),
+            overall,
+            labels: label_agreements,
+            pairs: pair_agreements,
+            lowest_documents: documents,
+        })
+    }
+}
+
+fn get_annotator_layers(annotation: &TextAnnotation) -> Vec<AnnotatorLayer> {
+    let words = split_words(&annotation.content);
+
+    annotation
+        .layers
+        .iter()
+        .map(|layer| {
+            let spans = get_labeled_spans(&get_layer_view(annotation.clone(), layer.user_id));
+
+            AnnotatorLayer {
+                user_id: layer.user_id,
+                tags: spans_to_word_labels(&words, &spans),
+                spans,
+            }
+        })
+        .collect()
+}
+
+/// the tags given to every word of the document, one per annotator.
+fn get_word_items(layers: &[AnnotatorLayer]) -> Vec<Vec<String>> {
+    let words_count = layers.first().map(|it| it.tags.len()).unwrap_or(0);
+
+    (0..words_count)
+        .map(|i| layers.iter().map(|it| it.tags[i].clone()).collect())
+        .collect()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+
+    values.iter().sum::<f64>() / values.len() as f64
+}
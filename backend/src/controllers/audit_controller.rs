@@ -0,0 +1,163 @@
+use std::str::FromStr;
+
+use actix_web::{http::StatusCode, web};
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, DateTime, Document},
+    options::FindOptions,
+};
+
+use crate::{
+    database::mongodb::DB,
+    helpers::audit_helpers::is_admin,
+    middleware::auth_middleware::UserAuthContext,
+    models::audit_model::{AuditEvent, AuditEventList, AuditQueryParams},
+    object::error::ApiError,
+};
+
+static DEFAULT_AUDIT_LIMIT: i64 = 50;
+static MAX_AUDIT_LIMIT: i64 = 200;
+
+pub struct AuditController;
+
+impl AuditController {
+    /// lists the events of the user's documents and account, newest first. admins can
+    /// read every event.
+    pub fn get_all(
+        query_params: web::Query<AuditQueryParams>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<AuditEventList, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to read the audit log"));
+        }
+
+        let auth = auth.unwrap();
+
+        let limit = query_params.limit.unwrap_or(DEFAULT_AUDIT_LIMIT);
+
+        if !(1..=MAX_AUDIT_LIMIT).contains(&limit) {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY).set_msg(
+                format!("limit should be between (1) and ({})", MAX_AUDIT_LIMIT).as_str(),
+            ));
+        }
+
+        let mut filter = doc! {};
+
+        if is_admin(&auth.user) {
+            if let Some(owner_id) = query_params.owner_id.clone() {
+                filter.insert("owner_id", parse_object_id(owner_id, "owner")?);
+            }
+        } else {
+            filter.insert("owner_id", auth.user_id);
+        }
+
+        if let Some(action) = query_params.action {
+            filter.insert("action", to_bson(&action).unwrap());
+        }
+
+        if let Some(actor_id) = query_params.actor_id.clone() {
+            filter.insert("actor_id", parse_object_id(actor_id, "actor")?);
+        }
+
+        if let Some(annotation_id) = query_params.annotation_id.clone() {
+            filter.insert(
+                "annotation_id",
+                parse_object_id(annotation_id, "annotation")?,
+            );
+        }
+
+        if let Some(target_id) = query_params.target_id.clone() {
+            filter.insert("target_id", parse_object_id(target_id, "target")?);
+        }
+
+        let mut created_at = Document::new();
+
+        if let Some(from) = query_params.from {
+            created_at.insert("$gte", DateTime::from_millis(from));
+        }
+
+        if let Some(to) = query_params.to {
+            created_at.insert("$lte", DateTime::from_millis(to));
+        }
+
+        if !created_at.is_empty() {
+            filter.insert("created_at", created_at);
+        }
+
+        if let Some(before) = query_params.before.clone() {
+            filter.insert("_id", doc! {"$lt": parse_object_id(before, "cursor")?});
+        }
+
+        let fetch_result = DB.audit_collection.find(
+            filter,
+            FindOptions::builder()
+                .sort(doc! {"_id": -1})
+                .limit(limit + 1)
+                .build(),
+        );
+
+        if fetch_result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to fetch audit events")
+                .set_error(fetch_result.err().unwrap().to_string().as_str()));
+        }
+
+        let mut results: Vec<AuditEvent> = fetch_result.unwrap().filter_map(|it| it.ok()).collect();
+
+        let has_more = results.len() as i64 > limit;
+
+        results.truncate(limit as usize);
+
+        let next_cursor = match has_more {
+            true => results.last().and_then(|it| it._id),
+            false => None,
+        };
+
+        Ok(AuditEventList {
+            results,
+            next_cursor,
+        })
+    }
+
+    pub fn get(id: String, auth: Option<UserAuthContext>) -> Result<AuditEvent, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to read the audit log"));
+        }
+
+        let auth = auth.unwrap();
+
+        let mut filter = doc! {"_id": parse_object_id(id, "audit event")?};
+
+        if !is_admin(&auth.user) {
+            filter.insert("owner_id", auth.user_id);
+        }
+
+        let event = DB.audit_collection.find_one(filter, None);
+
+        if event.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to fetch audit event")
+                .set_error(event.err().unwrap().to_string().as_str()));
+        }
+
+        let event = event.unwrap();
+
+        if event.is_none() {
+            return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("audit event not found"));
+        }
+
+        Ok(event.unwrap())
+    }
+}
+
+fn parse_object_id(id: String, name: &str) -> Result<ObjectId, ApiError> {
+    let object_id = ObjectId::from_str(id.as_str());
+
+    if object_id.is_err() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg(format!("unable to convert {} id to object id", name).as_str()));
+    }
+
+    Ok(object_id.unwrap())
+}
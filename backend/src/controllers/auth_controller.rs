@@ -4,23 +4,30 @@ use redis::Commands;
 use crate::{
     database::{mongodb::DB, redis::CACHE_DB},
     helpers::{
+        audit_helpers::record_audit_event,
         date_helpers::create_datetime_with_days_offset,
         password_helpers::{hash_password, verify_password},
         token_helpers::create_token_string,
     },
-    models::user_model::{CreateUserBody, SignInBody, User, UserAuthResponse},
+    models::{
+        audit_model::{AuditAction, AuditEvent},
+        user_model::{CreateUserBody, SignInBody, User, UserAuthResponse},
+    },
     object::error::ApiError,
     validators::{
         types::validator::BodyValidationHelpers,
         user_validator::{CreateUserBodyValidationResult, SignInValidationResult},
     },
 };
-use mongodb::bson::doc;
+use mongodb::bson::{doc, DateTime};
 
 pub struct AuthController;
 
 impl AuthController {
-    pub fn sign_up(json: Json<CreateUserBody>) -> Result<UserAuthResponse, ApiError> {
+    pub fn sign_up(
+        json: Json<CreateUserBody>,
+        ip: Option<String>,
+    ) -> Result<UserAuthResponse, ApiError> {
         let body = CreateUserBody {
             email: json.email.to_owned(),
             firstname: json.firstname.to_owned(),
@@ -53,6 +60,8 @@ impl AuthController {
             lastname: body.lastname.to_owned(),
             password: hashed_password.unwrap(),
             username: body.username.to_owned(),
+            created_at: Some(DateTime::now()),
+            updated_at: Some(DateTime::now()),
         };
 
         // check if email is used
@@ -94,6 +103,8 @@ impl AuthController {
 
         let user = user_result.unwrap().unwrap();
 
+        record_audit_event(AuditEvent::new(AuditAction::SignUp, user._id, ip).set_owner(user._id));
+
         // create token for 7 days
         let exp_date = create_datetime_with_days_offset(7);
         let sub = user._id.unwrap().to_string();
@@ -134,7 +145,10 @@ impl AuthController {
         })
     }
 
-    pub fn sign_in(json: Json<SignInBody>) -> Result<UserAuthResponse, ApiError> {
+    pub fn sign_in(
+        json: Json<SignInBody>,
+        ip: Option<String>,
+    ) -> Result<UserAuthResponse, ApiError> {
         let login = json.login.to_owned();
         let password = json.password.to_owned();
 
@@ -156,6 +170,11 @@ impl AuthController {
         );
 
         if user_result.as_ref().is_err() || user_result.as_ref().unwrap().is_none() {
+            // not tied to any account, only admins can read it
+            record_audit_event(
+                AuditEvent::new(AuditAction::SignInFailed, None, ip).set_details(login.as_str()),
+            );
+
             return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("user not found"));
         }
 
@@ -163,6 +182,12 @@ impl AuthController {
 
         // check if password match
         if !verify_password(password.clone(), user.password.clone()) {
+            record_audit_event(
+                AuditEvent::new(AuditAction::SignInFailed, None, ip)
+                    .set_owner(user._id)
+                    .set_details(login.as_str()),
+            );
+
             return Err(ApiError::new(StatusCode::BAD_REQUEST)
                 .set_msg("login and password does not match any user"));
         }
@@ -201,6 +226,8 @@ impl AuthController {
                 .set_msg("unable to store token in cache"));
         }
 
+        record_audit_event(AuditEvent::new(AuditAction::SignIn, user._id, ip).set_owner(user._id));
+
         // return token
         Ok(UserAuthResponse {
             token: token_string,
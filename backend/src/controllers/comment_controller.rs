@@ -0,0 +1,485 @@
+use std::{collections::HashMap, str::FromStr};
+
+use actix_web::{
+    http::StatusCode,
+    web::{self, Json},
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, Bson, DateTime, Document},
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+};
+
+use crate::{
+    database::mongodb::DB,
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        comment_model::{
+            Comment, CommentBody, CommentThread, CreateThreadBody, ThreadsQueryParams,
+        },
+        text_annotation_model::{AnnotationRole, TextAnnotation},
+    },
+    object::{common::Message, error::ApiError},
+    policies::annotation_policy::authorize_annotation,
+};
+
+static MAX_COMMENT_LENGTH: usize = 5000;
+
+pub struct CommentController;
+
+impl CommentController {
+    /// lists the document's threads the user can read, oldest first.
+    pub fn get_all(
+        annotation_id: String,
+        query_params: web::Query<ThreadsQueryParams>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Vec<CommentThread>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to read the comments"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (annotation, role) =
+            authorize_annotation(annotation_id, &auth, AnnotationRole::Viewer)?;
+
+        let mut filter = doc! {"annotation_id": annotation._id.unwrap()};
+
+        if role < AnnotationRole::Editor {
+            filter.insert(
+                "$or",
+                vec![doc! {"layer": Bson::Null}, doc! {"layer": auth.user_id}],
+            );
+        }
+
+        if let Some(resolved) = query_params.resolved {
+            filter.insert("resolved", resolved);
+        }
+
+        if let Some(token_id) = query_params.token_id.clone() {
+            filter.insert("token_id", parse_object_id(token_id, "token")?);
+        }
+
+        let fetch_result = DB
+            .comment_thread_collection
+            .find(filter, FindOptions::builder().sort(doc! {"_id": 1}).build());
+
+        if fetch_result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to fetch comment threads"));
+        }
+
+        let items: Vec<CommentThread> = fetch_result.unwrap().filter_map(|it| it.ok()).collect();
+
+        Ok(items)
+    }
+
+    /// opens a thread on the document, or on a token the user can see.
+    pub fn create(
+        annotation_id: String,
+        body: Json<CreateThreadBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<CommentThread, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to comment this annotation"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (annotation, role) =
+            authorize_annotation(annotation_id, &auth, AnnotationRole::Annotator)?;
+
+        let content = validate_content(&body.content)?;
+
+        let (token_id, layer) = match body.token_id.clone() {
+            Some(token_id) => {
+                let token_id = parse_object_id(token_id, "token")?;
+                let layer = find_token_layer(&annotation, token_id);
+
+                if layer.is_none() || !can_read_layer(layer.unwrap(), role, auth.user_id) {
+                    return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("token not found"));
+                }
+
+                (Some(token_id), layer.unwrap())
+            }
+            None => (None, None),
+        };
+
+        let thread = CommentThread {
+            _id: None,
+            annotation_id: annotation._id.unwrap(),
+            token_id,
+            layer,
+            user_id: auth.user_id,
+            comments: vec![Comment {
+                _id: ObjectId::new(),
+                user_id: auth.user_id,
+                content,
+                created_at: DateTime::now(),
+                edited_at: None,
+            }],
+            resolved: false,
+            resolved_by: None,
+            resolved_at: None,
+            created_at: DateTime::now(),
+            updated_at: DateTime::now(),
+        };
+
+        let result = DB.comment_thread_collection.insert_one(thread, None);
+
+        if result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to create comment thread")
+                .set_error(result.err().unwrap().to_string().as_str()));
+        }
+
+        let id = result.unwrap().inserted_id;
+
+        let thread = DB
+            .comment_thread_collection
+            .find_one(doc! {"_id": id}, None);
+
+        if thread.as_ref().is_err() || thread.as_ref().unwrap().is_none() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to retrieve created comment thread"));
+        }
+
+        Ok(thread.unwrap().unwrap())
+    }
+
+    pub fn reply(
+        params: web::Path<(String, String)>,
+        body: Json<CommentBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<CommentThread, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to reply to a comment"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (annotation_id, thread_id) = params.into_inner();
+
+        let thread = find_writable_thread(annotation_id, thread_id, &auth)?;
+
+        let content = validate_content(&body.content)?;
+
+        let comment = Comment {
+            _id: ObjectId::new(),
+            user_id: auth.user_id,
+            content,
+            created_at: DateTime::now(),
+            edited_at: None,
+        };
+
+        update_thread(
+            doc! {"_id": thread._id.unwrap()},
+            doc! {
+              "$push": {"comments": to_bson(&comment).unwrap()},
+              "$set": {"updated_at": DateTime::now()},
+            },
+        )
+    }
+
+    /// authors can edit their own comments only.
+    pub fn update_comment(
+        params: web::Path<(String, String, String)>,
+        body: Json<CommentBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<CommentThread, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to edit a comment"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (annotation_id, thread_id, comment_id) = params.into_inner();
+
+        let thread = find_writable_thread(annotation_id, thread_id, &auth)?;
+        let comment_id = find_own_comment(&thread, comment_id, &auth)?;
+
+        let content = validate_content(&body.content)?;
+
+        update_thread(
+            doc! {"_id": thread._id.unwrap(), "comments._id": comment_id},
+            doc! {"$set": {
+              "comments.$.content": content,
+              "comments.$.edited_at": DateTime::now(),
+              "updated_at": DateTime::now(),
+            }},
+        )
+    }
+
+    /// authors can delete their own comments only, the thread goes with its last comment.
+    pub fn delete_comment(
+        params: web::Path<(String, String, String)>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Message, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to delete a comment"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (annotation_id, thread_id, comment_id) = params.into_inner();
+
+        let thread = find_writable_thread(annotation_id, thread_id, &auth)?;
+        let comment_id = find_own_comment(&thread, comment_id, &auth)?;
+
+        let thread = update_thread(
+            doc! {"_id": thread._id.unwrap()},
+            doc! {
+              "$pull": {"comments": {"_id": comment_id}},
+              "$set": {"updated_at": DateTime::now()},
+            },
+        )?;
+
+        if thread.comments.is_empty() {
+            let result = DB.comment_thread_collection.delete_one(
+                doc! {"_id": thread._id.unwrap(), "comments": {"$size": 0}},
+                None,
+            );
+
+            if result.is_err() {
+                return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                    .set_msg("unable to delete comment thread"));
+            }
+        }
+
+        Ok(Message::new().set_msg("comment deleted successfully"))
+    }
+
+    /// the thread's author and the reviewers can resolve or reopen it.
+    pub fn set_resolved(
+        params: web::Path<(String, String)>,
+        resolved: bool,
+        auth: Option<UserAuthContext>,
+    ) -> Result<CommentThread, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to resolve a comment thread"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (annotation_id, thread_id) = params.into_inner();
+
+        let (annotation, role) =
+            authorize_annotation(annotation_id, &auth, AnnotationRole::Annotator)?;
+
+        let thread = find_thread(&annotation, thread_id, role, auth.user_id)?;
+
+        if thread.user_id != auth.user_id && role < AnnotationRole::Editor {
+            return Err(ApiError::new(StatusCode::FORBIDDEN)
+                .set_msg("only the thread's author or a reviewer can resolve it"));
+        }
+
+        let update_doc = match resolved {
+            true => doc! {"$set": {
+              "resolved": true,
+              "resolved_by": auth.user_id,
+              "resolved_at": DateTime::now(),
+              "updated_at": DateTime::now(),
+            }},
+            false => doc! {"$set": {
+              "resolved": false,
+              "resolved_by": Bson::Null,
+              "resolved_at": Bson::Null,
+              "updated_at": DateTime::now(),
+            }},
+        };
+
+        update_thread(doc! {"_id": thread._id.unwrap()}, update_doc)
+    }
+}
+
+/// counts the unresolved threads of each document the user can read.
+pub fn count_open_threads(
+    annotations: &[TextAnnotation],
+    user_id: ObjectId,
+) -> HashMap<ObjectId, i64> {
+    let ids: Vec<ObjectId> = annotations.iter().filter_map(|it| it._id).collect();
+
+    let cursor = DB.comment_thread_collection.aggregate(
+        vec![
+            doc! {"$match": {"annotation_id": {"$in": ids}, "resolved": false}},
+            doc! {"$group": {
+              "_id": {"annotation_id": "$annotation_id", "layer": "$layer"},
+              "count": {"$sum": 1},
+            }},
+        ],
+        None,
+    );
+
+    let mut counts: HashMap<ObjectId, i64> = HashMap::new();
+
+    if let Err(err) = cursor {
+        log::warn!("unable to count the open comment threads: {}", err);
+        return counts;
+    }
+
+    for group in cursor.unwrap().filter_map(|it| it.ok()) {
+        let key = group.get_document("_id").ok();
+        let annotation_id = key.and_then(|it| it.get_object_id("annotation_id").ok());
+        let layer = key.and_then(|it| it.get_object_id("layer").ok());
+        let count = match group.get("count") {
+            Some(Bson::Int32(count)) => *count as i64,
+            Some(Bson::Int64(count)) => *count,
+            _ => 0,
+        };
+
+        let annotation = annotations.iter().find(|it| it._id == annotation_id);
+
+        if let Some(annotation) = annotation {
+            let role = annotation.role.unwrap_or(AnnotationRole::Viewer);
+
+            if can_read_layer(layer, role, user_id) {
+                *counts.entry(annotation._id.unwrap()).or_default() += count;
+            }
+        }
+    }
+
+    counts
+}
+
+/// removes the threads of a deleted document.
+pub fn delete_annotation_threads(annotation_id: ObjectId) {
+    let result = DB
+        .comment_thread_collection
+        .delete_many(doc! {"annotation_id": annotation_id}, None);
+
+    if let Err(err) = result {
+        log::warn!("unable to delete the comment threads: {}", err);
+    }
+}
+
+/// threads on a layer's token follow the token's visibility.
+fn can_read_layer(layer: Option<ObjectId>, role: AnnotationRole, user_id: ObjectId) -> bool {
+    layer.is_none() || layer == Some(user_id) || role >= AnnotationRole::Editor
+}
+
+/// returns the layer holding the token, none for the document's tokens.
+fn find_token_layer(annotation: &TextAnnotation, token_id: ObjectId) -> Option<Option<ObjectId>> {
+    if annotation.tokens.iter().any(|it| it._id == Some(token_id)) {
+        return Some(None);
+    }
+
+    annotation
+        .layers
+        .iter()
+        .find(|layer| layer.tokens.iter().any(|it| it._id == Some(token_id)))
+        .map(|layer| Some(layer.user_id))
+}
+
+fn find_thread(
+    annotation: &TextAnnotation,
+    thread_id: String,
+    role: AnnotationRole,
+    user_id: ObjectId,
+) -> Result<CommentThread, ApiError> {
+    let thread_id = parse_object_id(thread_id, "thread")?;
+
+    let thread_result = DB.comment_thread_collection.find_one(
+        doc! {"_id": thread_id, "annotation_id": annotation._id.unwrap()},
+        None,
+    );
+
+    if thread_result.as_ref().is_err() || thread_result.as_ref().unwrap().is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("comment thread not found"));
+    }
+
+    let thread = thread_result.unwrap().unwrap();
+
+    if !can_read_layer(thread.layer, role, user_id) {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("comment thread not found"));
+    }
+
+    Ok(thread)
+}
+
+fn find_writable_thread(
+    annotation_id: String,
+    thread_id: String,
+    auth: &UserAuthContext,
+) -> Result<CommentThread, ApiError> {
+    let (annotation, role) = authorize_annotation(annotation_id, auth, AnnotationRole::Annotator)?;
+
+    find_thread(&annotation, thread_id, role, auth.user_id)
+}
+
+fn find_own_comment(
+    thread: &CommentThread,
+    comment_id: String,
+    auth: &UserAuthContext,
+) -> Result<ObjectId, ApiError> {
+    let comment_id = parse_object_id(comment_id, "comment")?;
+
+    let comment = thread.comments.iter().find(|it| it._id == comment_id);
+
+    if comment.is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("comment not found"));
+    }
+
+    if comment.unwrap().user_id != auth.user_id {
+        return Err(
+            ApiError::new(StatusCode::FORBIDDEN).set_msg("you can only change your own comments")
+        );
+    }
+
+    Ok(comment_id)
+}
+
+fn update_thread(filter: Document, update: Document) -> Result<CommentThread, ApiError> {
+    let update_result = DB.comment_thread_collection.find_one_and_update(
+        filter,
+        update,
+        FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build(),
+    );
+
+    if update_result.is_err() {
+        return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .set_msg("unable to update comment thread")
+            .set_error(update_result.err().unwrap().to_string().as_str()));
+    }
+
+    let thread = update_result.unwrap();
+
+    // the thread or the comment was deleted in the meantime
+    if thread.is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("comment thread not found"));
+    }
+
+    Ok(thread.unwrap())
+}
+
+fn validate_content(content: &str) -> Result<String, ApiError> {
+    let content = content.trim();
+
+    if content.is_empty() || content.chars().count() > MAX_COMMENT_LENGTH {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("failed to validate body")
+            .set_validation(vec![format!(
+                "\"content\": value length should be between (1) and ({})",
+                MAX_COMMENT_LENGTH
+            )]));
+    }
+
+    Ok(content.to_string())
+}
+
+fn parse_object_id(id: String, name: &str) -> Result<ObjectId, ApiError> {
+    let object_id = ObjectId::from_str(id.as_str());
+
+    if object_id.is_err() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg(format!("unable to convert {} id to object id", name).as_str()));
+    }
+
+    Ok(object_id.unwrap())
+}
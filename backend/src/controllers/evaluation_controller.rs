@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use actix_web::http::StatusCode;
+
+use crate::{
+    helpers::{
+        annotation_helpers::{get_labeled_spans, get_span_text},
+        metrics_helpers::{
+            compute_confusion_matrix, compute_partial_span_metrics, compute_span_metrics,
+            LabeledSpan,
+        },
+    },
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        evaluation_model::{EvaluationForm, EvaluationReport, EvaluationSpan, PredictionLine},
+        text_annotation_model::{AnnotationRole, TextAnnotation},
+    },
+    object::error::ApiError,
+    policies::annotation_policy::authorize_annotation,
+};
+
+pub struct EvaluationController;
+
+impl EvaluationController {
+    /// compares the uploaded predictions with the stored tokens of the same annotations.
+    pub fn evaluate(
+        form: EvaluationForm,
+        auth: Option<UserAuthContext>,
+    ) -> Result<EvaluationReport, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to evaluate predictions"));
+        }
+
+        let auth = auth.unwrap();
+
+        let content = std::fs::read_to_string(form.file.file.path());
+
+        if content.is_err() {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                .set_msg("unable to read predictions file"));
+        }
+
+        let lines = parse_prediction_lines(content.unwrap().as_str())?;
+
+        if lines.is_empty() {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                .set_msg("predictions file is empty"));
+        }
+
+        // the same annotation can be spread on several lines
+        let mut predictions: HashMap<String, Vec<LabeledSpan>> = HashMap::new();
+        let mut order: Vec<String> = vec![];
+
+        for line in lines {
+            if !predictions.contains_key(&line.annotation_id) {
+                order.push(line.annotation_id.clone());
+            }
+
+            predictions
+                .entry(line.annotation_id)
+                .or_default()
+                .extend(line.spans.into_iter().map(|it| LabeledSpan {
+                    start: it.start,
+                    end: it.end,
+                    label: it.label,
+                }));
+        }
+
+        let mut annotations: Vec<TextAnnotation> = vec![];
+
+        for id in order.iter() {
+            annotations.push(authorize_annotation(id.clone(), &auth, AnnotationRole::Editor)?.0);
+        }
+
+        let documents: Vec<(Vec<LabeledSpan>, Vec<LabeledSpan>)> = annotations
+            .iter()
+            .zip(order.iter())
+            .map(|(annotation, id)| (get_labeled_spans(annotation), predictions[id].clone()))
+            .collect();
+
+        let mut false_positives: Vec<EvaluationSpan> = vec![];
+        let mut false_negatives: Vec<EvaluationSpan> = vec![];
+
+        for ((gold, predicted), (annotation, id)) in
+            documents.iter().zip(annotations.iter().zip(order.iter()))
+        {
+            for span in predicted.iter().filter(|it| !gold.contains(it)) {
+                false_positives.push(to_evaluation_span(id, annotation, span));
+            }
+
+            for span in gold.iter().filter(|it| !predicted.contains(it)) {
+                false_negatives.push(to_evaluation_span(id, annotation, span));
+            }
+        }
+
+        Ok(EvaluationReport {
+            documents: documents.len() as i64,
+            exact: compute_span_metrics(&documents),
+            partial: compute_partial_span_metrics(&documents),
+            confusion_matrix: compute_confusion_matrix(&documents),
+            false_positives,
+            false_negatives,
+        })
+    }
+}
+
+fn parse_prediction_lines(content: &str) -> Result<Vec<PredictionLine>, ApiError> {
+    let mut lines: Vec<PredictionLine> = vec![];
+    let mut validation: Vec<String> = vec![];
+
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str::<PredictionLine>(line) {
+            Ok(item) => lines.push(item),
+            Err(err) => validation.push(format!("\"line {}\": {}", index + 1, err)),
+        }
+    }
+
+    if !validation.is_empty() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("unable to parse predictions file")
+            .set_validation(validation));
+    }
+
+    Ok(lines)
+}
+
+fn to_evaluation_span(id: &str, annotation: &TextAnnotation, span: &LabeledSpan) -> EvaluationSpan {
+    EvaluationSpan {
+        annotation_id: id.to_string(),
+        start: span.start,
+        end: span.end,
+        label: span.label.clone(),
+        text: get_span_text(annotation.content.as_str(), span.start, span.end),
+    }
+}
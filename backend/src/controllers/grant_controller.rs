@@ -0,0 +1,351 @@
+use std::str::FromStr;
+
+use actix_web::{
+    http::StatusCode,
+    web::{self, Json},
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, DateTime},
+    options::{FindOneAndUpdateOptions, ReturnDocument},
+};
+
+use crate::{
+    controllers::{
+        project_controller::find_owned_project,
+        text_annotation_controller::{ensure_version, find_version_conflict},
+    },
+    database::mongodb::DB,
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        grant_model::{AccessGrant, CreateGrantBody, UpdateGrantBody},
+        text_annotation_model::AnnotationRole,
+    },
+    object::error::ApiError,
+    policies::annotation_policy::authorize_annotation,
+};
+
+pub struct GrantController;
+
+/// the document or the project the grants are given on, the document's grants are
+/// changed over the version they were read from.
+#[derive(Clone, Copy)]
+enum GrantTarget {
+    Annotation(ObjectId, i64),
+    Project(ObjectId),
+}
+
+/// the grants' target, with its owner and current grants
+type GrantHolder = (GrantTarget, ObjectId, Vec<AccessGrant>);
+
+impl GrantController {
+    pub fn get_annotation_grants(
+        id: String,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Vec<AccessGrant>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to list the annotation grants"));
+        }
+
+        let (_, _, grants) = find_annotation_holder(id, &auth.unwrap())?;
+
+        Ok(grants)
+    }
+
+    pub fn add_annotation_grant(
+        id: String,
+        body: Json<CreateGrantBody>,
+        version: Option<i64>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Vec<AccessGrant>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to share this annotation"));
+        }
+
+        add_grant(
+            find_versioned_annotation_holder(id, &auth.unwrap(), version)?,
+            body,
+        )
+    }
+
+    pub fn update_annotation_grant(
+        params: web::Path<(String, String)>,
+        body: Json<UpdateGrantBody>,
+        version: Option<i64>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Vec<AccessGrant>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to update an annotation grant"));
+        }
+
+        let (id, user_id) = params.into_inner();
+
+        update_grant(
+            find_versioned_annotation_holder(id, &auth.unwrap(), version)?,
+            user_id,
+            body,
+        )
+    }
+
+    pub fn revoke_annotation_grant(
+        params: web::Path<(String, String)>,
+        version: Option<i64>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Vec<AccessGrant>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to revoke an annotation grant"));
+        }
+
+        let (id, user_id) = params.into_inner();
+
+        revoke_grant(
+            find_versioned_annotation_holder(id, &auth.unwrap(), version)?,
+            user_id,
+        )
+    }
+
+    pub fn get_project_grants(
+        id: String,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Vec<AccessGrant>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to list the project grants"));
+        }
+
+        let (_, _, grants) = find_project_holder(id, &auth.unwrap())?;
+
+        Ok(grants)
+    }
+
+    pub fn add_project_grant(
+        id: String,
+        body: Json<CreateGrantBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Vec<AccessGrant>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to share this project"));
+        }
+
+        add_grant(find_project_holder(id, &auth.unwrap())?, body)
+    }
+
+    pub fn update_project_grant(
+        params: web::Path<(String, String)>,
+        body: Json<UpdateGrantBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Vec<AccessGrant>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to update a project grant"));
+        }
+
+        let (id, user_id) = params.into_inner();
+
+        update_grant(find_project_holder(id, &auth.unwrap())?, user_id, body)
+    }
+
+    pub fn revoke_project_grant(
+        params: web::Path<(String, String)>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Vec<AccessGrant>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to revoke a project grant"));
+        }
+
+        let (id, user_id) = params.into_inner();
+
+        revoke_grant(find_project_holder(id, &auth.unwrap())?, user_id)
+    }
+}
+
+/// only the owner of a document manages who it is shared with.
+fn find_annotation_holder(id: String, auth: &UserAuthContext) -> Result<GrantHolder, ApiError> {
+    let (annotation, _) = authorize_annotation(id, auth, AnnotationRole::Owner)?;
+
+    Ok((
+        GrantTarget::Annotation(annotation._id.unwrap(), annotation.version),
+        annotation.user_id,
+        annotation.grants,
+    ))
+}
+
+/// same as `find_annotation_holder` for a change made against the given version.
+fn find_versioned_annotation_holder(
+    id: String,
+    auth: &UserAuthContext,
+    version: Option<i64>,
+) -> Result<GrantHolder, ApiError> {
+    let (annotation, role) = authorize_annotation(id, auth, AnnotationRole::Owner)?;
+
+    ensure_version(&annotation, version, role, auth.user_id)?;
+
+    Ok((
+        GrantTarget::Annotation(annotation._id.unwrap(), annotation.version),
+        annotation.user_id,
+        annotation.grants,
+    ))
+}
+
+fn find_project_holder(id: String, auth: &UserAuthContext) -> Result<GrantHolder, ApiError> {
+    let project = find_owned_project(id, auth)?;
+
+    Ok((
+        GrantTarget::Project(project._id.unwrap()),
+        project.user_id,
+        project.grants,
+    ))
+}
+
+fn add_grant(
+    holder: GrantHolder,
+    body: Json<CreateGrantBody>,
+) -> Result<Vec<AccessGrant>, ApiError> {
+    let (target, owner_id, mut grants) = holder;
+
+    validate_grant_role(body.role)?;
+
+    let user_result = DB.user_collection.find_one(
+        doc! {"$or": [{"username": body.login.clone()}, {"email": body.login.clone()}]},
+        None,
+    );
+
+    if user_result.as_ref().is_err() || user_result.as_ref().unwrap().is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("user not found"));
+    }
+
+    let user_id = user_result.unwrap().unwrap()._id.unwrap();
+
+    if user_id == owner_id {
+        return Err(
+            ApiError::new(StatusCode::CONFLICT).set_msg("the owner already has every access")
+        );
+    }
+
+    if grants.iter().any(|it| it.user_id == user_id) {
+        return Err(ApiError::new(StatusCode::CONFLICT)
+            .set_msg("user already has a grant, update it instead"));
+    }
+
+    grants.push(AccessGrant {
+        user_id,
+        role: body.role,
+        created_at: Some(DateTime::now()),
+    });
+
+    save_grants(target, owner_id, grants)
+}
+
+fn update_grant(
+    holder: GrantHolder,
+    user_id: String,
+    body: Json<UpdateGrantBody>,
+) -> Result<Vec<AccessGrant>, ApiError> {
+    let (target, owner_id, mut grants) = holder;
+
+    validate_grant_role(body.role)?;
+
+    let user_id = find_grantee_id(&grants, user_id)?;
+
+    for grant in grants.iter_mut().filter(|it| it.user_id == user_id) {
+        grant.role = body.role;
+    }
+
+    save_grants(target, owner_id, grants)
+}
+
+fn revoke_grant(holder: GrantHolder, user_id: String) -> Result<Vec<AccessGrant>, ApiError> {
+    let (target, owner_id, mut grants) = holder;
+
+    let user_id = find_grantee_id(&grants, user_id)?;
+
+    grants.retain(|it| it.user_id != user_id);
+
+    save_grants(target, owner_id, grants)
+}
+
+fn validate_grant_role(role: AnnotationRole) -> Result<(), ApiError> {
+    if role == AnnotationRole::Owner {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("the owner role cannot be granted"));
+    }
+
+    Ok(())
+}
+
+fn find_grantee_id(grants: &[AccessGrant], user_id: String) -> Result<ObjectId, ApiError> {
+    let user_id = ObjectId::from_str(user_id.as_str());
+
+    if user_id.is_err() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("unable to convert user id to object id"));
+    }
+
+    let user_id = user_id.unwrap();
+
+    if !grants.iter().any(|it| it.user_id == user_id) {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("grant not found"));
+    }
+
+    Ok(user_id)
+}
+
+fn save_grants(
+    target: GrantTarget,
+    owner_id: ObjectId,
+    grants: Vec<AccessGrant>,
+) -> Result<Vec<AccessGrant>, ApiError> {
+    let options = FindOneAndUpdateOptions::builder()
+        .return_document(ReturnDocument::After)
+        .build();
+
+    let saved_grants = match target {
+        GrantTarget::Annotation(id, version) => DB
+            .text_annotation_collection
+            .find_one_and_update(
+                doc! {"_id": id, "version": version},
+                doc! {
+                  "$set": {
+                    "grants": to_bson(&grants).unwrap(),
+                    "updated_at": DateTime::now(),
+                  },
+                  "$inc": {"version": 1},
+                },
+                options,
+            )
+            .map(|it| it.map(|it| it.grants)),
+        GrantTarget::Project(id) => DB
+            .project_collection
+            .find_one_and_update(
+                doc! {"_id": id},
+                doc! {"$set": {"grants": to_bson(&grants).unwrap()}},
+                options,
+            )
+            .map(|it| it.map(|it| it.grants)),
+    };
+
+    if saved_grants.as_ref().is_err() {
+        return Err(
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR).set_msg("unable to update grants")
+        );
+    }
+
+    if saved_grants.as_ref().unwrap().is_none() {
+        return Err(match target {
+            // only the owner manages the grants
+            GrantTarget::Annotation(id, _) => {
+                find_version_conflict(id, AnnotationRole::Owner, owner_id)
+            }
+            GrantTarget::Project(_) => {
+                ApiError::new(StatusCode::NOT_FOUND).set_msg("project not found")
+            }
+        });
+    }
+
+    Ok(saved_grants.unwrap().unwrap())
+}
@@ -1,4 +1,22 @@
+pub mod adjudication_controller;
+pub mod agreement_controller;
+pub mod audit_controller;
 pub mod auth_controller;
+pub mod comment_controller;
+pub mod evaluation_controller;
+pub mod grant_controller;
+pub mod model_backend_controller;
+pub mod project_controller;
 pub mod public_controller;
+pub mod queue_controller;
+pub mod realtime_controller;
+pub mod search_controller;
+pub mod share_controller;
+pub mod stats_controller;
+pub mod suggestion_controller;
+pub mod sync_controller;
+pub mod tagger_controller;
+pub mod team_controller;
 pub mod text_annotation_controller;
 pub mod user_controller;
+pub mod webhook_controller;
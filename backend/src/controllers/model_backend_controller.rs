@@ -0,0 +1,511 @@
+use std::{str::FromStr, sync::Once, thread, time::Duration};
+
+use actix_web::{
+    http::StatusCode,
+    web::{self, Json},
+};
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, DateTime},
+    options::{FindOneAndUpdateOptions, ReturnDocument},
+};
+
+use crate::{
+    controllers::{
+        suggestion_controller::{
+            accept_suggestions, find_invalid_suggestion, queue_suggestions, save_suggestion_state,
+        },
+        text_annotation_controller::ensure_version,
+    },
+    database::mongodb::DB,
+    helpers::{
+        annotation_helpers::get_change_events, audit_helpers::record_realtime_audit_event,
+        model_backend_helpers::request_predictions, realtime_helpers::publish_annotation_event,
+        webhook_helpers::queue_realtime_webhook_event,
+    },
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        model_backend_model::{
+            CreateModelBackendBody, DroppedPrediction, ModelBackend, PreAnnotationJob,
+            PreAnnotationResponse, PredictionTarget, UpdateModelBackendBody,
+        },
+        text_annotation_model::{AnnotationRole, Suggestion, TextAnnotation},
+    },
+    object::{common::Message, error::ApiError},
+    policies::annotation_policy::authorize_annotation,
+};
+
+static DEFAULT_TIMEOUT_MS: i64 = 5000;
+static MAX_TIMEOUT_MS: i64 = 60000;
+
+/// a claimed pre-annotation is run again by any instance once this is over, in case the
+/// instance running it stopped.
+static JOB_CLAIM_SECONDS: i64 = 10 * 60;
+
+/// a pre-annotation saved over a changed annotation is run again after this delay.
+static JOB_RETRY_SECONDS: i64 = 5;
+
+static MAX_JOB_ATTEMPTS: i64 = 3;
+
+static WORKER_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+static WORKER: Once = Once::new();
+
+pub struct ModelBackendController;
+
+impl ModelBackendController {
+    pub fn create(
+        body: Json<CreateModelBackendBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<ModelBackend, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to create a model backend"));
+        }
+
+        let backend = ModelBackend {
+            _id: None,
+            user_id: auth.unwrap().user_id,
+            name: body.name.trim().to_string(),
+            url: body.url.trim().to_string(),
+            timeout_ms: body.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS),
+            contract: body.contract.clone().unwrap_or_default(),
+            target: body.target.unwrap_or(PredictionTarget::Suggestions),
+            run_on_create: body.run_on_create.unwrap_or(false),
+        };
+
+        validate_backend(&backend)?;
+
+        let result = DB.model_backend_collection.insert_one(backend, None);
+
+        if result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to create model backend")
+                .set_error(result.err().unwrap().to_string().as_str()));
+        }
+
+        let id = result.unwrap().inserted_id;
+
+        let backend = DB.model_backend_collection.find_one(doc! {"_id": id}, None);
+
+        if backend.as_ref().is_err() || backend.as_ref().unwrap().is_none() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to retrieve created model backend"));
+        }
+
+        Ok(backend.unwrap().unwrap())
+    }
+
+    pub fn get_all(auth: Option<UserAuthContext>) -> Result<Vec<ModelBackend>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to list your model backends"));
+        }
+
+        let fetch_result = DB
+            .model_backend_collection
+            .find(doc! {"user_id": auth.unwrap().user_id}, None);
+
+        if fetch_result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to fetch model backends"));
+        }
+
+        let items: Vec<ModelBackend> = fetch_result.unwrap().filter_map(|it| it.ok()).collect();
+
+        Ok(items)
+    }
+
+    pub fn update(
+        id: String,
+        body: Json<UpdateModelBackendBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<ModelBackend, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to update a model backend"));
+        }
+
+        let mut backend = find_owned_backend(id, &auth.unwrap())?;
+
+        if let Some(name) = &body.name {
+            backend.name = name.trim().to_string();
+        }
+
+        if let Some(url) = &body.url {
+            backend.url = url.trim().to_string();
+        }
+
+        if let Some(timeout_ms) = body.timeout_ms {
+            backend.timeout_ms = timeout_ms;
+        }
+
+        if let Some(contract) = &body.contract {
+            backend.contract = contract.clone();
+        }
+
+        if let Some(target) = body.target {
+            backend.target = target;
+        }
+
+        if let Some(run_on_create) = body.run_on_create {
+            backend.run_on_create = run_on_create;
+        }
+
+        validate_backend(&backend)?;
+
+        let update_result = DB.model_backend_collection.find_one_and_update(
+            doc! {"_id": backend._id.unwrap()},
+            doc! {"$set": {
+              "name": backend.name.clone(),
+              "url": backend.url.clone(),
+              "timeout_ms": backend.timeout_ms,
+              "contract": to_bson(&backend.contract).unwrap(),
+              "target": to_bson(&backend.target).unwrap(),
+              "run_on_create": backend.run_on_create,
+            }},
+            FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build(),
+        );
+
+        if update_result.as_ref().is_err() || update_result.as_ref().unwrap().is_none() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to update model backend"));
+        }
+
+        Ok(update_result.unwrap().unwrap())
+    }
+
+    pub fn delete(id: String, auth: Option<UserAuthContext>) -> Result<Message, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to delete a model backend"));
+        }
+
+        let backend = find_owned_backend(id, &auth.unwrap())?;
+
+        let result = DB
+            .model_backend_collection
+            .delete_one(doc! {"_id": backend._id.unwrap()}, None);
+
+        if result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to delete model backend"));
+        }
+
+        Ok(Message::new().set_msg("model backend deleted successfully"))
+    }
+
+    /// sends the annotation content to the backend and stores the predicted spans.
+    pub fn run(
+        params: web::Path<(String, String)>,
+        version: Option<i64>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<PreAnnotationResponse, ApiError> {
+        let (annotation_id, backend_id) = params.into_inner();
+
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to pre-annotate this annotation"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (mut annotation, role) =
+            authorize_annotation(annotation_id, &auth, AnnotationRole::Editor)?;
+
+        ensure_version(&annotation, version, role, auth.user_id)?;
+
+        let backend = find_owned_backend(backend_id, &auth)?;
+
+        let previous = annotation.clone();
+
+        let dropped = apply_backend(&mut annotation, &backend)?;
+
+        let updated_annotation = save_suggestion_state(&annotation, role, auth.user_id)?;
+
+        for event in get_change_events(&previous, &updated_annotation) {
+            record_realtime_audit_event(&auth, &updated_annotation, &event);
+            queue_realtime_webhook_event(&updated_annotation, &event);
+
+            publish_annotation_event(
+                updated_annotation._id.unwrap(),
+                Some(auth.user_id),
+                Some(updated_annotation.version),
+                event,
+            );
+        }
+
+        Ok(PreAnnotationResponse {
+            annotation: updated_annotation,
+            dropped,
+        })
+    }
+}
+
+/// queues the pre-annotation of a created annotation when its owner has backends
+/// flagged with `run_on_create`, the worker runs it so the creation is not slowed down.
+pub fn queue_pre_annotation(annotation: &TextAnnotation) {
+    let count_result = DB.model_backend_collection.count_documents(
+        doc! {"user_id": annotation.user_id, "run_on_create": true},
+        None,
+    );
+
+    if count_result.unwrap_or(0) == 0 {
+        return;
+    }
+
+    let job = PreAnnotationJob {
+        _id: None,
+        annotation_id: annotation._id.unwrap(),
+        attempt_count: 0,
+        next_attempt_at: DateTime::now(),
+        created_at: DateTime::now(),
+    };
+
+    if let Err(err) = DB.pre_annotation_job_collection.insert_one(job, None) {
+        log::warn!("unable to queue the pre-annotation: {}", err);
+    }
+}
+
+/// starts running the queued pre-annotations in the background, once per instance.
+pub fn start_pre_annotation_worker() {
+    WORKER.call_once(|| {
+        thread::spawn(run_worker);
+    });
+}
+
+fn run_worker() {
+    loop {
+        while let Some(job) = claim_due_job() {
+            run_job(job);
+        }
+
+        thread::sleep(WORKER_POLL_INTERVAL);
+    }
+}
+
+fn seconds_from_now(seconds: i64) -> DateTime {
+    DateTime::from_millis(Utc::now().timestamp_millis() + seconds * 1000)
+}
+
+fn claim_due_job() -> Option<PreAnnotationJob> {
+    let claim_result = DB.pre_annotation_job_collection.find_one_and_update(
+        doc! {"next_attempt_at": {"$lte": DateTime::now()}},
+        doc! {
+          "$set": {"next_attempt_at": seconds_from_now(JOB_CLAIM_SECONDS)},
+          "$inc": {"attempt_count": 1},
+        },
+        FindOneAndUpdateOptions::builder()
+            .sort(doc! {"next_attempt_at": 1})
+            .return_document(ReturnDocument::After)
+            .build(),
+    );
+
+    match claim_result {
+        Ok(job) => job,
+        Err(err) => {
+            log::warn!("unable to claim a pre-annotation: {}", err);
+            None
+        }
+    }
+}
+
+fn run_job(job: PreAnnotationJob) {
+    let annotation = DB
+        .text_annotation_collection
+        .find_one(doc! {"_id": job.annotation_id}, None);
+
+    let done = match annotation {
+        Ok(Some(annotation)) => pre_annotate(annotation),
+        // the annotation was deleted in the meantime
+        Ok(None) => true,
+        Err(err) => {
+            log::warn!("unable to find the annotation to pre-annotate: {}", err);
+            false
+        }
+    };
+
+    if done || job.attempt_count >= MAX_JOB_ATTEMPTS {
+        if !done {
+            log::warn!(
+                "pre-annotation of annotation {} given up after {} attempts",
+                job.annotation_id,
+                job.attempt_count
+            );
+        }
+
+        let _ = DB
+            .pre_annotation_job_collection
+            .delete_one(doc! {"_id": job._id.unwrap()}, None);
+
+        return;
+    }
+
+    let _ = DB.pre_annotation_job_collection.update_one(
+        doc! {"_id": job._id.unwrap()},
+        doc! {"$set": {"next_attempt_at": seconds_from_now(JOB_RETRY_SECONDS)}},
+        None,
+    );
+}
+
+/// runs every backend flagged with `run_on_create`, a failing backend is logged and
+/// skipped. returns false when the annotation changed before the result was saved.
+fn pre_annotate(annotation: TextAnnotation) -> bool {
+    let fetch_result = DB.model_backend_collection.find(
+        doc! {"user_id": annotation.user_id, "run_on_create": true},
+        None,
+    );
+
+    if fetch_result.is_err() {
+        return false;
+    }
+
+    let backends: Vec<ModelBackend> = fetch_result.unwrap().filter_map(|it| it.ok()).collect();
+
+    let mut updated = annotation.clone();
+
+    for backend in backends.iter() {
+        match apply_backend(&mut updated, backend) {
+            Ok(dropped) if !dropped.is_empty() => log::warn!(
+                "model backend \"{}\" returned {} invalid spans on creation",
+                backend.name,
+                dropped.len()
+            ),
+            Ok(_) => {}
+            Err(err) => log::warn!(
+                "model backend \"{}\" failed on creation: {}",
+                backend.name,
+                err
+            ),
+        }
+    }
+
+    let saved = save_suggestion_state(&updated, AnnotationRole::Owner, updated.user_id);
+
+    if saved.is_err() {
+        return false;
+    }
+
+    let updated_annotation = saved.unwrap();
+
+    for event in get_change_events(&annotation, &updated_annotation) {
+        queue_realtime_webhook_event(&updated_annotation, &event);
+
+        publish_annotation_event(
+            updated_annotation._id.unwrap(),
+            Some(updated_annotation.user_id),
+            Some(updated_annotation.version),
+            event,
+        );
+    }
+
+    true
+}
+
+/// queues the backend predictions as suggestions, or accepts them as tokens, and returns
+/// the spans that could not be used.
+fn apply_backend(
+    annotation: &mut TextAnnotation,
+    backend: &ModelBackend,
+) -> Result<Vec<DroppedPrediction>, ApiError> {
+    let predictions = request_predictions(backend, annotation.content.as_str());
+
+    if predictions.is_err() {
+        return Err(ApiError::new(StatusCode::BAD_GATEWAY)
+            .set_msg("unable to get predictions from model backend")
+            .set_error(predictions.err().unwrap().description.as_str()));
+    }
+
+    let (predictions, mut dropped) = predictions.unwrap();
+    let content_len = annotation.content.len() as i64;
+
+    let mut valid = vec![];
+
+    for prediction in predictions.into_iter() {
+        match find_invalid_suggestion(&prediction, content_len) {
+            Some(reason) => dropped.push(DroppedPrediction {
+                span: serde_json::to_value(&prediction).unwrap_or_default(),
+                reason: reason.to_string(),
+            }),
+            None => valid.push(prediction),
+        }
+    }
+
+    queue_suggestions(annotation, &valid)?;
+
+    if backend.target == PredictionTarget::Tokens {
+        let queued: Vec<Suggestion> = annotation
+            .suggestions
+            .iter()
+            .filter(|it| it.source == backend.name)
+            .cloned()
+            .collect();
+
+        // spans that cannot become tokens stay as suggestions
+        accept_suggestions(annotation, &queued, backend.user_id)?;
+    }
+
+    Ok(dropped)
+}
+
+fn find_owned_backend(id: String, auth: &UserAuthContext) -> Result<ModelBackend, ApiError> {
+    let object_id = ObjectId::from_str(id.as_str());
+
+    if object_id.is_err() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("unable to convert model backend id to object id"));
+    }
+
+    let backend_result = DB
+        .model_backend_collection
+        .find_one(doc! {"_id": object_id.unwrap()}, None);
+
+    if backend_result.as_ref().is_err() || backend_result.as_ref().unwrap().is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("model backend not found"));
+    }
+
+    let backend = backend_result.unwrap().unwrap();
+
+    if backend.user_id != auth.user_id {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("model backend not found"));
+    }
+
+    Ok(backend)
+}
+
+fn validate_backend(backend: &ModelBackend) -> Result<(), ApiError> {
+    let mut validation: Vec<String> = vec![];
+
+    if backend.name.is_empty() || backend.name.len() > 50 {
+        validation.push("\"name\": value length should be between (1) and (50)".to_string());
+    }
+
+    if !backend.url.starts_with("http://") && !backend.url.starts_with("https://") {
+        validation.push("\"url\": value is not an http url".to_string());
+    }
+
+    if backend.timeout_ms < 1 || backend.timeout_ms > MAX_TIMEOUT_MS {
+        validation.push(format!(
+            "\"timeout_ms\": value should be between (1) and ({})",
+            MAX_TIMEOUT_MS
+        ));
+    }
+
+    if backend.contract.content_field.is_empty()
+        || backend.contract.start_field.is_empty()
+        || backend.contract.end_field.is_empty()
+        || backend.contract.label_field.is_empty()
+    {
+        validation.push("\"contract\": fields names cannot be empty".to_string());
+    }
+
+    if !validation.is_empty() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("failed to validate body")
+            .set_validation(validation));
+    }
+
+    Ok(())
+}
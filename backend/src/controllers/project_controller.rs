@@ -0,0 +1,448 @@
+use std::str::FromStr;
+
+use actix_web::{
+    http::StatusCode,
+    web::{self, Json},
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, DateTime},
+    options::{FindOneAndUpdateOptions, ReturnDocument},
+};
+
+use crate::{
+    controllers::team_controller::{find_managed_team, find_team_role, find_user_teams},
+    database::mongodb::DB,
+    middleware::auth_middleware::UserAuthContext,
+    models::project_model::{
+        AddProjectMemberBody, CreateProjectBody, Project, ProjectMember, ProjectRole,
+        UpdateProjectBody, UpdateProjectMemberBody,
+    },
+    object::{common::Message, error::ApiError},
+    policies::annotation_policy::get_project_access,
+};
+
+pub struct ProjectController;
+
+impl ProjectController {
+    pub fn create(
+        body: Json<CreateProjectBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Project, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to create a project"));
+        }
+
+        let auth = auth.unwrap();
+
+        let mut team_id: Option<ObjectId> = None;
+
+        if let Some(id) = body.team_id.clone() {
+            team_id = find_managed_team(id, &auth)?._id;
+        }
+
+        let project = Project {
+            _id: None,
+            user_id: auth.user_id,
+            name: body.name.trim().to_string(),
+            description: body.description.clone().unwrap_or_default(),
+            team_id,
+            members: vec![],
+            grants: vec![],
+        };
+
+        validate_project(&project)?;
+
+        let result = DB.project_collection.insert_one(project, None);
+
+        if result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to create project")
+                .set_error(result.err().unwrap().to_string().as_str()));
+        }
+
+        let id = result.unwrap().inserted_id;
+
+        let project = DB.project_collection.find_one(doc! {"_id": id}, None);
+
+        if project.as_ref().is_err() || project.as_ref().unwrap().is_none() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to retrieve created project"));
+        }
+
+        Ok(project.unwrap().unwrap())
+    }
+
+    pub fn get(id: String, auth: Option<UserAuthContext>) -> Result<Project, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to get this project"));
+        }
+
+        let user_id = auth.unwrap().user_id;
+
+        let project = find_project(id)?;
+
+        // users the project's documents are shared with can see it too
+        if get_project_access(&project, &find_user_teams(user_id), user_id).is_none() {
+            return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("project not found"));
+        }
+
+        Ok(project)
+    }
+
+    pub fn get_all(auth: Option<UserAuthContext>) -> Result<Vec<Project>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to list your projects"));
+        }
+
+        let user_id = auth.unwrap().user_id;
+
+        let team_ids: Vec<Option<ObjectId>> =
+            find_user_teams(user_id).iter().map(|it| it._id).collect();
+
+        // owned projects and the ones the user is a member of, was granted or
+        // reaches through a team
+        let fetch_result = DB.project_collection.find(
+            doc! {"$or": [
+              {"user_id": user_id},
+              {"members.user_id": user_id},
+              {"grants.user_id": user_id},
+              {"team_id": {"$in": team_ids}},
+            ]},
+            None,
+        );
+
+        if fetch_result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to fetch projects"));
+        }
+
+        let items: Vec<Project> = fetch_result.unwrap().filter_map(|it| it.ok()).collect();
+
+        Ok(items)
+    }
+
+    pub fn update(
+        id: String,
+        body: Json<UpdateProjectBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Project, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to update a project"));
+        }
+
+        let auth = auth.unwrap();
+
+        let mut project = find_owned_project(id, &auth)?;
+
+        if let Some(name) = &body.name {
+            project.name = name.trim().to_string();
+        }
+
+        if let Some(description) = &body.description {
+            project.description = description.clone();
+        }
+
+        if let Some(team_id) = body.team_id.clone() {
+            project.team_id = match team_id.is_empty() {
+                true => None,
+                false => find_managed_team(team_id, &auth)?._id,
+            };
+        }
+
+        validate_project(&project)?;
+
+        let mut update_doc = doc! {"$set": {
+          "name": project.name.clone(),
+          "description": project.description.clone(),
+        }};
+
+        match project.team_id {
+            Some(team_id) => {
+                update_doc
+                    .get_document_mut("$set")
+                    .unwrap()
+                    .insert("team_id", team_id);
+            }
+            None => {
+                update_doc.insert("$unset", doc! {"team_id": ""});
+            }
+        }
+
+        let update_result = DB.project_collection.find_one_and_update(
+            doc! {"_id": project._id.unwrap()},
+            update_doc,
+            FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build(),
+        );
+
+        if update_result.as_ref().is_err() || update_result.as_ref().unwrap().is_none() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to update project"));
+        }
+
+        Ok(update_result.unwrap().unwrap())
+    }
+
+    /// deletes the project, its annotations are kept and moved back to the user's corpus.
+    pub fn delete(id: String, auth: Option<UserAuthContext>) -> Result<Message, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to delete a project"));
+        }
+
+        let project = find_owned_project(id, &auth.unwrap())?;
+
+        let detach_result = DB.text_annotation_collection.update_many(
+            doc! {"project_id": project._id.unwrap()},
+            doc! {
+              "$unset": {"project_id": ""},
+              "$set": {"updated_at": DateTime::now()},
+              "$inc": {"version": 1},
+            },
+            None,
+        );
+
+        if detach_result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to detach project annotations"));
+        }
+
+        let result = DB
+            .project_collection
+            .delete_one(doc! {"_id": project._id.unwrap()}, None);
+
+        if result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to delete project"));
+        }
+
+        Ok(Message::new().set_msg("project deleted successfully"))
+    }
+
+    pub fn add_member(
+        id: String,
+        body: Json<AddProjectMemberBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Project, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to add a project member"));
+        }
+
+        let project = find_managed_project(id, &auth.unwrap())?;
+
+        let user_result = DB.user_collection.find_one(
+            doc! {"$or": [{"username": body.login.clone()}, {"email": body.login.clone()}]},
+            None,
+        );
+
+        if user_result.as_ref().is_err() || user_result.as_ref().unwrap().is_none() {
+            return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("user not found"));
+        }
+
+        let user_id = user_result.unwrap().unwrap()._id.unwrap();
+
+        if get_project_role(&project, user_id).is_some() {
+            return Err(ApiError::new(StatusCode::CONFLICT)
+                .set_msg("user is already a member of the project"));
+        }
+
+        save_members(&project, {
+            let mut members = project.members.clone();
+
+            members.push(ProjectMember {
+                user_id,
+                role: body.role,
+            });
+
+            members
+        })
+    }
+
+    pub fn update_member(
+        params: web::Path<(String, String)>,
+        body: Json<UpdateProjectMemberBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Project, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to update a project member"));
+        }
+
+        let (project_id, user_id) = params.into_inner();
+
+        let project = find_managed_project(project_id, &auth.unwrap())?;
+
+        let user_id = find_member_id(&project, user_id)?;
+
+        let members = project
+            .members
+            .iter()
+            .map(|it| match it.user_id == user_id {
+                true => ProjectMember {
+                    user_id,
+                    role: body.role,
+                },
+                false => it.clone(),
+            })
+            .collect();
+
+        save_members(&project, members)
+    }
+
+    pub fn remove_member(
+        params: web::Path<(String, String)>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Project, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to remove a project member"));
+        }
+
+        let (project_id, user_id) = params.into_inner();
+
+        let project = find_managed_project(project_id, &auth.unwrap())?;
+
+        let user_id = find_member_id(&project, user_id)?;
+
+        let members = project
+            .members
+            .iter()
+            .filter(|it| it.user_id != user_id)
+            .cloned()
+            .collect();
+
+        save_members(&project, members)
+    }
+}
+
+/// the owner of a project acts as its manager.
+pub fn get_project_role(project: &Project, user_id: ObjectId) -> Option<ProjectRole> {
+    if project.user_id == user_id {
+        return Some(ProjectRole::Manager);
+    }
+
+    project
+        .members
+        .iter()
+        .find(|it| it.user_id == user_id)
+        .map(|it| it.role)
+}
+
+/// role of the user in the project, directly or through the project's team.
+pub fn find_member_role(project: &Project, user_id: ObjectId) -> Option<ProjectRole> {
+    let team_role = find_team_role(project.team_id, user_id).and_then(|it| it.project_role());
+
+    get_project_role(project, user_id).max(team_role)
+}
+
+/// finds a project the user owns or is a member of.
+pub fn find_member_project(id: String, auth: &UserAuthContext) -> Result<Project, ApiError> {
+    let project = find_project(id)?;
+
+    if find_member_role(&project, auth.user_id).is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("project not found"));
+    }
+
+    Ok(project)
+}
+
+/// finds a project the user owns or manages.
+pub fn find_managed_project(id: String, auth: &UserAuthContext) -> Result<Project, ApiError> {
+    let project = find_member_project(id, auth)?;
+
+    if find_member_role(&project, auth.user_id) != Some(ProjectRole::Manager) {
+        return Err(ApiError::new(StatusCode::FORBIDDEN)
+            .set_msg("only a project manager can do this action"));
+    }
+
+    Ok(project)
+}
+
+pub fn find_owned_project(id: String, auth: &UserAuthContext) -> Result<Project, ApiError> {
+    let project = find_project(id)?;
+
+    if project.user_id != auth.user_id {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("project not found"));
+    }
+
+    Ok(project)
+}
+
+pub fn find_project(id: String) -> Result<Project, ApiError> {
+    let object_id = ObjectId::from_str(id.as_str());
+
+    if object_id.is_err() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("unable to convert project id to object id"));
+    }
+
+    let project_result = DB
+        .project_collection
+        .find_one(doc! {"_id": object_id.unwrap()}, None);
+
+    if project_result.as_ref().is_err() || project_result.as_ref().unwrap().is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("project not found"));
+    }
+
+    Ok(project_result.unwrap().unwrap())
+}
+
+fn find_member_id(project: &Project, user_id: String) -> Result<ObjectId, ApiError> {
+    let user_id = ObjectId::from_str(user_id.as_str());
+
+    if user_id.is_err() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("unable to convert user id to object id"));
+    }
+
+    let user_id = user_id.unwrap();
+
+    if !project.members.iter().any(|it| it.user_id == user_id) {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("project member not found"));
+    }
+
+    Ok(user_id)
+}
+
+fn save_members(project: &Project, members: Vec<ProjectMember>) -> Result<Project, ApiError> {
+    let update_result = DB.project_collection.find_one_and_update(
+        doc! {"_id": project._id.unwrap()},
+        doc! {"$set": {"members": to_bson(&members).unwrap()}},
+        FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build(),
+    );
+
+    if update_result.as_ref().is_err() || update_result.as_ref().unwrap().is_none() {
+        return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .set_msg("unable to update project members"));
+    }
+
+    Ok(update_result.unwrap().unwrap())
+}
+
+fn validate_project(project: &Project) -> Result<(), ApiError> {
+    let mut validation: Vec<String> = vec![];
+
+    if project.name.is_empty() || project.name.len() > 50 {
+        validation.push("\"name\": value length should be between (1) and (50)".to_string());
+    }
+
+    if project.description.len() > 500 {
+        validation.push("\"description\": value exceeds max length (500)".to_string());
+    }
+
+    if !validation.is_empty() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("failed to validate body")
+            .set_validation(validation));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,330 @@
+use std::str::FromStr;
+
+use actix_web::{
+    http::StatusCode,
+    web::{self, Json},
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId, DateTime},
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+};
+
+use crate::{
+    controllers::{
+        project_controller::{find_managed_project, find_member_project, find_member_role},
+        team_controller::find_user_teams,
+        text_annotation_controller::{
+            ensure_version, find_version_conflict, get_role_view, get_token_layer,
+        },
+    },
+    database::{mongodb::DB, redis::CACHE_DB},
+    helpers::{
+        lease_helpers::{
+            acquire_lease, get_layer_lease_holders, get_lease_holder, get_lease_ttl,
+            get_user_lease, release_lease,
+        },
+        realtime_helpers::publish_annotation_event,
+    },
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        queue_model::{AssignAnnotationBody, QueueItem},
+        realtime_model::RealtimeEvent,
+        text_annotation_model::{AnnotationRole, TextAnnotation},
+    },
+    object::error::ApiError,
+    policies::annotation_policy::{get_project_access, resolve_annotation_role},
+};
+
+pub struct QueueController;
+
+impl QueueController {
+    /// hands the next unfinished document of the project to the user, documents
+    /// assigned to them come first, then the unclaimed ones.
+    ///
+    /// annotators writing their own layer lease the document for themselves and only
+    /// skip the documents they finished.
+    pub fn next(
+        project_id: String,
+        auth: Option<UserAuthContext>,
+    ) -> Result<TextAnnotation, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to get the next document"));
+        }
+
+        let auth = auth.unwrap();
+
+        let project = find_member_project(project_id, &auth)?;
+        let project_id = project._id.unwrap();
+
+        let teams = find_user_teams(auth.user_id);
+        let project_role = match project.user_id == auth.user_id {
+            true => AnnotationRole::Owner,
+            false => {
+                get_project_access(&project, &teams, auth.user_id).unwrap_or(AnnotationRole::Viewer)
+            }
+        };
+        let layer = get_token_layer(project_role, auth.user_id);
+
+        let mut cnx = get_cache_connection()?;
+
+        let mut candidates = find_unfinished(project_id, layer)?;
+
+        // the document already claimed by the user is handed back
+        let current = get_user_lease(&mut cnx, project_id, auth.user_id).map_err(lease_error)?;
+
+        candidates.sort_by_key(|it| {
+            (
+                it._id != current,
+                it.assigned_to != Some(auth.user_id),
+                it._id,
+            )
+        });
+
+        for annotation in candidates {
+            if annotation.assigned_to.is_some_and(|it| it != auth.user_id) {
+                continue;
+            }
+
+            let acquired = acquire_lease(
+                &mut cnx,
+                project_id,
+                annotation._id.unwrap(),
+                auth.user_id,
+                layer,
+            )
+            .map_err(lease_error)?;
+
+            if acquired {
+                // annotators only get their own layer
+                let role =
+                    resolve_annotation_role(&annotation, Some(&project), &teams, auth.user_id)
+                        .unwrap_or(AnnotationRole::Viewer);
+
+                return Ok(get_role_view(annotation, role, auth.user_id));
+            }
+        }
+
+        Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("no document is left in the queue"))
+    }
+
+    /// lists the unfinished documents of the project with their assignee and lease.
+    pub fn get(
+        project_id: String,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Vec<QueueItem>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to see the queue"));
+        }
+
+        let project = find_managed_project(project_id, &auth.unwrap())?;
+
+        let mut cnx = get_cache_connection()?;
+
+        let mut items: Vec<QueueItem> = vec![];
+
+        for annotation in find_unfinished(project._id.unwrap(), None)? {
+            let annotation_id = annotation._id.unwrap();
+
+            let leased_by = get_lease_holder(&mut cnx, annotation_id, None).map_err(lease_error)?;
+
+            let lease_ttl = match leased_by {
+                Some(_) => Some(get_lease_ttl(&mut cnx, annotation_id).map_err(lease_error)?),
+                None => None,
+            };
+
+            items.push(QueueItem {
+                annotation_id,
+                title: annotation.title,
+                status: annotation.status,
+                assigned_to: annotation.assigned_to,
+                leased_by,
+                lease_ttl,
+                annotated_by: get_layer_lease_holders(&mut cnx, annotation_id)
+                    .map_err(lease_error)?,
+                completed_by: annotation.completed_by,
+            });
+        }
+
+        Ok(items)
+    }
+
+    /// assigns the document to a member of the project, its current lease is released.
+    pub fn assign(
+        params: web::Path<(String, String)>,
+        body: Json<AssignAnnotationBody>,
+        version: Option<i64>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<TextAnnotation, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to assign a document"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (project_id, annotation_id) = params.into_inner();
+
+        let project = find_managed_project(project_id, &auth)?;
+
+        let annotation_id = ObjectId::from_str(annotation_id.as_str());
+
+        if annotation_id.is_err() {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                .set_msg("unable to convert annotation id to object id"));
+        }
+
+        let annotation_id = annotation_id.unwrap();
+
+        let annotation_result = DB.text_annotation_collection.find_one(
+            doc! {"_id": annotation_id, "project_id": project._id.unwrap()},
+            None,
+        );
+
+        if annotation_result.as_ref().is_err() || annotation_result.as_ref().unwrap().is_none() {
+            return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("annotation not found"));
+        }
+
+        let annotation = annotation_result.unwrap().unwrap();
+
+        let teams = find_user_teams(auth.user_id);
+        let role = resolve_annotation_role(&annotation, Some(&project), &teams, auth.user_id)
+            .unwrap_or(AnnotationRole::Viewer);
+
+        ensure_version(&annotation, version, role, auth.user_id)?;
+
+        let update_doc = match body.user_id.clone() {
+            Some(user_id) => {
+                let user_id = ObjectId::from_str(user_id.as_str());
+
+                if user_id.is_err() {
+                    return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                        .set_msg("unable to convert user id to object id"));
+                }
+
+                let user_id = user_id.unwrap();
+
+                if find_member_role(&project, user_id).is_none() {
+                    return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                        .set_msg("documents can only be assigned to project members"));
+                }
+
+                doc! {
+                  "$set": {"assigned_to": user_id, "updated_at": DateTime::now()},
+                  "$inc": {"version": 1},
+                }
+            }
+            None => doc! {
+              "$unset": {"assigned_to": ""},
+              "$set": {"updated_at": DateTime::now()},
+              "$inc": {"version": 1},
+            },
+        };
+
+        let update_result = DB.text_annotation_collection.find_one_and_update(
+            doc! {"_id": annotation_id, "version": annotation.version},
+            update_doc,
+            FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build(),
+        );
+
+        if update_result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to assign document")
+                .set_error(update_result.err().unwrap().to_string().as_str()));
+        }
+
+        let updated_annotation = update_result.unwrap();
+
+        if updated_annotation.is_none() {
+            return Err(find_version_conflict(annotation_id, role, auth.user_id));
+        }
+
+        let updated_annotation = updated_annotation.unwrap();
+
+        publish_annotation_event(
+            annotation_id,
+            Some(auth.user_id),
+            Some(updated_annotation.version),
+            RealtimeEvent::AssigneeUpdated {
+                assigned_to: updated_annotation.assigned_to,
+            },
+        );
+
+        let mut cnx = get_cache_connection()?;
+
+        release_lease(&mut cnx, project._id.unwrap(), annotation_id, None).map_err(lease_error)?;
+
+        Ok(get_role_view(updated_annotation, role, auth.user_id))
+    }
+}
+
+/// releases the lease of a finished document, or of the finished layer, failures are
+/// only logged since the lease expires on its own.
+pub fn release_annotation_lease(annotation: &TextAnnotation, layer: Option<ObjectId>) {
+    if annotation.project_id.is_none() {
+        return;
+    }
+
+    let released = CACHE_DB.client.get_connection().and_then(|mut cnx| {
+        release_lease(
+            &mut cnx,
+            annotation.project_id.unwrap(),
+            annotation._id.unwrap(),
+            layer,
+        )
+    });
+
+    if let Err(err) = released {
+        log::warn!(
+            "unable to release the lease of annotation {}: {}",
+            annotation._id.unwrap(),
+            err
+        );
+    }
+}
+
+/// unfinished documents of the project, without the ones whose `layer` is finished.
+fn find_unfinished(
+    project_id: ObjectId,
+    layer: Option<ObjectId>,
+) -> Result<Vec<TextAnnotation>, ApiError> {
+    let mut filter = doc! {"project_id": project_id, "status": {"$in": ["todo", "in_progress"]}};
+
+    if let Some(layer) = layer {
+        filter.insert("completed_by", doc! {"$ne": layer});
+    }
+
+    let fetch_result = DB
+        .text_annotation_collection
+        .find(filter, FindOptions::builder().sort(doc! {"_id": 1}).build());
+
+    if fetch_result.is_err() {
+        return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .set_msg("unable to fetch the queue documents")
+            .set_error(fetch_result.err().unwrap().to_string().as_str()));
+    }
+
+    let items: Vec<TextAnnotation> = fetch_result.unwrap().filter_map(|it| it.ok()).collect();
+
+    Ok(items)
+}
+
+fn get_cache_connection() -> Result<redis::Connection, ApiError> {
+    let cnx = CACHE_DB.client.get_connection();
+
+    if cnx.is_err() {
+        return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .set_msg("unable to connect to caching db"));
+    }
+
+    Ok(cnx.unwrap())
+}
+
+fn lease_error(err: redis::RedisError) -> ApiError {
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+        .set_msg("unable to manage document leases")
+        .set_error(err.to_string().as_str())
+}
@@ -0,0 +1,171 @@
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse};
+use actix_ws::Message;
+use chrono::Utc;
+use futures::{channel::mpsc, StreamExt};
+use mongodb::bson::oid::ObjectId;
+use redis::Connection;
+
+use crate::{
+    database::redis::CACHE_DB,
+    helpers::realtime_helpers::{
+        can_receive, join_presence, leave_presence, publish_presence, refresh_presence,
+        register_connection, unregister_connection, RealtimeConnection,
+    },
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        realtime_model::{PresenceViewer, RealtimeMessage},
+        text_annotation_model::AnnotationRole,
+    },
+    object::error::ApiError,
+    policies::annotation_policy::authorize_annotation,
+};
+
+enum Incoming {
+    Client(Result<Message, actix_ws::ProtocolError>),
+    Broadcast(RealtimeMessage),
+}
+
+pub struct RealtimeController;
+
+impl RealtimeController {
+    /// opens a websocket receiving the changes made on the annotation and who is viewing it.
+    ///
+    /// clients should send a ping at least every minute to stay in the presence list.
+    /// the access is checked again for every event, the websocket is closed once it is lost.
+    pub fn connect(
+        id: String,
+        req: HttpRequest,
+        body: web::Payload,
+        auth: Option<UserAuthContext>,
+    ) -> Result<HttpResponse, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to follow this annotation"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (annotation, _) = authorize_annotation(id, &auth, AnnotationRole::Viewer)?;
+
+        let ws = actix_ws::handle(&req, body);
+
+        if ws.is_err() {
+            return Err(ApiError::new(StatusCode::BAD_REQUEST)
+                .set_msg("unable to open the websocket")
+                .set_error(ws.err().unwrap().to_string().as_str()));
+        }
+
+        let (response, mut session, msg_stream) = ws.unwrap();
+
+        let annotation_id = annotation._id.unwrap();
+        let (sender, receiver) = mpsc::unbounded::<RealtimeMessage>();
+
+        let viewer = PresenceViewer {
+            connection_id: ObjectId::new(),
+            user_id: auth.user_id,
+            username: auth.user.username.clone(),
+            firstname: auth.user.firstname.clone(),
+            lastname: auth.user.lastname.clone(),
+            connected_at: Utc::now().timestamp_millis(),
+        };
+
+        register_connection(
+            annotation_id,
+            RealtimeConnection {
+                connection_id: viewer.connection_id,
+                sender,
+            },
+        );
+
+        actix_web::rt::spawn(async move {
+            // presence is best effort, the events still flow without the caching db
+            let mut cnx = CACHE_DB.client.get_connection().ok();
+
+            update_presence(cnx.as_mut(), annotation_id, &viewer, true);
+
+            let mut incoming = futures::stream::select(
+                msg_stream.map(Incoming::Client),
+                receiver.map(Incoming::Broadcast),
+            );
+
+            while let Some(item) = incoming.next().await {
+                match item {
+                    Incoming::Broadcast(message) => {
+                        // grants, teams and projects may have changed since the connection
+                        let access = authorize_annotation(
+                            annotation_id.to_hex(),
+                            &auth,
+                            AnnotationRole::Viewer,
+                        );
+
+                        let Ok((_, role)) = access else {
+                            break;
+                        };
+
+                        if !can_receive(&message, role, auth.user_id) {
+                            continue;
+                        }
+
+                        let payload = serde_json::to_string(&message).unwrap();
+
+                        if session.text(payload).await.is_err() {
+                            break;
+                        }
+                    }
+                    Incoming::Client(Ok(Message::Ping(bytes))) => {
+                        update_presence(cnx.as_mut(), annotation_id, &viewer, false);
+
+                        if session.pong(&bytes).await.is_err() {
+                            break;
+                        }
+                    }
+                    Incoming::Client(Ok(Message::Close(_))) | Incoming::Client(Err(_)) => break,
+                    Incoming::Client(Ok(_)) => {
+                        update_presence(cnx.as_mut(), annotation_id, &viewer, false);
+                    }
+                }
+            }
+
+            unregister_connection(annotation_id, viewer.connection_id);
+
+            if let Some(cnx) = cnx.as_mut() {
+                let left = leave_presence(cnx, annotation_id, viewer.connection_id)
+                    .and_then(|_| publish_presence(cnx, annotation_id));
+
+                if let Err(err) = left {
+                    log::warn!("unable to update the annotation viewers: {}", err);
+                }
+            }
+
+            let _ = session.close(None).await;
+        });
+
+        Ok(response)
+    }
+}
+
+/// keeps the viewer listed, the others are told when it joins or comes back after expiring.
+fn update_presence(
+    cnx: Option<&mut Connection>,
+    annotation_id: ObjectId,
+    viewer: &PresenceViewer,
+    joined: bool,
+) {
+    let Some(cnx) = cnx else {
+        return;
+    };
+
+    let result = match joined {
+        true => Ok(false),
+        false => refresh_presence(cnx, annotation_id, viewer.connection_id),
+    }
+    .and_then(|refreshed| match refreshed {
+        true => Ok(()),
+        false => join_presence(cnx, annotation_id, viewer)
+            .and_then(|_| publish_presence(cnx, annotation_id)),
+    });
+
+    if let Err(err) = result {
+        log::warn!("unable to update the annotation viewers: {}", err);
+    }
+}
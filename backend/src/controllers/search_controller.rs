@@ -0,0 +1,608 @@
+use actix_web::{http::StatusCode, web};
+use mongodb::{
+    bson::{doc, oid::ObjectId, Bson, Document},
+    options::FindOptions,
+};
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+
+use crate::{
+    controllers::project_controller::find_owned_project,
+    database::mongodb::DB,
+    helpers::{
+        annotation_helpers::get_labeled_spans,
+        metrics_helpers::{overlaps, LabeledSpan},
+        text_helpers::{
+            build_snippets, escape_csv_field, find_occurrences, get_search_terms, is_whole_word,
+        },
+    },
+    middleware::auth_middleware::UserAuthContext,
+    models::search_model::{
+        KwicCoverage, KwicLine, KwicQueryParams, KwicResponse, SearchQueryParams, SearchResponse,
+        SearchResult, SpanQueryParams, SpanResult, SpanSearchResponse, TextMatchMode,
+    },
+    object::{common::CsvFile, error::ApiError},
+};
+
+static DEFAULT_COUNT: i64 = 20;
+static MAX_COUNT: i64 = 100;
+static SNIPPET_CONTEXT: usize = 40;
+static MAX_SNIPPETS: usize = 3;
+static DEFAULT_SPAN_CONTEXT: i64 = 30;
+static MAX_SPAN_CONTEXT: i64 = 500;
+static MAX_PATTERN_LENGTH: usize = 200;
+/// compiled size limit of a span text pattern, in bytes
+static MAX_PATTERN_SIZE: usize = 1 << 20;
+
+/// a text search hit, before its snippets are built.
+#[derive(Debug, Deserialize)]
+struct SearchHit {
+    _id: ObjectId,
+    title: String,
+    content: String,
+    #[serde(default)]
+    project_id: Option<ObjectId>,
+    score: f64,
+    tokens: i64,
+}
+
+pub struct SearchController;
+
+impl SearchController {
+    /// ranks the user's documents by their text score, the filters are applied on top
+    /// of the text search.
+    pub fn search(
+        query_params: web::Query<SearchQueryParams>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<SearchResponse, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to search annotations"));
+        }
+
+        let auth = auth.unwrap();
+
+        let terms = get_search_terms(query_params.q.as_str());
+
+        if terms.is_empty() {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                .set_msg("search query cannot be empty"));
+        }
+
+        let (skip, count) = get_pagination(query_params.page, query_params.count)?;
+
+        // the text search has to be the first condition of the match
+        let mut filter = doc! {
+            "$text": {"$search": query_params.q.clone()},
+            "user_id": auth.user_id,
+        };
+
+        if let Some(project_id) = query_params.project_id.clone() {
+            let project = find_owned_project(project_id, &auth)?;
+
+            filter.insert("project_id", project._id);
+        }
+
+        if let Some(has_tokens) = query_params.has_tokens {
+            filter.insert("tokens.0", doc! {"$exists": has_tokens});
+        }
+
+        if let Some(label) = query_params.label.clone() {
+            filter.insert("$expr", has_label_expr(label.as_str()));
+        }
+
+        let total = DB
+            .text_annotation_collection
+            .count_documents(filter.clone(), None);
+
+        if total.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to search annotations")
+                .set_error(total.err().unwrap().to_string().as_str()));
+        }
+
+        let cursor = DB.text_annotation_collection.aggregate(
+            vec![
+                doc! {"$match": filter},
+                doc! {"$addFields": {"score": {"$meta": "textScore"}}},
+                doc! {"$sort": {"score": -1, "_id": 1}},
+                doc! {"$skip": skip},
+                doc! {"$limit": count},
+                doc! {"$project": {
+                  "title": 1,
+                  "content": 1,
+                  "project_id": 1,
+                  "score": 1,
+                  "tokens": {"$size": "$tokens"},
+                }},
+            ],
+            None,
+        );
+
+        if cursor.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to search annotations")
+                .set_error(cursor.err().unwrap().to_string().as_str()));
+        }
+
+        let results: Vec<SearchResult> = cursor
+            .unwrap()
+            .filter_map(|it| it.ok())
+            .filter_map(|it| mongodb::bson::from_document::<SearchHit>(it).ok())
+            .map(|hit| SearchResult {
+                snippets: build_snippets(
+                    hit.content.as_str(),
+                    &terms,
+                    SNIPPET_CONTEXT,
+                    MAX_SNIPPETS,
+                ),
+                _id: hit._id,
+                title: hit.title,
+                project_id: hit.project_id,
+                score: hit.score,
+                tokens: hit.tokens,
+            })
+            .collect();
+
+        Ok(SearchResponse {
+            total: total.unwrap(),
+            results,
+        })
+    }
+
+    pub fn kwic(
+        query_params: web::Query<KwicQueryParams>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<KwicResponse, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to get a concordance"));
+        }
+
+        let (skip, count) = get_pagination(query_params.page, query_params.count)?;
+
+        let lines = find_kwic_lines(&query_params, &auth.unwrap())?;
+
+        Ok(KwicResponse {
+            total: lines.len() as u64,
+            results: lines
+                .into_iter()
+                .skip(skip as usize)
+                .take(count as usize)
+                .collect(),
+        })
+    }
+
+    /// exports every occurrence of the concordance as csv.
+    pub fn export_kwic(
+        query_params: web::Query<KwicQueryParams>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<CsvFile, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to export a concordance"));
+        }
+
+        let lines = find_kwic_lines(&query_params, &auth.unwrap())?;
+
+        let mut content =
+            "annotation_id,title,start,end,left,keyword,right,coverage,label\n".to_string();
+
+        for line in lines.iter() {
+            let fields = [
+                line.annotation_id.to_hex(),
+                line.title.clone(),
+                line.start.to_string(),
+                line.end.to_string(),
+                line.left.clone(),
+                line.keyword.clone(),
+                line.right.clone(),
+                line.coverage.as_str().to_string(),
+                line.label.clone().unwrap_or_default(),
+            ];
+
+            let fields: Vec<String> = fields.iter().map(|it| escape_csv_field(it)).collect();
+
+            content.push_str(fields.join(",").as_str());
+            content.push('\n');
+        }
+
+        Ok(CsvFile {
+            filename: "concordance.csv".to_string(),
+            content,
+        })
+    }
+
+    /// lists the tokens of the user's documents matching the filters, in document order.
+    pub fn search_spans(
+        query_params: web::Query<SpanQueryParams>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<SpanSearchResponse, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to search spans"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (skip, count) = get_pagination(query_params.page, query_params.count)?;
+        let context = query_params.context.unwrap_or(DEFAULT_SPAN_CONTEXT);
+
+        if !(0..=MAX_SPAN_CONTEXT).contains(&context) {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY).set_msg(
+                format!("context should be between (0) and ({})", MAX_SPAN_CONTEXT).as_str(),
+            ));
+        }
+
+        let mut filter = doc! {"user_id": auth.user_id, "tokens.0": {"$exists": true}};
+
+        if let Some(project_id) = query_params.project_id.clone() {
+            let project = find_owned_project(project_id, &auth)?;
+
+            filter.insert("project_id", project._id);
+        }
+
+        let mut span_filter = doc! {};
+
+        if let Some(label) = query_params.label.clone() {
+            span_filter.insert("label", label);
+        }
+
+        let mut text_regex: Option<Regex> = None;
+
+        if let Some(text) = query_params.text.clone() {
+            let mode = query_params.mode.unwrap_or(TextMatchMode::Exact);
+            let ignore_case = query_params.ignore_case.unwrap_or(false);
+            let options = if ignore_case { "i" } else { "" };
+
+            match mode {
+                // user patterns are matched here, mongodb would run them with a
+                // backtracking engine
+                TextMatchMode::Regex => {
+                    text_regex = Some(get_text_regex(text.as_str(), ignore_case)?);
+                }
+                _ => {
+                    span_filter.insert(
+                        "text",
+                        doc! {"$regex": get_text_pattern(text.as_str(), mode), "$options": options},
+                    );
+                }
+            }
+        }
+
+        let mut length_filter = doc! {};
+
+        if let Some(min_length) = query_params.min_length {
+            length_filter.insert("$gte", min_length);
+        }
+
+        if let Some(max_length) = query_params.max_length {
+            length_filter.insert("$lte", max_length);
+        }
+
+        if !length_filter.is_empty() {
+            span_filter.insert("length", length_filter);
+        }
+
+        let span_projection = doc! {"$project": {
+          "title": 1,
+          "content": 1,
+          "token_id": "$tokens._id",
+          "start": "$tokens.start",
+          "end": "$tokens.end",
+          "label": {"$arrayElemAt": [
+            {"$map": {
+              "input": {"$filter": {
+                "input": "$labels",
+                "as": "label",
+                "cond": {"$eq": ["$$label._id", "$tokens.label"]}
+              }},
+              "as": "label",
+              "in": "$$label.name"
+            }},
+            0
+          ]},
+          "text": {"$substrCP": [
+            "$content",
+            "$tokens.start",
+            {"$add": [{"$subtract": ["$tokens.end", "$tokens.start"]}, 1]}
+          ]},
+          "length": {"$add": [{"$subtract": ["$tokens.end", "$tokens.start"]}, 1]},
+        }};
+
+        if let Some(regex) = text_regex {
+            let ids = find_matching_spans(&filter, &span_projection, &span_filter, &regex)?;
+
+            span_filter.insert("token_id", doc! {"$in": ids});
+        }
+
+        let context_start = doc! {"$max": [{"$subtract": ["$start", context]}, 0]};
+
+        let cursor = DB.text_annotation_collection.aggregate(
+            vec![
+                doc! {"$match": filter},
+                doc! {"$unwind": "$tokens"},
+                span_projection,
+                doc! {"$match": span_filter},
+                doc! {"$sort": {"_id": 1, "start": 1}},
+                doc! {"$facet": {
+                  "total": [{"$count": "count"}],
+                  "results": [
+                    {"$skip": skip},
+                    {"$limit": count},
+                    {"$project": {
+                      "_id": 0,
+                      "annotation_id": "$_id",
+                      "title": 1,
+                      "token_id": 1,
+                      "start": 1,
+                      "end": 1,
+                      "label": 1,
+                      "text": 1,
+                      "before": {"$substrCP": [
+                        "$content",
+                        context_start.clone(),
+                        {"$subtract": ["$start", context_start]}
+                      ]},
+                      "after": {"$substrCP": ["$content", {"$add": ["$end", 1]}, context]},
+                    }},
+                  ],
+                }},
+            ],
+            None,
+        );
+
+        if cursor.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to search spans")
+                .set_error(cursor.err().unwrap().to_string().as_str()));
+        }
+
+        let facet = cursor
+            .unwrap()
+            .filter_map(|it| it.ok())
+            .next()
+            .unwrap_or_default();
+
+        let total = facet
+            .get_array("total")
+            .ok()
+            .and_then(|it| it.first())
+            .and_then(|it| it.as_document())
+            .and_then(|it| match it.get("count") {
+                Some(Bson::Int32(v)) => Some(*v as u64),
+                Some(Bson::Int64(v)) => Some(*v as u64),
+                _ => None,
+            })
+            .unwrap_or(0);
+
+        let results: Vec<SpanResult> = facet
+            .get_array("results")
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|it| it.as_document())
+                    .filter_map(|it| mongodb::bson::from_document(it.clone()).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(SpanSearchResponse { total, results })
+    }
+}
+
+/// finds every occurrence of the keyword in the user's documents, in document order.
+fn find_kwic_lines(
+    query_params: &KwicQueryParams,
+    auth: &UserAuthContext,
+) -> Result<Vec<KwicLine>, ApiError> {
+    let keyword: Vec<char> = query_params.q.chars().collect();
+
+    if query_params.q.trim().is_empty() {
+        return Err(
+            ApiError::new(StatusCode::UNPROCESSABLE_ENTITY).set_msg("keyword cannot be empty")
+        );
+    }
+
+    let context = query_params.context.unwrap_or(DEFAULT_SPAN_CONTEXT);
+
+    if !(0..=MAX_SPAN_CONTEXT).contains(&context) {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY).set_msg(
+            format!("context should be between (0) and ({})", MAX_SPAN_CONTEXT).as_str(),
+        ));
+    }
+
+    let context = context as usize;
+    let ignore_case = query_params.ignore_case.unwrap_or(true);
+    let whole_word = query_params.whole_word.unwrap_or(false);
+
+    // only the documents containing the keyword are loaded
+    let mut filter = doc! {
+        "user_id": auth.user_id,
+        "content": {
+            "$regex": regex::escape(query_params.q.as_str()),
+            "$options": if ignore_case { "i" } else { "" },
+        },
+    };
+
+    if let Some(project_id) = query_params.project_id.clone() {
+        let project = find_owned_project(project_id, auth)?;
+
+        filter.insert("project_id", project._id);
+    }
+
+    let fetch_result = DB
+        .text_annotation_collection
+        .find(filter, FindOptions::builder().sort(doc! {"_id": 1}).build());
+
+    if fetch_result.is_err() {
+        return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .set_msg("unable to fetch annotations")
+            .set_error(fetch_result.err().unwrap().to_string().as_str()));
+    }
+
+    let mut lines: Vec<KwicLine> = vec![];
+
+    for annotation in fetch_result.unwrap().filter_map(|it| it.ok()) {
+        let content: Vec<char> = annotation.content.chars().collect();
+        let spans = get_labeled_spans(&annotation);
+
+        for start in find_occurrences(&content, &keyword, ignore_case) {
+            let end = start + keyword.len();
+
+            if whole_word && !is_whole_word(&content, start, end) {
+                continue;
+            }
+
+            let occurrence = LabeledSpan {
+                start: start as i64,
+                end: end as i64 - 1,
+                label: "".to_string(),
+            };
+
+            let exact = spans
+                .iter()
+                .find(|it| it.start == occurrence.start && it.end == occurrence.end);
+            let partial = spans.iter().find(|it| overlaps(it, &occurrence));
+
+            let (coverage, label) = match (exact, partial) {
+                (Some(span), _) => (KwicCoverage::Exact, Some(span.label.clone())),
+                (None, Some(span)) => (KwicCoverage::Partial, Some(span.label.clone())),
+                _ => (KwicCoverage::None, None),
+            };
+
+            lines.push(KwicLine {
+                annotation_id: annotation._id.unwrap(),
+                title: annotation.title.clone(),
+                start: occurrence.start,
+                end: occurrence.end,
+                left: content[start.saturating_sub(context)..start]
+                    .iter()
+                    .collect(),
+                keyword: content[start..end].iter().collect(),
+                right: content[end..(end + context).min(content.len())]
+                    .iter()
+                    .collect(),
+                coverage,
+                label,
+            });
+        }
+    }
+
+    Ok(lines)
+}
+
+/// returns the number of items to skip and the page size.
+fn get_pagination(page: Option<i64>, count: Option<i64>) -> Result<(i64, i64), ApiError> {
+    let page = page.unwrap_or(1);
+    let count = count.unwrap_or(DEFAULT_COUNT);
+
+    if page < 1 {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("page should be greater than (0)"));
+    }
+
+    if !(1..=MAX_COUNT).contains(&count) {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg(format!("count should be between (1) and ({})", MAX_COUNT).as_str()));
+    }
+
+    let skip = count.checked_mul(page - 1);
+
+    if skip.is_none() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY).set_msg("page is too large"));
+    }
+
+    Ok((skip.unwrap(), count))
+}
+
+/// builds the mongodb regex matching a span text literally, from its start.
+fn get_text_pattern(text: &str, mode: TextMatchMode) -> String {
+    match mode {
+        TextMatchMode::Prefix => format!("^{}", regex::escape(text)),
+        _ => format!("^{}$", regex::escape(text)),
+    }
+}
+
+/// compiles a user pattern, the regex crate runs in linear time so a pattern cannot
+/// stall the search.
+fn get_text_regex(text: &str, ignore_case: bool) -> Result<Regex, ApiError> {
+    if text.chars().count() > MAX_PATTERN_LENGTH {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("invalid text pattern")
+            .set_validation(vec![format!(
+                "\"text\": pattern cannot be longer than ({}) characters",
+                MAX_PATTERN_LENGTH
+            )]));
+    }
+
+    let regex = RegexBuilder::new(text)
+        .case_insensitive(ignore_case)
+        .size_limit(MAX_PATTERN_SIZE)
+        .build();
+
+    if let Err(err) = regex {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("invalid text pattern")
+            .set_validation(vec![format!("\"text\": {}", err)]));
+    }
+
+    Ok(regex.unwrap())
+}
+
+/// ids of the tokens matching the other filters whose text matches the pattern.
+fn find_matching_spans(
+    filter: &Document,
+    span_projection: &Document,
+    span_filter: &Document,
+    regex: &Regex,
+) -> Result<Vec<ObjectId>, ApiError> {
+    let cursor = DB.text_annotation_collection.aggregate(
+        vec![
+            doc! {"$match": filter.clone()},
+            doc! {"$unwind": "$tokens"},
+            span_projection.clone(),
+            doc! {"$match": span_filter.clone()},
+            doc! {"$project": {"_id": 0, "token_id": 1, "text": 1}},
+        ],
+        None,
+    );
+
+    if cursor.is_err() {
+        return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .set_msg("unable to search spans")
+            .set_error(cursor.err().unwrap().to_string().as_str()));
+    }
+
+    let ids = cursor
+        .unwrap()
+        .filter_map(|it| it.ok())
+        .filter(|it| regex.is_match(it.get_str("text").unwrap_or_default()))
+        .filter_map(|it| it.get_object_id("token_id").ok())
+        .collect();
+
+    Ok(ids)
+}
+
+/// matches documents having at least one token whose label is named `label`.
+pub fn has_label_expr(label: &str) -> Document {
+    doc! {"$gt": [
+      {"$size": {"$filter": {
+        "input": "$tokens",
+        "as": "token",
+        "cond": {"$in": [
+          "$$token.label",
+          {"$map": {
+            "input": {"$filter": {
+              "input": "$labels",
+              "as": "label",
+              "cond": {"$eq": ["$$label.name", label]}
+            }},
+            "as": "label",
+            "in": "$$label._id"
+          }}
+        ]}
+      }}},
+      0
+    ]}
+}
@@ -0,0 +1,191 @@
+use std::str::FromStr;
+
+use actix_web::{
+    http::StatusCode,
+    web::{self, Json},
+};
+use chrono::Utc;
+use mongodb::bson::{doc, oid::ObjectId};
+
+use crate::{
+    database::{mongodb::DB, redis::CACHE_DB},
+    helpers::share_helpers::{
+        create_share_token, delete_share_link, get_annotation_share_links, get_share_link,
+        get_share_token_link, store_share_link, DEFAULT_SHARE_LINK_TTL_SECONDS,
+        MAX_SHARE_LINK_TTL_SECONDS,
+    },
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        share_model::{CreateShareLinkBody, ShareLink, SharedAnnotation, SharedToken},
+        text_annotation_model::AnnotationRole,
+    },
+    object::{common::Message, error::ApiError},
+    policies::annotation_policy::authorize_annotation,
+};
+
+pub struct ShareController;
+
+impl ShareController {
+    pub fn create(
+        annotation_id: String,
+        body: Json<CreateShareLinkBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<ShareLink, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to share this annotation"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (annotation, _) = authorize_annotation(annotation_id, &auth, AnnotationRole::Owner)?;
+
+        let expires_in = body.expires_in.unwrap_or(DEFAULT_SHARE_LINK_TTL_SECONDS);
+
+        if !(1..=MAX_SHARE_LINK_TTL_SECONDS).contains(&expires_in) {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY).set_msg(
+                format!(
+                    "expires_in should be between (1) and ({}) seconds",
+                    MAX_SHARE_LINK_TTL_SECONDS
+                )
+                .as_str(),
+            ));
+        }
+
+        let link_id = ObjectId::new();
+        let created_at = Utc::now().timestamp();
+        let expires_at = created_at + expires_in;
+
+        let token = create_share_token(link_id, expires_at);
+
+        if token.is_none() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to sign the share link"));
+        }
+
+        let link = ShareLink {
+            _id: link_id,
+            annotation_id: annotation._id.unwrap(),
+            created_by: auth.user_id,
+            token: token.unwrap(),
+            created_at,
+            expires_at,
+        };
+
+        let mut cnx = get_cache_connection()?;
+
+        store_share_link(&mut cnx, &link).map_err(share_link_error)?;
+
+        Ok(link)
+    }
+
+    /// lists the links of the annotation that did not expire yet.
+    pub fn get_all(
+        annotation_id: String,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Vec<ShareLink>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to list the share links"));
+        }
+
+        let (annotation, _) =
+            authorize_annotation(annotation_id, &auth.unwrap(), AnnotationRole::Owner)?;
+
+        let mut cnx = get_cache_connection()?;
+
+        get_annotation_share_links(&mut cnx, annotation._id.unwrap()).map_err(share_link_error)
+    }
+
+    pub fn revoke(
+        params: web::Path<(String, String)>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Message, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to revoke a share link"));
+        }
+
+        let (annotation_id, link_id) = params.into_inner();
+
+        let (annotation, _) =
+            authorize_annotation(annotation_id, &auth.unwrap(), AnnotationRole::Owner)?;
+
+        let link_id = ObjectId::from_str(link_id.as_str());
+
+        if link_id.is_err() {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                .set_msg("unable to convert share link id to object id"));
+        }
+
+        let link_id = link_id.unwrap();
+
+        let mut cnx = get_cache_connection()?;
+
+        let link = get_share_link(&mut cnx, link_id).map_err(share_link_error)?;
+
+        if link.is_none() || link.unwrap().annotation_id != annotation._id.unwrap() {
+            return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("share link not found"));
+        }
+
+        delete_share_link(&mut cnx, annotation._id.unwrap(), link_id).map_err(share_link_error)?;
+
+        Ok(Message::new().set_msg("share link revoked successfully"))
+    }
+
+    /// read-only view of a shared annotation, no account is needed.
+    pub fn get_shared(token: String) -> Result<SharedAnnotation, ApiError> {
+        let not_found =
+            || ApiError::new(StatusCode::NOT_FOUND).set_msg("share link not found or expired");
+
+        // the signature and the expiration are checked with the token
+        let link_id = get_share_token_link(token).ok_or_else(not_found)?;
+
+        let mut cnx = get_cache_connection()?;
+
+        // revoked links are gone from the cache db
+        let link = get_share_link(&mut cnx, link_id)
+            .map_err(share_link_error)?
+            .ok_or_else(not_found)?;
+
+        let annotation_result = DB
+            .text_annotation_collection
+            .find_one(doc! {"_id": link.annotation_id}, None);
+
+        if annotation_result.as_ref().is_err() || annotation_result.as_ref().unwrap().is_none() {
+            return Err(not_found());
+        }
+
+        let annotation = annotation_result.unwrap().unwrap();
+
+        Ok(SharedAnnotation {
+            title: annotation.title,
+            content: annotation.content,
+            labels: annotation.labels,
+            // only the document's tokens, not the annotators' layers
+            tokens: annotation
+                .tokens
+                .into_iter()
+                .map(SharedToken::from)
+                .collect(),
+            expires_at: link.expires_at,
+        })
+    }
+}
+
+fn get_cache_connection() -> Result<redis::Connection, ApiError> {
+    let cnx = CACHE_DB.client.get_connection();
+
+    if cnx.is_err() {
+        return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .set_msg("unable to connect to caching db"));
+    }
+
+    Ok(cnx.unwrap())
+}
+
+fn share_link_error(err: redis::RedisError) -> ApiError {
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+        .set_msg("unable to manage share links")
+        .set_error(err.to_string().as_str())
+}
@@ -0,0 +1,271 @@
+use actix_web::{http::StatusCode, web};
+use mongodb::bson::{doc, Bson, Document};
+
+use crate::{
+    controllers::project_controller::find_owned_project,
+    database::mongodb::DB,
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        stats_model::{AnnotationStats, LabelStats, LengthBucket, SpanTextCount, StatsQueryParams},
+        text_annotation_model::AnnotationRole,
+    },
+    object::error::ApiError,
+    policies::annotation_policy::authorize_annotation,
+};
+
+static DEFAULT_TOP: i64 = 10;
+static MAX_TOP: i64 = 100;
+static LENGTH_BOUNDARIES: [i64; 10] = [1, 2, 3, 4, 5, 6, 11, 21, 51, 101];
+
+pub struct StatsController;
+
+impl StatsController {
+    pub fn get_annotation_stats(
+        id: String,
+        query_params: web::Query<StatsQueryParams>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<AnnotationStats, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to get annotation statistics"));
+        }
+
+        let (annotation, role) = authorize_annotation(id, &auth.unwrap(), AnnotationRole::Viewer)?;
+
+        // the statistics are computed over the document's tokens, annotators only see
+        // their own layer
+        if role == AnnotationRole::Annotator {
+            return Err(ApiError::new(StatusCode::FORBIDDEN)
+                .set_msg("annotators cannot see the statistics of the document's tokens"));
+        }
+
+        compute_stats(
+            doc! {"_id": annotation._id.unwrap()},
+            get_top(&query_params)?,
+        )
+    }
+
+    pub fn get_corpus_stats(
+        query_params: web::Query<StatsQueryParams>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<AnnotationStats, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to get corpus statistics"));
+        }
+
+        compute_stats(
+            doc! {"user_id": auth.unwrap().user_id},
+            get_top(&query_params)?,
+        )
+    }
+
+    pub fn get_project_stats(
+        id: String,
+        query_params: web::Query<StatsQueryParams>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<AnnotationStats, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to get project statistics"));
+        }
+
+        let project = find_owned_project(id, &auth.unwrap())?;
+
+        compute_stats(
+            doc! {"project_id": project._id.unwrap()},
+            get_top(&query_params)?,
+        )
+    }
+}
+
+fn get_top(query_params: &StatsQueryParams) -> Result<i64, ApiError> {
+    let top = query_params.top.unwrap_or(DEFAULT_TOP);
+
+    if !(1..=MAX_TOP).contains(&top) {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg(format!("top should be between (1) and ({})", MAX_TOP).as_str()));
+    }
+
+    Ok(top)
+}
+
+/// every statistic is computed by mongodb, only the aggregated results are loaded.
+fn compute_stats(filter: Document, top: i64) -> Result<AnnotationStats, ApiError> {
+    let token_length = doc! {"$add": [{"$subtract": ["$tokens.end", "$tokens.start"]}, 1]};
+
+    let token_label = doc! {"$arrayElemAt": [
+      {"$filter": {
+        "input": "$labels",
+        "as": "label",
+        "cond": {"$eq": ["$$label._id", "$tokens.label"]}
+      }},
+      0
+    ]};
+
+    // documents, spans and coverage
+    let totals = run_pipeline(vec![
+        doc! {"$match": filter.clone()},
+        doc! {"$group": {
+          "_id": Bson::Null,
+          "documents": {"$sum": 1},
+          "unlabeled_documents": {"$sum": {"$cond": [{"$eq": [{"$size": "$tokens"}, 0]}, 1, 0]}},
+          "spans": {"$sum": {"$size": "$tokens"}},
+          "characters": {"$sum": {"$strLenCP": "$content"}},
+          "covered_characters": {"$sum": {"$sum": {"$map": {
+            "input": "$tokens",
+            "as": "token",
+            "in": {"$add": [{"$subtract": ["$$token.end", "$$token.start"]}, 1]}
+          }}}},
+        }},
+    ])?;
+
+    let totals = totals.first().cloned().unwrap_or_default();
+
+    // spans per label
+    let labels = run_pipeline(vec![
+        doc! {"$match": filter.clone()},
+        doc! {"$unwind": "$tokens"},
+        doc! {"$project": {"label": token_label.clone(), "length": token_length.clone()}},
+        doc! {"$group": {
+          "_id": "$label.name",
+          "spans": {"$sum": 1},
+          "covered_characters": {"$sum": "$length"},
+          "average_length": {"$avg": "$length"},
+        }},
+        doc! {"$sort": {"spans": -1, "_id": 1}},
+    ])?;
+
+    // most frequent texts per label
+    let texts = run_pipeline(vec![
+        doc! {"$match": filter.clone()},
+        doc! {"$unwind": "$tokens"},
+        doc! {"$project": {
+          "label": token_label,
+          "text": {"$substrCP": ["$content", "$tokens.start", token_length.clone()]},
+        }},
+        doc! {"$group": {"_id": {"label": "$label.name", "text": "$text"}, "count": {"$sum": 1}}},
+        doc! {"$sort": {"count": -1, "_id.text": 1}},
+        doc! {"$group": {
+          "_id": "$_id.label",
+          "texts": {"$push": {"text": "$_id.text", "count": "$count"}},
+        }},
+        doc! {"$project": {"texts": {"$slice": ["$texts", top]}}},
+    ])?;
+
+    // span length histogram
+    let buckets = run_pipeline(vec![
+        doc! {"$match": filter},
+        doc! {"$unwind": "$tokens"},
+        doc! {"$project": {"length": token_length}},
+        doc! {"$bucket": {
+          "groupBy": "$length",
+          "boundaries": LENGTH_BOUNDARIES.to_vec(),
+          "default": "larger",
+          "output": {"count": {"$sum": 1}},
+        }},
+    ])?;
+
+    let characters = get_i64(&totals, "characters");
+    let covered_characters = get_i64(&totals, "covered_characters");
+
+    let coverage = if characters == 0 {
+        0.0
+    } else {
+        covered_characters as f64 * 100.0 / characters as f64
+    };
+
+    let labels: Vec<LabelStats> = labels
+        .iter()
+        .map(|item| {
+            let label = item.get_str("_id").unwrap_or_default().to_string();
+
+            let top_texts: Vec<SpanTextCount> = texts
+                .iter()
+                .find(|it| it.get_str("_id").unwrap_or_default() == label)
+                .and_then(|it| it.get_array("texts").ok())
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|it| it.as_document())
+                        .map(|it| SpanTextCount {
+                            text: it.get_str("text").unwrap_or_default().to_string(),
+                            count: get_i64(it, "count"),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            LabelStats {
+                label,
+                spans: get_i64(item, "spans"),
+                covered_characters: get_i64(item, "covered_characters"),
+                average_length: item.get_f64("average_length").unwrap_or_default(),
+                top_texts,
+            }
+        })
+        .collect();
+
+    let mut length_histogram: Vec<LengthBucket> = LENGTH_BOUNDARIES
+        .windows(2)
+        .map(|bounds| LengthBucket {
+            min: bounds[0],
+            max: Some(bounds[1] - 1),
+            count: buckets
+                .iter()
+                .find(|it| get_i64_bson(it.get("_id")) == Some(bounds[0]))
+                .map(|it| get_i64(it, "count"))
+                .unwrap_or(0),
+        })
+        .collect();
+
+    // spans longer than the last boundary fall in the default bucket
+    length_histogram.push(LengthBucket {
+        min: LENGTH_BOUNDARIES[LENGTH_BOUNDARIES.len() - 1],
+        max: None,
+        count: buckets
+            .iter()
+            .find(|it| it.get_str("_id").is_ok())
+            .map(|it| get_i64(it, "count"))
+            .unwrap_or(0),
+    });
+
+    Ok(AnnotationStats {
+        documents: get_i64(&totals, "documents"),
+        unlabeled_documents: get_i64(&totals, "unlabeled_documents"),
+        spans: get_i64(&totals, "spans"),
+        characters,
+        covered_characters,
+        coverage,
+        labels,
+        length_histogram,
+    })
+}
+
+fn run_pipeline(pipeline: Vec<Document>) -> Result<Vec<Document>, ApiError> {
+    let cursor = DB.text_annotation_collection.aggregate(pipeline, None);
+
+    if cursor.is_err() {
+        return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .set_msg("unable to compute statistics")
+            .set_error(cursor.err().unwrap().to_string().as_str()));
+    }
+
+    let items: Vec<Document> = cursor.unwrap().filter_map(|it| it.ok()).collect();
+
+    Ok(items)
+}
+
+/// aggregation sums are returned as int32, int64 or double depending on their size.
+fn get_i64_bson(value: Option<&Bson>) -> Option<i64> {
+    match value {
+        Some(Bson::Int32(v)) => Some(*v as i64),
+        Some(Bson::Int64(v)) => Some(*v),
+        Some(Bson::Double(v)) => Some(*v as i64),
+        _ => None,
+    }
+}
+
+fn get_i64(document: &Document, key: &str) -> i64 {
+    get_i64_bson(document.get(key)).unwrap_or(0)
+}
@@ -0,0 +1,424 @@
+use std::str::FromStr;
+
+use actix_web::{
+    http::StatusCode,
+    web::{self, Json},
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, DateTime},
+    options::{FindOneAndUpdateOptions, ReturnDocument},
+};
+
+use crate::{
+    controllers::text_annotation_controller::{
+        ensure_editable, ensure_version, find_version_conflict,
+    },
+    database::mongodb::DB,
+    helpers::{
+        annotation_helpers::{get_change_events, is_suggestion_rejected, validate_token_span},
+        audit_helpers::record_realtime_audit_event,
+        colors_helpers::get_next_valid_color,
+        realtime_helpers::publish_annotation_event,
+        webhook_helpers::queue_realtime_webhook_event,
+    },
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        realtime_model::RealtimeEvent,
+        text_annotation_model::{
+            AnnotationRole, BulkSuggestionBody, BulkSuggestionResponse, CreateSuggestionBody,
+            CreateSuggestionsBody, Label, RejectedSuggestion, Suggestion, SuggestionAction,
+            TextAnnotation, Token,
+        },
+    },
+    object::error::ApiError,
+    policies::annotation_policy::authorize_annotation,
+};
+
+pub struct SuggestionController;
+
+impl SuggestionController {
+    pub fn create_many(
+        annotation_id: String,
+        body: Json<CreateSuggestionsBody>,
+        version: Option<i64>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<TextAnnotation, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to add suggestions to this annotation"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (mut annotation, role) =
+            authorize_annotation(annotation_id, &auth, AnnotationRole::Editor)?;
+
+        ensure_version(&annotation, version, role, auth.user_id)?;
+
+        queue_suggestions(&mut annotation, &body.suggestions)?;
+
+        save_suggestion_state(&annotation, role, auth.user_id)
+    }
+
+    pub fn accept(
+        params: web::Path<(String, String)>,
+        version: Option<i64>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<TextAnnotation, ApiError> {
+        let (annotation_id, suggestion_id) = params.into_inner();
+
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to accept a suggestion"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (mut annotation, role) =
+            authorize_annotation(annotation_id, &auth, AnnotationRole::Editor)?;
+
+        ensure_version(&annotation, version, role, auth.user_id)?;
+
+        let suggestion = find_suggestion(&annotation, suggestion_id)?;
+
+        let previous = annotation.clone();
+
+        accept_suggestion(&mut annotation, &suggestion, auth.user_id)?;
+
+        let updated_annotation = save_suggestion_state(&annotation, role, auth.user_id)?;
+
+        for event in get_change_events(&previous, &updated_annotation) {
+            record_realtime_audit_event(&auth, &updated_annotation, &event);
+            queue_realtime_webhook_event(&updated_annotation, &event);
+
+            publish_annotation_event(
+                updated_annotation._id.unwrap(),
+                Some(auth.user_id),
+                Some(updated_annotation.version),
+                event,
+            );
+        }
+
+        Ok(updated_annotation)
+    }
+
+    pub fn reject(
+        params: web::Path<(String, String)>,
+        version: Option<i64>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<TextAnnotation, ApiError> {
+        let (annotation_id, suggestion_id) = params.into_inner();
+
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to reject a suggestion"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (mut annotation, role) =
+            authorize_annotation(annotation_id, &auth, AnnotationRole::Editor)?;
+
+        ensure_version(&annotation, version, role, auth.user_id)?;
+
+        let suggestion = find_suggestion(&annotation, suggestion_id)?;
+
+        reject_suggestion(&mut annotation, &suggestion);
+
+        save_suggestion_state(&annotation, role, auth.user_id)
+    }
+
+    pub fn bulk(
+        annotation_id: String,
+        body: Json<BulkSuggestionBody>,
+        version: Option<i64>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<BulkSuggestionResponse, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to review suggestions"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (mut annotation, role) =
+            authorize_annotation(annotation_id, &auth, AnnotationRole::Editor)?;
+
+        ensure_version(&annotation, version, role, auth.user_id)?;
+
+        let mut selected: Vec<Suggestion> = annotation
+            .suggestions
+            .iter()
+            .filter(|it| body.source.is_none() || body.source.as_ref() == Some(&it.source))
+            .filter(|it| body.min_score.is_none() || it.score >= body.min_score.unwrap())
+            .filter(|it| body.max_score.is_none() || it.score < body.max_score.unwrap())
+            .cloned()
+            .collect();
+
+        // best candidates get the first chance when spans overlap
+        selected.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        let previous = annotation.clone();
+
+        let skipped = match body.action {
+            SuggestionAction::Accept => {
+                accept_suggestions(&mut annotation, &selected, auth.user_id)?
+            }
+            SuggestionAction::Reject => {
+                for suggestion in selected.iter() {
+                    reject_suggestion(&mut annotation, suggestion);
+                }
+
+                vec![]
+            }
+        };
+
+        let updated_annotation = save_suggestion_state(&annotation, role, auth.user_id)?;
+
+        for event in get_change_events(&previous, &updated_annotation) {
+            record_realtime_audit_event(&auth, &updated_annotation, &event);
+            queue_realtime_webhook_event(&updated_annotation, &event);
+
+            publish_annotation_event(
+                updated_annotation._id.unwrap(),
+                Some(auth.user_id),
+                Some(updated_annotation.version),
+                event,
+            );
+        }
+
+        Ok(BulkSuggestionResponse {
+            annotation: updated_annotation,
+            skipped,
+        })
+    }
+}
+
+/// adds the items to the annotation's queue, skipping rejected and already queued ones.
+pub fn queue_suggestions(
+    annotation: &mut TextAnnotation,
+    items: &[CreateSuggestionBody],
+) -> Result<(), ApiError> {
+    let content_len = annotation.content.len() as i64;
+
+    for item in items.iter() {
+        if let Some(reason) = find_invalid_suggestion(item, content_len) {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY).set_msg(reason));
+        }
+
+        let suggestion = Suggestion {
+            _id: Some(ObjectId::new()),
+            start: item.start,
+            end: item.end,
+            label: item.label.trim().to_string(),
+            source: item.source.trim().to_string(),
+            score: item.score,
+        };
+
+        // skip what was already refused or is already queued
+        if is_suggestion_rejected(&suggestion, &annotation.rejected_suggestions) {
+            continue;
+        }
+
+        let is_queued = annotation.suggestions.iter().any(|it| {
+            it.start == suggestion.start
+                && it.end == suggestion.end
+                && it.label == suggestion.label
+                && it.source == suggestion.source
+        });
+
+        if is_queued {
+            continue;
+        }
+
+        annotation.suggestions.push(suggestion);
+    }
+
+    Ok(())
+}
+
+/// tells why a suggestion cannot be queued, `end` is the offset of the span's last
+/// character.
+pub fn find_invalid_suggestion(
+    item: &CreateSuggestionBody,
+    content_len: i64,
+) -> Option<&'static str> {
+    if item.start.is_negative() || item.end <= item.start || item.end >= content_len {
+        return Some("suggestion span is out of the content bounds");
+    }
+
+    if item.label.trim().is_empty() || item.source.trim().is_empty() {
+        return Some("suggestion label and source cannot be empty");
+    }
+
+    None
+}
+
+fn find_suggestion(
+    annotation: &TextAnnotation,
+    suggestion_id: String,
+) -> Result<Suggestion, ApiError> {
+    let suggestion_oid = ObjectId::from_str(suggestion_id.as_str());
+
+    if suggestion_oid.is_err() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("unable to convert suggestion id to object id"));
+    }
+
+    let suggestion = annotation
+        .suggestions
+        .iter()
+        .find(|it| it._id == Some(suggestion_oid.clone().unwrap()));
+
+    if suggestion.is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("suggestion not found"));
+    }
+
+    Ok(suggestion.unwrap().clone())
+}
+
+/// turns the suggestion into a token, creating its label when the annotation does not have it yet.
+pub fn accept_suggestion(
+    annotation: &mut TextAnnotation,
+    suggestion: &Suggestion,
+    user_id: ObjectId,
+) -> Result<(), ApiError> {
+    ensure_editable(annotation)?;
+
+    let span_validation = validate_token_span(suggestion.start, suggestion.end, annotation);
+
+    if span_validation.is_err() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg(span_validation.err().unwrap().description.as_str()));
+    }
+
+    let existing_label = annotation
+        .labels
+        .iter()
+        .find(|it| it.name == suggestion.label);
+
+    let label_id = if let Some(label) = existing_label {
+        label._id.unwrap()
+    } else {
+        let color = get_next_valid_color(&annotation.labels);
+
+        if color.is_err() {
+            return Err(
+                ApiError::new(StatusCode::FORBIDDEN).set_msg("cannot generate new color for labels, you may have reached the maximum amount of labels allowed.")
+            );
+        }
+
+        let label = Label {
+            _id: Some(ObjectId::new()),
+            name: suggestion.label.clone(),
+            color: color.unwrap(),
+            created_at: Some(DateTime::now()),
+            updated_at: Some(DateTime::now()),
+        };
+
+        annotation.labels.push(label.clone());
+
+        label._id.unwrap()
+    };
+
+    annotation.tokens.push(Token {
+        _id: Some(ObjectId::new()),
+        start: suggestion.start,
+        end: suggestion.end,
+        label: label_id,
+        created_at: Some(DateTime::now()),
+        created_by: Some(user_id),
+    });
+
+    annotation.suggestions.retain(|it| it._id != suggestion._id);
+
+    Ok(())
+}
+
+/// accepts the suggestions in order and returns the ids of the ones left in the queue
+/// because their span overlaps a token or is out of the content.
+pub fn accept_suggestions(
+    annotation: &mut TextAnnotation,
+    suggestions: &[Suggestion],
+    user_id: ObjectId,
+) -> Result<Vec<ObjectId>, ApiError> {
+    ensure_editable(annotation)?;
+
+    let mut skipped: Vec<ObjectId> = vec![];
+
+    for suggestion in suggestions.iter() {
+        if validate_token_span(suggestion.start, suggestion.end, annotation).is_err() {
+            skipped.push(suggestion._id.unwrap());
+            continue;
+        }
+
+        accept_suggestion(annotation, suggestion, user_id)?;
+    }
+
+    Ok(skipped)
+}
+
+fn reject_suggestion(annotation: &mut TextAnnotation, suggestion: &Suggestion) {
+    annotation.suggestions.retain(|it| it._id != suggestion._id);
+
+    if !is_suggestion_rejected(suggestion, &annotation.rejected_suggestions) {
+        annotation.rejected_suggestions.push(RejectedSuggestion {
+            start: suggestion.start,
+            end: suggestion.end,
+            label: suggestion.label.clone(),
+            source: suggestion.source.clone(),
+        });
+    }
+}
+
+/// writes the suggestion queue back, `role` and `user_id` shape the document sent back
+/// when it changed in the meantime.
+pub fn save_suggestion_state(
+    annotation: &TextAnnotation,
+    role: AnnotationRole,
+    user_id: ObjectId,
+) -> Result<TextAnnotation, ApiError> {
+    let update_result = DB.text_annotation_collection.find_one_and_update(
+        doc! {"_id": annotation._id.unwrap(), "version": annotation.version},
+        doc! {
+          "$set": {
+            "labels": to_bson(&annotation.labels).unwrap(),
+            "tokens": to_bson(&annotation.tokens).unwrap(),
+            "suggestions": to_bson(&annotation.suggestions).unwrap(),
+            "rejected_suggestions": to_bson(&annotation.rejected_suggestions).unwrap(),
+            "updated_at": DateTime::now(),
+          },
+          "$inc": {"version": 1},
+        },
+        FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build(),
+    );
+
+    if update_result.as_ref().is_err() {
+        return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .set_msg("unable to update annotation suggestions"));
+    }
+
+    // the whole arrays are written back, a concurrent change would be lost
+    if update_result.as_ref().unwrap().is_none() {
+        return Err(find_version_conflict(
+            annotation._id.unwrap(),
+            role,
+            user_id,
+        ));
+    }
+
+    let updated_annotation = update_result.unwrap().unwrap();
+
+    // the label and token changes are published by the callers, this one tells the
+    // clients about the new version even when only the queue changed
+    publish_annotation_event(
+        updated_annotation._id.unwrap(),
+        Some(user_id),
+        Some(updated_annotation.version),
+        RealtimeEvent::SuggestionsUpdated,
+    );
+
+    Ok(updated_annotation)
+}
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+use actix_web::{http::StatusCode, web::Json};
+use mongodb::{
+    bson::{doc, to_bson, DateTime},
+    options::{FindOneAndUpdateOptions, ReturnDocument},
+};
+
+use crate::{
+    controllers::text_annotation_controller::{
+        ensure_editable, find_version_conflict, get_role_view, get_token_layer,
+    },
+    database::mongodb::DB,
+    helpers::{
+        annotation_helpers::get_layer_view,
+        audit_helpers::record_realtime_audit_event,
+        realtime_helpers::publish_annotation_event,
+        sync_helpers::{apply_operation, SyncContext},
+        webhook_helpers::queue_realtime_webhook_event,
+    },
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        sync_model::{RejectedOperation, SyncBody, SyncReference, SyncResponse},
+        text_annotation_model::{AnnotationLayer, AnnotationRole},
+    },
+    object::error::ApiError,
+    policies::annotation_policy::authorize_annotation,
+};
+
+static MAX_SYNC_OPERATIONS: usize = 500;
+
+/// a batch is replayed again when someone else saved the annotation in the meantime.
+static MAX_SYNC_ATTEMPTS: usize = 3;
+
+pub struct SyncController;
+
+impl SyncController {
+    /// replays operations queued offline on the current annotation.
+    pub fn sync(
+        id: String,
+        body: Json<SyncBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<SyncResponse, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to sync this annotation"));
+        }
+
+        let auth = auth.unwrap();
+
+        if body.operations.len() > MAX_SYNC_OPERATIONS {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY).set_msg(
+                format!(
+                    "a sync cannot have more than ({}) operations",
+                    MAX_SYNC_OPERATIONS
+                )
+                .as_str(),
+            ));
+        }
+
+        for _ in 0..MAX_SYNC_ATTEMPTS {
+            let (annotation, role) =
+                authorize_annotation(id.clone(), &auth, AnnotationRole::Annotator)?;
+
+            ensure_editable(&annotation)?;
+
+            if body.base_version > annotation.version {
+                return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                    .set_msg("base version is ahead of the annotation"));
+            }
+
+            // annotators write in their own layer
+            let layer = get_token_layer(role, auth.user_id);
+
+            let mut view = match layer {
+                Some(layer) => get_layer_view(annotation.clone(), layer),
+                None => annotation.clone(),
+            };
+
+            let mut ctx = SyncContext {
+                user_id: auth.user_id,
+                layer,
+                can_edit_labels: role >= AnnotationRole::Editor,
+                references: HashMap::new(),
+            };
+
+            let mut applied: Vec<String> = vec![];
+            let mut rejected: Vec<RejectedOperation> = vec![];
+            let mut events = vec![];
+
+            for operation in body.operations.iter() {
+                match apply_operation(&mut view, &operation.kind, &mut ctx) {
+                    Ok(event) => {
+                        applied.push(operation.id.clone());
+                        events.extend(event);
+                    }
+                    Err(reason) => rejected.push(RejectedOperation {
+                        id: operation.id.clone(),
+                        reason,
+                    }),
+                }
+            }
+
+            let mut references: Vec<SyncReference> = ctx
+                .references
+                .into_iter()
+                .map(|(ref_id, id)| SyncReference { ref_id, id })
+                .collect();
+            references.sort_by(|a, b| a.ref_id.cmp(&b.ref_id));
+
+            let rebased = body.base_version != annotation.version;
+
+            // nothing changed, the document is returned as it is
+            if events.is_empty() {
+                return Ok(SyncResponse {
+                    annotation: get_role_view(annotation, role, auth.user_id),
+                    rebased,
+                    applied,
+                    rejected,
+                    references,
+                });
+            }
+
+            let mut saved = annotation.clone();
+
+            match layer {
+                Some(layer) => {
+                    saved.layers.retain(|it| it.user_id != layer);
+                    saved.layers.push(AnnotationLayer {
+                        user_id: layer,
+                        tokens: view.tokens,
+                    });
+                }
+                None => {
+                    saved.labels = view.labels;
+                    saved.tokens = view.tokens;
+
+                    // the layers lose the tokens of the deleted labels
+                    for layer in saved.layers.iter_mut() {
+                        layer.tokens.retain(|token| {
+                            saved.labels.iter().any(|it| it._id == Some(token.label))
+                        });
+                    }
+                }
+            }
+
+            // the whole arrays are written, so only over the version they were built from
+            let update_result = DB.text_annotation_collection.find_one_and_update(
+                doc! {"_id": annotation._id.unwrap(), "version": annotation.version},
+                doc! {
+                  "$set": {
+                    "labels": to_bson(&saved.labels).unwrap(),
+                    "tokens": to_bson(&saved.tokens).unwrap(),
+                    "layers": to_bson(&saved.layers).unwrap(),
+                    "updated_at": DateTime::now(),
+                  },
+                  "$inc": {"version": 1},
+                },
+                FindOneAndUpdateOptions::builder()
+                    .return_document(ReturnDocument::After)
+                    .build(),
+            );
+
+            if update_result.is_err() {
+                return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                    .set_msg("unable to sync annotation")
+                    .set_error(update_result.err().unwrap().to_string().as_str()));
+            }
+
+            let updated_annotation = update_result.unwrap();
+
+            if updated_annotation.is_none() {
+                continue;
+            }
+
+            let updated_annotation = updated_annotation.unwrap();
+
+            for event in events {
+                record_realtime_audit_event(&auth, &updated_annotation, &event);
+                queue_realtime_webhook_event(&updated_annotation, &event);
+
+                publish_annotation_event(
+                    updated_annotation._id.unwrap(),
+                    Some(auth.user_id),
+                    Some(updated_annotation.version),
+                    event,
+                );
+            }
+
+            return Ok(SyncResponse {
+                annotation: get_role_view(updated_annotation, role, auth.user_id),
+                rebased,
+                applied,
+                rejected,
+                references,
+            });
+        }
+
+        // the annotation kept changing while replaying the operations
+        let (annotation, role) = authorize_annotation(id, &auth, AnnotationRole::Annotator)?;
+
+        Err(find_version_conflict(
+            annotation._id.unwrap(),
+            role,
+            auth.user_id,
+        ))
+    }
+}
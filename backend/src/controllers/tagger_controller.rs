@@ -0,0 +1,365 @@
+use actix_web::{
+    http::StatusCode,
+    web::{self, Json},
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId, Document},
+    options::{FindOneAndReplaceOptions, FindOptions, ReturnDocument},
+};
+
+use crate::{
+    controllers::{
+        project_controller::find_owned_project,
+        suggestion_controller::{accept_suggestions, queue_suggestions, save_suggestion_state},
+    },
+    database::mongodb::DB,
+    helpers::{
+        annotation_helpers::{get_change_events, get_labeled_spans},
+        audit_helpers::record_realtime_audit_event,
+        metrics_helpers::compute_span_metrics,
+        realtime_helpers::publish_annotation_event,
+        tagger_helpers::{
+            delete_tagger_weights, find_tagger_weights, save_tagger_weights, spans_to_tags,
+            split_held_out, split_words, tags_to_spans, Perceptron, Word,
+        },
+        webhook_helpers::queue_realtime_webhook_event,
+    },
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        model_backend_model::PredictionTarget,
+        tagger_model::{
+            PredictTaggerBody, PredictTaggerResponse, TaggerModel, TaggerQueryParams,
+            TrainTaggerBody,
+        },
+        text_annotation_model::{AnnotationRole, CreateSuggestionBody, Suggestion, TextAnnotation},
+    },
+    object::error::ApiError,
+    policies::annotation_policy::authorize_annotation,
+};
+
+static TAGGER_SOURCE: &str = "tagger";
+static DEFAULT_ITERATIONS: i64 = 5;
+static MAX_ITERATIONS: i64 = 50;
+static DEFAULT_TEST_RATIO: f64 = 0.2;
+
+pub struct TaggerController;
+
+impl TaggerController {
+    /// trains a tagger on the annotated documents, metrics are computed on a held-out
+    /// split and the saved model is then trained on every document.
+    pub fn train(
+        body: Json<TrainTaggerBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<TaggerModel, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to train a tagger"));
+        }
+
+        let auth = auth.unwrap();
+
+        let iterations = body.iterations.unwrap_or(DEFAULT_ITERATIONS);
+        let test_ratio = body.test_ratio.unwrap_or(DEFAULT_TEST_RATIO);
+
+        if !(1..=MAX_ITERATIONS).contains(&iterations) {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY).set_msg(
+                format!("iterations should be between (1) and ({})", MAX_ITERATIONS).as_str(),
+            ));
+        }
+
+        if test_ratio <= 0.0 || test_ratio > 0.5 {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                .set_msg("test ratio should be greater than (0) and at most (0.5)"));
+        }
+
+        let project_id = get_project_id(body.project_id.clone(), &auth)?;
+
+        let mut filter = scope_filter(&auth, project_id);
+        filter.insert("tokens.0", doc! {"$exists": true});
+
+        let annotations = find_annotations(filter)?;
+
+        if annotations.len() < 2 {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                .set_msg("at least two annotated documents are needed to train a tagger"));
+        }
+
+        let samples: Vec<(Vec<Word>, Vec<String>)> = annotations
+            .iter()
+            .map(|annotation| {
+                let words = split_words(annotation.content.as_str());
+                let tags = spans_to_tags(&words, &get_labeled_spans(annotation));
+
+                (words, tags)
+            })
+            .collect();
+
+        let (train_indexes, test_indexes) = split_held_out(samples.len(), test_ratio);
+
+        // metrics computed on no document would report a perfect tagger
+        if test_indexes.is_empty() {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                .set_msg("not enough annotated documents to hold some out for the evaluation"));
+        }
+
+        let train_samples: Vec<(Vec<Word>, Vec<String>)> = train_indexes
+            .iter()
+            .map(|it| samples[*it].clone())
+            .collect();
+        let test_samples: Vec<(Vec<Word>, Vec<String>)> =
+            test_indexes.iter().map(|it| samples[*it].clone()).collect();
+
+        let evaluated = Perceptron::train(&train_samples, iterations as usize);
+
+        let documents: Vec<_> = test_samples
+            .iter()
+            .map(|(words, tags)| {
+                let gold = tags_to_spans(words, tags);
+                let predicted = tags_to_spans(words, &evaluated.predict(words));
+
+                (gold, predicted)
+            })
+            .collect();
+
+        let metrics = compute_span_metrics(&documents);
+
+        let model = Perceptron::train(&samples, iterations as usize);
+        let weights = model.to_weights();
+
+        // the weights are saved first, the tagger then switches to them
+        let training_id = ObjectId::new();
+
+        if let Err(err) = save_tagger_weights(training_id, &weights) {
+            delete_tagger_weights(training_id);
+
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to save tagger")
+                .set_error(err.to_string().as_str()));
+        }
+
+        let tagger = TaggerModel {
+            _id: None,
+            user_id: auth.user_id,
+            project_id,
+            tags: model.tags.clone(),
+            training_id,
+            weight_count: weights.len() as i64,
+            iterations,
+            train_documents: train_samples.len() as i64,
+            test_documents: test_samples.len() as i64,
+            metrics,
+        };
+
+        let result = DB.tagger_collection.find_one_and_replace(
+            doc! {"user_id": auth.user_id, "project_id": project_id},
+            tagger.clone(),
+            FindOneAndReplaceOptions::builder()
+                .upsert(true)
+                .return_document(ReturnDocument::Before)
+                .build(),
+        );
+
+        if result.is_err() {
+            delete_tagger_weights(training_id);
+
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to save tagger")
+                .set_error(result.err().unwrap().to_string().as_str()));
+        }
+
+        // the weights of the replaced training are no longer used
+        if let Some(previous) = result.unwrap() {
+            delete_tagger_weights(previous.training_id);
+        }
+
+        Ok(tagger)
+    }
+
+    pub fn get(
+        query_params: web::Query<TaggerQueryParams>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<TaggerModel, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to get a tagger"));
+        }
+
+        let auth = auth.unwrap();
+
+        let project_id = get_project_id(query_params.project_id.clone(), &auth)?;
+
+        find_tagger(&auth, project_id)
+    }
+
+    /// stores the tagger's predictions as suggestions, or tokens, of the given documents,
+    /// every document is checked before any of them is written.
+    pub fn predict(
+        body: Json<PredictTaggerBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<PredictTaggerResponse, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to use a tagger"));
+        }
+
+        let auth = auth.unwrap();
+
+        let project_id = get_project_id(body.project_id.clone(), &auth)?;
+
+        let tagger = find_tagger(&auth, project_id)?;
+
+        let weights = find_tagger_weights(tagger.training_id);
+
+        if weights.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to load tagger")
+                .set_error(weights.err().unwrap().to_string().as_str()));
+        }
+
+        let model = Perceptron::from_weights(&tagger.tags, &weights.unwrap());
+
+        let annotations: Vec<(TextAnnotation, AnnotationRole)> = if body.annotation_ids.is_some() {
+            let mut items = vec![];
+
+            for id in body.annotation_ids.clone().unwrap() {
+                items.push(authorize_annotation(id, &auth, AnnotationRole::Editor)?);
+            }
+
+            items
+        } else {
+            let mut filter = scope_filter(&auth, project_id);
+            filter.insert("tokens", doc! {"$size": 0});
+
+            // the scope only holds the user's own documents
+            find_annotations(filter)?
+                .into_iter()
+                .map(|it| (it, AnnotationRole::Owner))
+                .collect()
+        };
+
+        let target = body.target.unwrap_or(PredictionTarget::Suggestions);
+
+        let mut response = PredictTaggerResponse {
+            annotations: 0,
+            spans: 0,
+        };
+
+        let mut changes: Vec<(TextAnnotation, TextAnnotation, AnnotationRole)> = vec![];
+
+        for (mut annotation, role) in annotations {
+            let words = split_words(annotation.content.as_str());
+
+            let predictions: Vec<CreateSuggestionBody> =
+                tags_to_spans(&words, &model.predict(&words))
+                    .into_iter()
+                    .map(|span| CreateSuggestionBody {
+                        start: span.start,
+                        end: span.end,
+                        label: span.label,
+                        source: TAGGER_SOURCE.to_string(),
+                        score: 1.0,
+                    })
+                    .collect();
+
+            response.annotations += 1;
+            response.spans += predictions.len() as i64;
+
+            if predictions.is_empty() {
+                continue;
+            }
+
+            let previous = annotation.clone();
+
+            queue_suggestions(&mut annotation, &predictions)?;
+
+            if target == PredictionTarget::Tokens {
+                let queued: Vec<Suggestion> = annotation
+                    .suggestions
+                    .iter()
+                    .filter(|it| it.source == TAGGER_SOURCE)
+                    .cloned()
+                    .collect();
+
+                // spans that cannot become tokens stay as suggestions
+                accept_suggestions(&mut annotation, &queued, auth.user_id)?;
+            }
+
+            changes.push((previous, annotation, role));
+        }
+
+        for (previous, annotation, role) in changes {
+            let updated_annotation = save_suggestion_state(&annotation, role, auth.user_id)?;
+
+            for event in get_change_events(&previous, &updated_annotation) {
+                record_realtime_audit_event(&auth, &updated_annotation, &event);
+                queue_realtime_webhook_event(&updated_annotation, &event);
+
+                publish_annotation_event(
+                    updated_annotation._id.unwrap(),
+                    Some(auth.user_id),
+                    Some(updated_annotation.version),
+                    event,
+                );
+            }
+        }
+
+        Ok(response)
+    }
+}
+
+fn get_project_id(
+    project_id: Option<String>,
+    auth: &UserAuthContext,
+) -> Result<Option<ObjectId>, ApiError> {
+    if project_id.is_none() {
+        return Ok(None);
+    }
+
+    let project = find_owned_project(project_id.unwrap(), auth)?;
+
+    Ok(project._id)
+}
+
+fn scope_filter(auth: &UserAuthContext, project_id: Option<ObjectId>) -> Document {
+    let mut filter = doc! {"user_id": auth.user_id};
+
+    if let Some(project_id) = project_id {
+        filter.insert("project_id", project_id);
+    }
+
+    filter
+}
+
+fn find_annotations(filter: Document) -> Result<Vec<TextAnnotation>, ApiError> {
+    let fetch_result = DB
+        .text_annotation_collection
+        .find(filter, FindOptions::builder().sort(doc! {"_id": 1}).build());
+
+    if fetch_result.is_err() {
+        return Err(
+            ApiError::new(StatusCode::INTERNAL_SERVER_ERROR).set_msg("unable to fetch annotations")
+        );
+    }
+
+    let items: Vec<TextAnnotation> = fetch_result.unwrap().filter_map(|it| it.ok()).collect();
+
+    Ok(items)
+}
+
+fn find_tagger(
+    auth: &UserAuthContext,
+    project_id: Option<ObjectId>,
+) -> Result<TaggerModel, ApiError> {
+    let tagger_result = DB.tagger_collection.find_one(
+        doc! {"user_id": auth.user_id, "project_id": project_id},
+        None,
+    );
+
+    if tagger_result.as_ref().is_err() || tagger_result.as_ref().unwrap().is_none() {
+        return Err(
+            ApiError::new(StatusCode::NOT_FOUND).set_msg("no tagger was trained for this corpus")
+        );
+    }
+
+    Ok(tagger_result.unwrap().unwrap())
+}
@@ -0,0 +1,642 @@
+use std::str::FromStr;
+
+use actix_web::{
+    http::StatusCode,
+    web::{self, Json},
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, DateTime},
+    options::{FindOneAndUpdateOptions, ReturnDocument},
+};
+
+use crate::{
+    database::mongodb::DB,
+    middleware::auth_middleware::UserAuthContext,
+    models::team_model::{
+        CreateTeamBody, InviteTeamMemberBody, PendingInvitation, Team, TeamInvitation, TeamMember,
+        TeamRole, UpdateTeamBody, UpdateTeamMemberBody,
+    },
+    object::{common::Message, error::ApiError},
+};
+
+/// a change is made again when someone else saved the team in the meantime.
+static MAX_SAVE_ATTEMPTS: usize = 3;
+
+pub struct TeamController;
+
+impl TeamController {
+    pub fn create(
+        body: Json<CreateTeamBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Team, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to create a team"));
+        }
+
+        let team = Team {
+            _id: None,
+            name: body.name.trim().to_string(),
+            description: body.description.clone().unwrap_or_default(),
+            members: vec![TeamMember {
+                user_id: auth.unwrap().user_id,
+                role: TeamRole::Owner,
+                joined_at: Some(DateTime::now()),
+            }],
+            invitations: vec![],
+            created_at: Some(DateTime::now()),
+            updated_at: Some(DateTime::now()),
+            version: 0,
+        };
+
+        validate_team(&team)?;
+
+        let result = DB.team_collection.insert_one(team, None);
+
+        if result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to create team")
+                .set_error(result.err().unwrap().to_string().as_str()));
+        }
+
+        let team = DB
+            .team_collection
+            .find_one(doc! {"_id": result.unwrap().inserted_id}, None);
+
+        if team.as_ref().is_err() || team.as_ref().unwrap().is_none() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to retrieve created team"));
+        }
+
+        Ok(team.unwrap().unwrap())
+    }
+
+    pub fn get(id: String, auth: Option<UserAuthContext>) -> Result<Team, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to get this team"));
+        }
+
+        find_member_team(id, &auth.unwrap())
+    }
+
+    pub fn get_all(auth: Option<UserAuthContext>) -> Result<Vec<Team>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to list your teams"));
+        }
+
+        Ok(find_user_teams(auth.unwrap().user_id))
+    }
+
+    pub fn update(
+        id: String,
+        body: Json<UpdateTeamBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Team, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to update a team"));
+        }
+
+        let auth = auth.unwrap();
+
+        save_team(|| {
+            let mut team = find_managed_team(id.clone(), &auth)?;
+
+            if let Some(name) = &body.name {
+                team.name = name.trim().to_string();
+            }
+
+            if let Some(description) = &body.description {
+                team.description = description.clone();
+            }
+
+            validate_team(&team)?;
+
+            Ok(team)
+        })
+    }
+
+    /// deletes the team, its projects and documents are kept by their owners.
+    pub fn delete(id: String, auth: Option<UserAuthContext>) -> Result<Message, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to delete a team"));
+        }
+
+        let auth = auth.unwrap();
+
+        let team = find_member_team(id, &auth)?;
+
+        if get_team_role(&team, auth.user_id) != Some(TeamRole::Owner) {
+            return Err(ApiError::new(StatusCode::FORBIDDEN)
+                .set_msg("only a team owner can delete the team"));
+        }
+
+        let team_id = team._id.unwrap();
+
+        let detach_projects = DB.project_collection.update_many(
+            doc! {"team_id": team_id},
+            doc! {"$unset": {"team_id": ""}},
+            None,
+        );
+
+        let detach_annotations = DB.text_annotation_collection.update_many(
+            doc! {"team_id": team_id},
+            doc! {
+              "$unset": {"team_id": ""},
+              "$set": {"updated_at": DateTime::now()},
+              "$inc": {"version": 1},
+            },
+            None,
+        );
+
+        if detach_projects.is_err() || detach_annotations.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to detach team projects and annotations"));
+        }
+
+        let result = DB.team_collection.delete_one(doc! {"_id": team_id}, None);
+
+        if result.is_err() {
+            return Err(
+                ApiError::new(StatusCode::INTERNAL_SERVER_ERROR).set_msg("unable to delete team")
+            );
+        }
+
+        Ok(Message::new().set_msg("team deleted successfully"))
+    }
+
+    /// invites a user by username or email, an unknown email is kept until someone
+    /// signs up with it.
+    pub fn invite(
+        id: String,
+        body: Json<InviteTeamMemberBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Team, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to invite a team member"));
+        }
+
+        let auth = auth.unwrap();
+
+        // only managers get to look users up
+        let team = find_managed_team(id.clone(), &auth)?;
+
+        ensure_role_assignable(&team, auth.user_id, body.role)?;
+
+        let login = body.login.trim().to_string();
+
+        let user_result = DB.user_collection.find_one(
+            doc! {"$or": [{"username": login.clone()}, {"email": login.clone()}]},
+            None,
+        );
+
+        if user_result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to fetch the invited user"));
+        }
+
+        let user_id = user_result.unwrap().and_then(|it| it._id);
+
+        if user_id.is_none() && !login.contains('@') {
+            return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("user not found"));
+        }
+
+        let email = user_id.map_or(Some(login.to_lowercase()), |_| None);
+
+        save_team(|| {
+            let mut team = find_managed_team(id.clone(), &auth)?;
+
+            ensure_role_assignable(&team, auth.user_id, body.role)?;
+
+            if user_id.is_some_and(|it| get_team_role(&team, it).is_some()) {
+                return Err(ApiError::new(StatusCode::CONFLICT)
+                    .set_msg("user is already a member of the team"));
+            }
+
+            let is_invited = team.invitations.iter().any(|it| {
+                (user_id.is_some() && it.user_id == user_id)
+                    || (email.is_some() && it.email == email)
+            });
+
+            if is_invited {
+                return Err(ApiError::new(StatusCode::CONFLICT).set_msg("user is already invited"));
+            }
+
+            team.invitations.push(TeamInvitation {
+                _id: ObjectId::new(),
+                user_id,
+                email: email.clone(),
+                role: body.role,
+                invited_by: auth.user_id,
+                created_at: Some(DateTime::now()),
+            });
+
+            Ok(team)
+        })
+    }
+
+    pub fn cancel_invitation(
+        params: web::Path<(String, String)>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Team, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to cancel an invitation"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (team_id, invitation_id) = params.into_inner();
+
+        let invitation_id = parse_object_id(invitation_id, "invitation")?;
+
+        save_team(|| {
+            let mut team = find_managed_team(team_id.clone(), &auth)?;
+
+            if !team.invitations.iter().any(|it| it._id == invitation_id) {
+                return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("invitation not found"));
+            }
+
+            team.invitations.retain(|it| it._id != invitation_id);
+
+            Ok(team)
+        })
+    }
+
+    /// lists the invitations sent to the user or to their email.
+    pub fn get_invitations(
+        auth: Option<UserAuthContext>,
+    ) -> Result<Vec<PendingInvitation>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to list your invitations"));
+        }
+
+        let user_id = auth.unwrap().user_id;
+        let email = find_user_email(user_id)?;
+
+        let fetch_result = DB.team_collection.find(
+            doc! {"$or": [{"invitations.user_id": user_id}, {"invitations.email": email.clone()}]},
+            None,
+        );
+
+        if fetch_result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to fetch invitations"));
+        }
+
+        let items: Vec<PendingInvitation> = fetch_result
+            .unwrap()
+            .filter_map(|it| it.ok())
+            .flat_map(|team| {
+                team.invitations
+                    .iter()
+                    .filter(|it| is_invitation_for(it, user_id, &email))
+                    .map(|it| PendingInvitation {
+                        _id: it._id,
+                        team_id: team._id.unwrap(),
+                        team_name: team.name.clone(),
+                        role: it.role,
+                        invited_by: it.invited_by,
+                        created_at: it.created_at,
+                    })
+                    .collect::<Vec<PendingInvitation>>()
+            })
+            .collect();
+
+        Ok(items)
+    }
+
+    pub fn accept_invitation(
+        invitation_id: String,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Team, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to accept an invitation"));
+        }
+
+        let user_id = auth.unwrap().user_id;
+
+        save_team(|| {
+            let (mut team, invitation) = find_user_invitation(invitation_id.clone(), user_id)?;
+
+            team.invitations.retain(|it| it._id != invitation._id);
+
+            if get_team_role(&team, user_id).is_none() {
+                team.members.push(TeamMember {
+                    user_id,
+                    role: invitation.role,
+                    joined_at: Some(DateTime::now()),
+                });
+            }
+
+            Ok(team)
+        })
+    }
+
+    pub fn decline_invitation(
+        invitation_id: String,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Message, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to decline an invitation"));
+        }
+
+        let user_id = auth.unwrap().user_id;
+
+        save_team(|| {
+            let (mut team, invitation) = find_user_invitation(invitation_id.clone(), user_id)?;
+
+            team.invitations.retain(|it| it._id != invitation._id);
+
+            Ok(team)
+        })?;
+
+        Ok(Message::new().set_msg("invitation declined successfully"))
+    }
+
+    pub fn update_member(
+        params: web::Path<(String, String)>,
+        body: Json<UpdateTeamMemberBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Team, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to update a team member"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (team_id, user_id) = params.into_inner();
+
+        save_team(|| {
+            let mut team = find_managed_team(team_id.clone(), &auth)?;
+
+            let user_id = find_team_member_id(&team, user_id.clone())?;
+
+            // owners are the only ones changing an owner
+            ensure_role_assignable(&team, auth.user_id, body.role)?;
+            ensure_role_assignable(&team, auth.user_id, get_team_role(&team, user_id).unwrap())?;
+
+            for member in team.members.iter_mut().filter(|it| it.user_id == user_id) {
+                member.role = body.role;
+            }
+
+            ensure_owner_left(&team)?;
+
+            Ok(team)
+        })
+    }
+
+    /// removes a member, members can also leave the team by themselves.
+    pub fn remove_member(
+        params: web::Path<(String, String)>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Team, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to remove a team member"));
+        }
+
+        let auth = auth.unwrap();
+
+        let (team_id, user_id) = params.into_inner();
+
+        save_team(|| {
+            let mut team = find_member_team(team_id.clone(), &auth)?;
+
+            let user_id = find_team_member_id(&team, user_id.clone())?;
+
+            if user_id != auth.user_id {
+                if !get_team_role(&team, auth.user_id).is_some_and(|it| it.can_manage()) {
+                    return Err(ApiError::new(StatusCode::FORBIDDEN)
+                        .set_msg("only a team manager can do this action"));
+                }
+
+                ensure_role_assignable(
+                    &team,
+                    auth.user_id,
+                    get_team_role(&team, user_id).unwrap(),
+                )?;
+            }
+
+            team.members.retain(|it| it.user_id != user_id);
+
+            ensure_owner_left(&team)?;
+
+            Ok(team)
+        })
+    }
+}
+
+pub fn get_team_role(team: &Team, user_id: ObjectId) -> Option<TeamRole> {
+    team.members
+        .iter()
+        .find(|it| it.user_id == user_id)
+        .map(|it| it.role)
+}
+
+/// teams the user is a member of.
+pub fn find_user_teams(user_id: ObjectId) -> Vec<Team> {
+    let fetch_result = DB
+        .team_collection
+        .find(doc! {"members.user_id": user_id}, None);
+
+    match fetch_result {
+        Ok(cursor) => cursor.filter_map(|it| it.ok()).collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// role of the user in the team a project or a document belongs to.
+pub fn find_team_role(team_id: Option<ObjectId>, user_id: ObjectId) -> Option<TeamRole> {
+    let team = DB
+        .team_collection
+        .find_one(doc! {"_id": team_id?}, None)
+        .ok()??;
+
+    get_team_role(&team, user_id)
+}
+
+pub fn find_member_team(id: String, auth: &UserAuthContext) -> Result<Team, ApiError> {
+    let team_id = parse_object_id(id, "team")?;
+
+    let team_result = DB.team_collection.find_one(doc! {"_id": team_id}, None);
+
+    if team_result.as_ref().is_err() || team_result.as_ref().unwrap().is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("team not found"));
+    }
+
+    let team = team_result.unwrap().unwrap();
+
+    if get_team_role(&team, auth.user_id).is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("team not found"));
+    }
+
+    Ok(team)
+}
+
+/// finds a team the user owns or manages, projects and documents are moved to a
+/// team by its managers.
+pub fn find_managed_team(id: String, auth: &UserAuthContext) -> Result<Team, ApiError> {
+    let team = find_member_team(id, auth)?;
+
+    if !get_team_role(&team, auth.user_id).is_some_and(|it| it.can_manage()) {
+        return Err(
+            ApiError::new(StatusCode::FORBIDDEN).set_msg("only a team manager can do this action")
+        );
+    }
+
+    Ok(team)
+}
+
+fn parse_object_id(id: String, name: &str) -> Result<ObjectId, ApiError> {
+    let object_id = ObjectId::from_str(id.as_str());
+
+    if object_id.is_err() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg(format!("unable to convert {} id to object id", name).as_str()));
+    }
+
+    Ok(object_id.unwrap())
+}
+
+fn find_team_member_id(team: &Team, user_id: String) -> Result<ObjectId, ApiError> {
+    let user_id = parse_object_id(user_id, "user")?;
+
+    if get_team_role(team, user_id).is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("team member not found"));
+    }
+
+    Ok(user_id)
+}
+
+fn find_user_email(user_id: ObjectId) -> Result<String, ApiError> {
+    let user_result = DB.user_collection.find_one(doc! {"_id": user_id}, None);
+
+    if user_result.as_ref().is_err() || user_result.as_ref().unwrap().is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("user not found"));
+    }
+
+    Ok(user_result.unwrap().unwrap().email.to_lowercase())
+}
+
+fn is_invitation_for(invitation: &TeamInvitation, user_id: ObjectId, email: &str) -> bool {
+    invitation.user_id == Some(user_id) || invitation.email.as_deref() == Some(email)
+}
+
+fn find_user_invitation(
+    invitation_id: String,
+    user_id: ObjectId,
+) -> Result<(Team, TeamInvitation), ApiError> {
+    let invitation_id = parse_object_id(invitation_id, "invitation")?;
+    let email = find_user_email(user_id)?;
+
+    let team_result = DB
+        .team_collection
+        .find_one(doc! {"invitations._id": invitation_id}, None);
+
+    if team_result.as_ref().is_err() || team_result.as_ref().unwrap().is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("invitation not found"));
+    }
+
+    let team = team_result.unwrap().unwrap();
+
+    let invitation = team
+        .invitations
+        .iter()
+        .find(|it| it._id == invitation_id && is_invitation_for(it, user_id, &email))
+        .cloned();
+
+    if invitation.is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("invitation not found"));
+    }
+
+    Ok((team, invitation.unwrap()))
+}
+
+/// only owners can give or take the owner role.
+fn ensure_role_assignable(team: &Team, user_id: ObjectId, role: TeamRole) -> Result<(), ApiError> {
+    if role == TeamRole::Owner && get_team_role(team, user_id) != Some(TeamRole::Owner) {
+        return Err(
+            ApiError::new(StatusCode::FORBIDDEN).set_msg("only a team owner can manage the owners")
+        );
+    }
+
+    Ok(())
+}
+
+fn ensure_owner_left(team: &Team) -> Result<(), ApiError> {
+    if !team.members.iter().any(|it| it.role == TeamRole::Owner) {
+        return Err(ApiError::new(StatusCode::CONFLICT).set_msg("a team needs at least one owner"));
+    }
+
+    Ok(())
+}
+
+/// makes the change on the current team and saves it over the version it was made on,
+/// a change racing another one is made again on the saved team instead of undoing it.
+fn save_team<F>(change: F) -> Result<Team, ApiError>
+where
+    F: Fn() -> Result<Team, ApiError>,
+{
+    for _ in 0..MAX_SAVE_ATTEMPTS {
+        let team = change()?;
+
+        let update_result = DB.team_collection.find_one_and_update(
+            doc! {"_id": team._id.unwrap(), "version": team.version},
+            doc! {
+              "$set": {
+                "name": team.name.clone(),
+                "description": team.description.clone(),
+                "members": to_bson(&team.members).unwrap(),
+                "invitations": to_bson(&team.invitations).unwrap(),
+                "updated_at": DateTime::now(),
+              },
+              "$inc": {"version": 1},
+            },
+            FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build(),
+        );
+
+        if update_result.is_err() {
+            return Err(
+                ApiError::new(StatusCode::INTERNAL_SERVER_ERROR).set_msg("unable to update team")
+            );
+        }
+
+        if let Some(team) = update_result.unwrap() {
+            return Ok(team);
+        }
+    }
+
+    Err(ApiError::new(StatusCode::CONFLICT)
+        .set_msg("the team kept changing while saving, try again"))
+}
+
+fn validate_team(team: &Team) -> Result<(), ApiError> {
+    let mut validation: Vec<String> = vec![];
+
+    if team.name.is_empty() || team.name.len() > 50 {
+        validation.push("\"name\": value length should be between (1) and (50)".to_string());
+    }
+
+    if team.description.len() > 500 {
+        validation.push("\"description\": value exceeds max length (500)".to_string());
+    }
+
+    if !validation.is_empty() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("failed to validate body")
+            .set_validation(validation));
+    }
+
+    Ok(())
+}
@@ -6,24 +6,51 @@ use actix_web::{
     Result,
 };
 use mongodb::{
-    bson::{doc, oid::ObjectId},
-    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+    bson::{doc, from_document, oid::ObjectId, to_bson, Bson, DateTime, Document},
+    options::{FindOneAndUpdateOptions, ReturnDocument},
 };
+use serde_json::json;
 
 use crate::{
+    controllers::{
+        comment_controller::{count_open_threads, delete_annotation_threads},
+        model_backend_controller::queue_pre_annotation,
+        project_controller::{find_owned_project, find_project},
+        queue_controller::release_annotation_lease,
+        search_controller::has_label_expr,
+        team_controller::{find_managed_team, find_user_teams},
+    },
     database::mongodb::DB,
-    helpers::colors_helpers::{get_next_valid_color, is_color_used, is_valid_color},
-    middleware::auth_middleware::UserAuthContext,
-    models::text_annotation_model::{
-        CreateLabelBody, CreateTextAnnotationBody, CreateTokenBody, Label, TextAnnotation,
-        UpdateLabelBody, UpdateTextAnnotationBody,
+    helpers::{
+        annotation_helpers::{
+            get_layer_view, is_review_transition, is_status_transition_allowed, validate_token_span,
+        },
+        audit_helpers::record_audit_event,
+        colors_helpers::{get_next_valid_color, is_color_used, is_valid_color},
+        cursor_helpers::{decode_cursor, encode_cursor, ListCursor},
+        realtime_helpers::publish_annotation_event,
+        webhook_helpers::{queue_realtime_webhook_event, queue_webhook_event},
     },
-    object::{
-        common::{Message, PaginationQueryParams},
-        error::ApiError,
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        audit_model::{AuditAction, AuditEvent},
+        project_model::Project,
+        realtime_model::RealtimeEvent,
+        text_annotation_model::{
+            AnnotationLayer, AnnotationRole, AnnotationSort, AnnotationStatus, CreateLabelBody,
+            CreateTextAnnotationBody, CreateTokenBody, Label, ListAnnotationsQueryParams,
+            SortOrder, TextAnnotation, TextAnnotationList, Token, UpdateAnnotationStatusBody,
+            UpdateLabelBody, UpdateTextAnnotationBody,
+        },
+        webhook_model::WebhookEvent,
     },
+    object::{common::Message, error::ApiError},
+    policies::annotation_policy::{authorize_annotation, resolve_annotation_role},
 };
 
+static DEFAULT_LIST_LIMIT: i64 = 20;
+static MAX_LIST_LIMIT: i64 = 100;
+
 pub struct AnnotationController {}
 
 impl AnnotationController {
@@ -36,15 +63,43 @@ impl AnnotationController {
                 .set_msg("you need to be signed in to create an annotation"));
         }
 
-        let user_id = auth.unwrap().user_id;
+        let auth = auth.unwrap();
+
+        let mut project_id: Option<ObjectId> = None;
+
+        if body.project_id.is_some() {
+            let project = find_owned_project(body.project_id.clone().unwrap(), &auth)?;
+
+            project_id = project._id;
+        }
+
+        let mut team_id: Option<ObjectId> = None;
+
+        if let Some(id) = body.team_id.clone() {
+            team_id = find_managed_team(id, &auth)?._id;
+        }
 
         let new_doc = TextAnnotation {
             title: body.title.to_owned(),
             _id: None,
             content: body.content.to_owned(),
-            user_id,
+            user_id: auth.user_id,
+            project_id,
+            team_id,
             labels: vec![],
             tokens: vec![],
+            layers: vec![],
+            grants: vec![],
+            role: None,
+            open_thread_count: None,
+            version: 0,
+            status: AnnotationStatus::Todo,
+            assigned_to: None,
+            completed_by: vec![],
+            suggestions: vec![],
+            rejected_suggestions: vec![],
+            created_at: Some(DateTime::now()),
+            updated_at: Some(DateTime::now()),
         };
 
         // create the text annotation
@@ -74,41 +129,95 @@ impl AnnotationController {
                 .set_error(annotation.err().unwrap().to_string().as_str()));
         }
 
-        Ok(annotation.unwrap().unwrap())
+        let annotation = annotation.unwrap().unwrap();
+
+        record_audit_event(
+            AuditEvent::from_auth(AuditAction::AnnotationCreated, &auth)
+                .set_annotation(&annotation),
+        );
+
+        // pre-annotated with the user's model backends in the background
+        queue_pre_annotation(&annotation);
+
+        queue_webhook_event(WebhookEvent::AnnotationCreated, &annotation, json!({}));
+
+        Ok(annotation)
     }
 
     pub fn update(
         id: String,
         auth: Option<UserAuthContext>,
         body: Json<UpdateTextAnnotationBody>,
+        version: Option<i64>,
     ) -> Result<TextAnnotation, ApiError> {
         if auth.is_none() {
             return Err(ApiError::new(StatusCode::UNAUTHORIZED)
                 .set_msg("you need to be signed in to update an annotation"));
         }
 
-        let doc_id = ObjectId::from_str(id.as_str());
+        let user_id = auth.as_ref().unwrap().user_id;
 
-        if doc_id.is_err() {
-            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
-                .set_msg("unable to convert id to object id"));
+        let (annotation, role) =
+            authorize_annotation(id, auth.as_ref().unwrap(), AnnotationRole::Editor)?;
+
+        ensure_version(&annotation, version, role, user_id)?;
+
+        // moving the document changes who can access it
+        let moved = body.project_id.is_some() || body.team_id.is_some();
+
+        if moved && role < AnnotationRole::Owner {
+            return Err(ApiError::new(StatusCode::FORBIDDEN)
+                .set_msg("only the owner can move the annotation to a project or a team"));
         }
 
-        // find annotation
-        let annotation_result = DB
-            .text_annotation_collection
-            .find_one(doc! {"_id": doc_id.as_ref().unwrap()}, None);
+        let doc_id = annotation._id;
+
+        let mut update_doc = doc! {
+          "$set": {
+            "title": body.title.to_owned(),
+            "updated_at": DateTime::now(),
+          },
+          "$inc": {"version": 1},
+        };
 
-        if annotation_result.as_ref().is_err() || annotation_result.as_ref().unwrap().is_none() {
-            return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("annotation was not found"));
+        let mut unset_doc = doc! {};
+
+        if body.project_id.is_some() {
+            let project_id = body.project_id.clone().unwrap();
+
+            if project_id.is_empty() {
+                unset_doc.insert("project_id", "");
+            } else {
+                let project = find_owned_project(project_id, auth.as_ref().unwrap())?;
+
+                update_doc
+                    .get_document_mut("$set")
+                    .unwrap()
+                    .insert("project_id", project._id.unwrap());
+            }
+        }
+
+        if let Some(team_id) = body.team_id.clone() {
+            if team_id.is_empty() {
+                unset_doc.insert("team_id", "");
+            } else {
+                let team = find_managed_team(team_id, auth.as_ref().unwrap())?;
+
+                update_doc
+                    .get_document_mut("$set")
+                    .unwrap()
+                    .insert("team_id", team._id.unwrap());
+            }
+        }
+
+        if !unset_doc.is_empty() {
+            update_doc.insert("$unset", unset_doc);
         }
 
         // create label
         let creation_result = DB.text_annotation_collection.find_one_and_update(
-            doc! {"_id": doc_id.as_ref().unwrap()},
-            doc! {"$set": {
-              "title": body.title.to_owned()
-            }},
+            doc! {"_id": doc_id.as_ref().unwrap(), "version": annotation.version},
+            update_doc,
             FindOneAndUpdateOptions::builder()
                 .return_document(ReturnDocument::After)
                 .build(),
@@ -123,120 +232,411 @@ impl AnnotationController {
         }
 
         if creation_result.as_ref().unwrap().is_none() {
-            return Err(
-                ApiError::new(StatusCode::INTERNAL_SERVER_ERROR).set_msg("unable to create label")
-            );
+            return Err(find_version_conflict(doc_id.unwrap(), role, user_id));
         }
 
         let updated_annotation = creation_result.unwrap().unwrap();
 
+        record_audit_event(
+            AuditEvent::from_auth(AuditAction::AnnotationUpdated, auth.as_ref().unwrap())
+                .set_annotation(&updated_annotation),
+        );
+
+        publish_annotation_event(
+            doc_id.unwrap(),
+            Some(user_id),
+            Some(updated_annotation.version),
+            RealtimeEvent::TitleUpdated {
+                title: updated_annotation.title.clone(),
+            },
+        );
+
         Ok(updated_annotation)
     }
 
-    pub fn get(id: String, auth: Option<UserAuthContext>) -> Result<TextAnnotation, ApiError> {
+    /// moves the annotation through its workflow, annotators can change it while
+    /// reviews are left to editors.
+    pub fn update_status(
+        id: String,
+        body: Json<UpdateAnnotationStatusBody>,
+        version: Option<i64>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<TextAnnotation, ApiError> {
         if auth.is_none() {
             return Err(ApiError::new(StatusCode::UNAUTHORIZED)
-                .set_msg("you need to be signed in to create an annotation"));
+                .set_msg("you need to be signed in to update an annotation status"));
         }
 
-        let doc_id = ObjectId::from_str(id.as_str());
+        let user_id = auth.as_ref().unwrap().user_id;
 
-        if doc_id.is_err() {
-            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
-                .set_msg("you need to be signed in to get this annotation"));
+        let (annotation, role) =
+            authorize_annotation(id, auth.as_ref().unwrap(), AnnotationRole::Annotator)?;
+
+        ensure_version(&annotation, version, role, user_id)?;
+
+        let doc_id = annotation._id;
+
+        // annotators cannot review their own work
+        let is_reviewer = role >= AnnotationRole::Editor;
+
+        let from = annotation.status;
+        let to = body.status;
+
+        // annotators finish their own layer, the document stays unfinished for the
+        // others until a reviewer marks it done
+        let layer = get_token_layer(role, user_id);
+
+        let reopens_layer = layer.is_some_and(|it| annotation.completed_by.contains(&it))
+            && from == AnnotationStatus::InProgress
+            && to == AnnotationStatus::InProgress;
+
+        if !reopens_layer && !is_status_transition_allowed(from, to) {
+            return Err(ApiError::new(StatusCode::CONFLICT).set_msg(
+                format!(
+                    "annotation status cannot go from ({}) to ({})",
+                    from.as_str(),
+                    to.as_str()
+                )
+                .as_str(),
+            ));
         }
 
-        // find annotation
-        let annotation_result = DB
-            .text_annotation_collection
-            .find_one(doc! {"_id": doc_id.unwrap()}, None);
+        if is_review_transition(from, to) && !is_reviewer {
+            return Err(ApiError::new(StatusCode::FORBIDDEN)
+                .set_msg("only a reviewer can review or reopen an annotation"));
+        }
+
+        let update_doc = match layer {
+            Some(layer) if to == AnnotationStatus::Done => doc! {
+              "$set": {"updated_at": DateTime::now()},
+              "$addToSet": {"completed_by": layer},
+              "$inc": {"version": 1},
+            },
+            Some(layer) => doc! {
+              "$set": {
+                "status": to_bson(&to).unwrap(),
+                "updated_at": DateTime::now(),
+              },
+              "$pull": {"completed_by": layer},
+              "$inc": {"version": 1},
+            },
+            None => doc! {
+              "$set": {
+                "status": to_bson(&to).unwrap(),
+                "updated_at": DateTime::now(),
+              },
+              "$inc": {"version": 1},
+            },
+        };
+
+        let update_result = DB.text_annotation_collection.find_one_and_update(
+            doc! {"_id": doc_id.unwrap(), "version": annotation.version},
+            update_doc,
+            FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build(),
+        );
+
+        if update_result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to update annotation status")
+                .set_error(update_result.err().unwrap().to_string().as_str()));
+        }
+
+        // the annotation was changed by someone else in the meantime
+        if update_result.as_ref().unwrap().is_none() {
+            return Err(find_version_conflict(doc_id.unwrap(), role, user_id));
+        }
+
+        let annotation = update_result.unwrap().unwrap();
+
+        record_audit_event(
+            AuditEvent::from_auth(AuditAction::AnnotationStatusUpdated, auth.as_ref().unwrap())
+                .set_annotation(&annotation)
+                .set_details(to.as_str()),
+        );
 
-        if annotation_result.as_ref().is_err() || annotation_result.as_ref().unwrap().is_none() {
-            return Err(ApiError::new(StatusCode::NOT_FOUND)
+        if annotation.status != from {
+            queue_webhook_event(
+                WebhookEvent::AnnotationStatusUpdated,
+                &annotation,
+                json!({"from": from, "to": to}),
+            );
+        }
+
+        // lets pipelines start once a document is finished
+        if to == AnnotationStatus::Done && layer.is_none() {
+            queue_webhook_event(WebhookEvent::AnnotationDone, &annotation, json!({}));
+        }
+
+        // completing a document, or a layer, frees it in the project queue
+        if to == AnnotationStatus::Done {
+            release_annotation_lease(&annotation, layer);
+        }
+
+        Ok(get_role_view(annotation, role, user_id))
+    }
+
+    pub fn get(id: String, auth: Option<UserAuthContext>) -> Result<TextAnnotation, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
                 .set_msg("you need to be signed in to get this annotation"));
         }
 
-        let annotation = annotation_result.unwrap().unwrap();
+        let auth = auth.unwrap();
 
-        Ok(annotation)
+        let (annotation, role) = authorize_annotation(id, &auth, AnnotationRole::Viewer)?;
+
+        Ok(get_role_view(annotation, role, auth.user_id))
     }
 
-    pub fn delete(id: String, auth: Option<UserAuthContext>) -> Result<Message, ApiError> {
+    pub fn delete(
+        id: String,
+        version: Option<i64>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Message, ApiError> {
         if auth.is_none() {
             return Err(ApiError::new(StatusCode::UNAUTHORIZED)
                 .set_msg("you need to be signed in to delete this annotation"));
         }
 
-        let doc_id = ObjectId::from_str(id.as_str());
+        let user_id = auth.as_ref().unwrap().user_id;
 
-        if doc_id.is_err() {
-            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
-                .set_msg("unable to convert id to object id"));
-        }
+        let (annotation, role) =
+            authorize_annotation(id, auth.as_ref().unwrap(), AnnotationRole::Owner)?;
 
-        // find annotation
-        let annotation_result = DB
-            .text_annotation_collection
-            .find_one(doc! {"_id": doc_id.as_ref().unwrap()}, None);
+        ensure_version(&annotation, version, role, user_id)?;
 
-        if annotation_result.as_ref().is_err() || annotation_result.as_ref().unwrap().is_none() {
-            return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("annotation was not found"));
-        }
+        let doc_id = annotation._id;
 
         // delete the annotation
-        let result = DB
-            .text_annotation_collection
-            .delete_one(doc! {"_id":doc_id.as_ref().unwrap()}, None);
+        let result = DB.text_annotation_collection.delete_one(
+            doc! {"_id":doc_id.as_ref().unwrap(), "version": annotation.version},
+            None,
+        );
 
         if result.is_err() {
             return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
                 .set_msg("unable to delete annotation"));
         }
 
+        if result.unwrap().deleted_count == 0 {
+            return Err(find_version_conflict(doc_id.unwrap(), role, user_id));
+        }
+
+        record_audit_event(
+            AuditEvent::from_auth(AuditAction::AnnotationDeleted, auth.as_ref().unwrap())
+                .set_annotation(&annotation),
+        );
+
+        queue_webhook_event(WebhookEvent::AnnotationDeleted, &annotation, json!({}));
+
+        delete_annotation_threads(doc_id.unwrap());
+
         Ok(Message::new().set_msg("annotation deleted successfully"))
     }
 
-    pub fn get_page(
-        query_params: web::Query<PaginationQueryParams>,
+    /// lists the user's documents with keyset pagination, cursors stay valid when
+    /// documents are added or removed.
+    pub fn list(
+        query_params: web::Query<ListAnnotationsQueryParams>,
         auth: Option<UserAuthContext>,
-    ) -> Result<Vec<TextAnnotation>, ApiError> {
+    ) -> Result<TextAnnotationList, ApiError> {
         if auth.is_none() {
             return Err(ApiError::new(StatusCode::UNAUTHORIZED)
-                .set_msg("you need to be signed in to delete this annotation"));
+                .set_msg("you need to be signed in to list annotations"));
         }
 
-        let user_id = auth.unwrap().user_id;
+        let auth = auth.unwrap();
 
-        let count = query_params.count;
-        let page = query_params.page;
+        let limit = query_params.limit.unwrap_or(DEFAULT_LIST_LIMIT);
 
-        let skip = (count * (page - 1)) as u64;
+        if !(1..=MAX_LIST_LIMIT).contains(&limit) {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY).set_msg(
+                format!("limit should be between (1) and ({})", MAX_LIST_LIMIT).as_str(),
+            ));
+        }
 
-        let fetch_result = DB.text_annotation_collection.find(
-            doc! {"user_id": user_id},
-            FindOptions::builder().limit(count).skip(skip).build(),
-        );
+        if query_params.after.is_some() && query_params.before.is_some() {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                .set_msg("after and before cursors cannot be used together"));
+        }
+
+        // owned documents and the ones shared with the user, directly, by their team
+        // or by a project they are granted or a member of
+        let teams = find_user_teams(auth.user_id);
+        let team_ids: Vec<Option<ObjectId>> = teams.iter().map(|it| it._id).collect();
+        let shared_projects = find_shared_projects(auth.user_id, &team_ids)?;
+
+        let mut filter = doc! {"$or": [
+          {"user_id": auth.user_id},
+          {"grants.user_id": auth.user_id},
+          {"team_id": {"$in": team_ids}},
+          {"project_id": {"$in": shared_projects.iter().map(|it| it._id).collect::<Vec<_>>()}},
+        ]};
+
+        if let Some(title) = query_params.title.clone() {
+            filter.insert(
+                "title",
+                doc! {"$regex": regex::escape(title.as_str()), "$options": "i"},
+            );
+        }
+
+        if let Some(has_tokens) = query_params.has_tokens {
+            filter.insert("tokens.0", doc! {"$exists": has_tokens});
+        }
 
-        if fetch_result.is_err() {
+        if let Some(label) = query_params.label.clone() {
+            filter.insert("$expr", has_label_expr(label.as_str()));
+        }
+
+        if let Some(project_id) = query_params.project_id.clone() {
+            let project = find_project(project_id)?;
+
+            // the project's documents are shared through a grant, a team or a membership
+            let is_shared = shared_projects.iter().any(|it| it._id == project._id);
+
+            if project.user_id != auth.user_id && !is_shared {
+                return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("project not found"));
+            }
+
+            filter.insert("project_id", project._id);
+        }
+
+        if let Some(status) = query_params.status {
+            filter.insert("status", to_bson(&status).unwrap());
+        }
+
+        let total = DB
+            .text_annotation_collection
+            .count_documents(filter.clone(), None);
+
+        if total.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to count annotations")
+                .set_error(total.err().unwrap().to_string().as_str()));
+        }
+
+        let sort = query_params.sort.unwrap_or(AnnotationSort::Created);
+        let field = match sort {
+            AnnotationSort::Title => Some("title"),
+            AnnotationSort::Created => None,
+            AnnotationSort::Updated => Some("updated_at"),
+            AnnotationSort::Tokens => Some("token_count"),
+        };
+
+        let backward = query_params.before.is_some();
+        // a previous page is read in the reverse order, then flipped back
+        let ascending = (query_params.order == Some(SortOrder::Asc)) != backward;
+        let direction = if ascending { 1 } else { -1 };
+
+        let mut pipeline = vec![
+            doc! {"$match": filter},
+            doc! {"$addFields": {"token_count": {"$size": "$tokens"}}},
+        ];
+
+        if let Some(cursor) = query_params.after.clone().or(query_params.before.clone()) {
+            pipeline.push(doc! {"$match": cursor_filter(sort, field, cursor.as_str(), ascending)?});
+        }
+
+        let mut sort_doc = doc! {};
+
+        if let Some(field) = field {
+            sort_doc.insert(field, direction);
+        }
+
+        sort_doc.insert("_id", direction);
+
+        pipeline.push(doc! {"$sort": sort_doc});
+        pipeline.push(doc! {"$limit": limit + 1});
+        pipeline.push(doc! {"$unset": "token_count"});
+
+        let cursor = DB.text_annotation_collection.aggregate(pipeline, None);
+
+        if cursor.is_err() {
             return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
                 .set_msg("unable to fetch annotations")
-                .set_error(fetch_result.is_err().to_string().as_str()));
+                .set_error(cursor.err().unwrap().to_string().as_str()));
         }
 
-        let mut items: Vec<TextAnnotation> = vec![];
+        let mut results: Vec<TextAnnotation> = cursor
+            .unwrap()
+            .filter_map(|it| it.ok())
+            .filter_map(|it| from_document(it).ok())
+            .collect();
 
-        fetch_result.unwrap().for_each(|item| {
-            if item.is_ok() {
-                items.push(item.unwrap());
-            }
-        });
+        let has_more = results.len() as i64 > limit;
+
+        results.truncate(limit as usize);
+
+        for item in results.iter_mut() {
+            let project = shared_projects.iter().find(|it| it._id == item.project_id);
+
+            item.role = resolve_annotation_role(item, project, &teams, auth.user_id);
+        }
+
+        let open_threads = count_open_threads(&results, auth.user_id);
+
+        for item in results.iter_mut() {
+            item.open_thread_count = Some(*open_threads.get(&item._id.unwrap()).unwrap_or(&0));
+        }
+
+        if backward {
+            results.reverse();
+        }
 
-        Ok(items)
+        let item_cursor = |item: &TextAnnotation| {
+            encode_cursor(&ListCursor {
+                id: item._id.unwrap().to_hex(),
+                value: match sort {
+                    AnnotationSort::Title => serde_json::json!(item.title),
+                    AnnotationSort::Created => serde_json::Value::Null,
+                    AnnotationSort::Updated => {
+                        serde_json::json!(item.updated_at.map(|it| it.timestamp_millis()))
+                    }
+                    AnnotationSort::Tokens => serde_json::json!(item.tokens.len()),
+                },
+            })
+        };
+
+        let (next_cursor, prev_cursor) = if backward {
+            (
+                results.last().map(item_cursor),
+                results.first().filter(|_| has_more).map(item_cursor),
+            )
+        } else {
+            (
+                results.last().filter(|_| has_more).map(item_cursor),
+                results
+                    .first()
+                    .filter(|_| query_params.after.is_some())
+                    .map(item_cursor),
+            )
+        };
+
+        // the cursors are computed on the stored tokens, before the annotators' layers
+        // replace them
+        let results = results
+            .into_iter()
+            .map(|item| {
+                let role = item.role.unwrap_or(AnnotationRole::Viewer);
+
+                get_role_view(item, role, auth.user_id)
+            })
+            .collect();
+
+        Ok(TextAnnotationList {
+            total: total.unwrap(),
+            results,
+            next_cursor,
+            prev_cursor,
+        })
     }
 
     pub fn create_label(
         annotation_id: String,
         body: Json<CreateLabelBody>,
+        version: Option<i64>,
         auth: Option<UserAuthContext>,
     ) -> Result<TextAnnotation, ApiError> {
         if auth.is_none() {
@@ -251,23 +651,17 @@ impl AnnotationController {
                 .set_msg("unable to convert annotation id to object id"));
         }
 
-        // find annotation
-        let annotation_result = DB
-            .text_annotation_collection
-            .find_one(doc! {"_id": object_id.as_ref().unwrap()}, None);
+        let user_id = auth.as_ref().unwrap().user_id;
 
-        if annotation_result.as_ref().is_err() || annotation_result.as_ref().unwrap().is_none() {
-            return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("annotation not found"));
-        }
+        let (annotation, role) = authorize_annotation(
+            annotation_id,
+            auth.as_ref().unwrap(),
+            AnnotationRole::Editor,
+        )?;
 
-        let annotation = annotation_result.unwrap().unwrap();
+        ensure_version(&annotation, version, role, user_id)?;
 
-        // check if owned by user
-        if annotation.user_id != auth.unwrap().user_id {
-            return Err(
-                ApiError::new(StatusCode::UNAUTHORIZED).set_msg("you cannot edit this annotation")
-            );
-        }
+        ensure_editable(&annotation)?;
 
         // check if we can add label
         let generated_color = get_next_valid_color(&annotation.labels);
@@ -282,6 +676,8 @@ impl AnnotationController {
             name: body.name.clone().to_owned(),
             color: "".to_string(),
             _id: Some(ObjectId::new()),
+            created_at: Some(DateTime::now()),
+            updated_at: Some(DateTime::now()),
         };
 
         let mut _color: Option<String> = None;
@@ -330,14 +726,20 @@ impl AnnotationController {
 
         // create label
         let creation_result = DB.text_annotation_collection.find_one_and_update(
-            doc! {"_id": object_id.as_ref().unwrap()},
-            doc! {"$push": {
-              "labels": {
-                "name": label.name,
-                "color": label.color,
-                "_id": label._id,
-              }
-            }},
+            doc! {"_id": object_id.as_ref().unwrap(), "version": annotation.version},
+            doc! {
+              "$push": {
+                "labels": {
+                  "name": label.name.clone(),
+                  "color": label.color.clone(),
+                  "_id": label._id,
+                  "created_at": label.created_at,
+                  "updated_at": label.updated_at,
+                }
+              },
+              "$set": {"updated_at": DateTime::now()},
+              "$inc": {"version": 1},
+            },
             FindOneAndUpdateOptions::builder()
                 .return_document(ReturnDocument::After)
                 .build(),
@@ -352,13 +754,32 @@ impl AnnotationController {
         }
 
         if creation_result.as_ref().unwrap().is_none() {
-            return Err(
-                ApiError::new(StatusCode::INTERNAL_SERVER_ERROR).set_msg("unable to create label")
-            );
+            return Err(find_version_conflict(
+                annotation._id.unwrap(),
+                role,
+                user_id,
+            ));
         }
 
         let updated_annotation = creation_result.unwrap().unwrap();
 
+        record_audit_event(
+            AuditEvent::from_auth(AuditAction::LabelCreated, auth.as_ref().unwrap())
+                .set_annotation(&updated_annotation)
+                .set_target(label._id),
+        );
+
+        let event = RealtimeEvent::LabelCreated { label };
+
+        queue_realtime_webhook_event(&updated_annotation, &event);
+
+        publish_annotation_event(
+            updated_annotation._id.unwrap(),
+            Some(user_id),
+            Some(updated_annotation.version),
+            event,
+        );
+
         Ok(updated_annotation)
     }
 
@@ -366,6 +787,7 @@ impl AnnotationController {
         body: Json<UpdateLabelBody>,
         auth: Option<UserAuthContext>,
         params: web::Path<(String, String)>,
+        version: Option<i64>,
     ) -> Result<TextAnnotation, ApiError> {
         if auth.is_none() {
             return Err(ApiError::new(StatusCode::UNAUTHORIZED)
@@ -387,22 +809,17 @@ impl AnnotationController {
                 .set_msg("unable to convert label id to object id"));
         }
 
-        // find annotation
-        let annotation_result = DB
-            .text_annotation_collection
-            .find_one(doc! {"_id": annotation_oid.as_ref().unwrap()}, None);
+        let user_id = auth.as_ref().unwrap().user_id;
 
-        if annotation_result.as_ref().is_err() || annotation_result.as_ref().unwrap().is_none() {
-            return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("annotation not found"));
-        }
+        let (annotation, role) = authorize_annotation(
+            annotation_id,
+            auth.as_ref().unwrap(),
+            AnnotationRole::Editor,
+        )?;
 
-        let annotation = annotation_result.unwrap().unwrap();
+        ensure_version(&annotation, version, role, user_id)?;
 
-        // check if owned by user
-        if annotation.user_id != auth.unwrap().user_id {
-            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
-                .set_msg("you cannot update this annotation at the moment"));
-        }
+        ensure_editable(&annotation)?;
 
         // check if label exist
         let exists = annotation
@@ -414,7 +831,10 @@ impl AnnotationController {
             return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("label not found"));
         }
 
-        let mut update_doc = doc! {};
+        let mut update_doc = doc! {
+          "labels.$.updated_at": DateTime::now(),
+          "updated_at": DateTime::now(),
+        };
 
         if body.name.is_some() {
             // check if name already exists
@@ -454,28 +874,65 @@ impl AnnotationController {
         // update the label
         let update_result = DB.text_annotation_collection.find_one_and_update(
             doc! {
-              "_id":annotation_oid.as_ref().unwrap(),"labels._id": label_oid.unwrap()
+              "_id":annotation_oid.as_ref().unwrap(),
+              "labels._id": label_oid.clone().unwrap(),
+              "version": annotation.version,
             },
-            doc! {"$set":update_doc},
+            doc! {"$set":update_doc, "$inc": {"version": 1}},
             FindOneAndUpdateOptions::builder()
                 .return_document(ReturnDocument::After)
                 .build(),
         );
 
-        if update_result.as_ref().is_err() || update_result.as_ref().unwrap().is_none() {
+        if update_result.as_ref().is_err() {
             return Err(
-                ApiError::new(StatusCode::INTERNAL_SERVER_ERROR).set_msg("unable to create label")
+                ApiError::new(StatusCode::INTERNAL_SERVER_ERROR).set_msg("unable to update label")
             );
         }
 
+        if update_result.as_ref().unwrap().is_none() {
+            return Err(find_version_conflict(
+                annotation._id.unwrap(),
+                role,
+                user_id,
+            ));
+        }
+
         let updated_annotation = update_result.unwrap().unwrap();
 
+        record_audit_event(
+            AuditEvent::from_auth(AuditAction::LabelUpdated, auth.as_ref().unwrap())
+                .set_annotation(&updated_annotation)
+                .set_target(label_oid.clone().ok()),
+        );
+
+        let label = updated_annotation
+            .labels
+            .iter()
+            .find(|item| item._id == Some(label_oid.clone().unwrap()));
+
+        if let Some(label) = label {
+            let event = RealtimeEvent::LabelUpdated {
+                label: label.clone(),
+            };
+
+            queue_realtime_webhook_event(&updated_annotation, &event);
+
+            publish_annotation_event(
+                updated_annotation._id.unwrap(),
+                Some(user_id),
+                Some(updated_annotation.version),
+                event,
+            );
+        }
+
         Ok(updated_annotation)
     }
 
     pub fn delete_label(
         auth: Option<UserAuthContext>,
         params: web::Path<(String, String)>,
+        version: Option<i64>,
     ) -> Result<TextAnnotation, ApiError> {
         let (annotation_id, label_id) = params.into_inner();
 
@@ -497,22 +954,17 @@ impl AnnotationController {
                 .set_msg("unable to convert label id to object id"));
         }
 
-        // find annotation
-        let annotation_result = DB
-            .text_annotation_collection
-            .find_one(doc! {"_id": annotation_oid.as_ref().unwrap()}, None);
+        let user_id = auth.as_ref().unwrap().user_id;
 
-        if annotation_result.as_ref().is_err() || annotation_result.as_ref().unwrap().is_none() {
-            return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("annotation not found"));
-        }
+        let (annotation, role) = authorize_annotation(
+            annotation_id,
+            auth.as_ref().unwrap(),
+            AnnotationRole::Editor,
+        )?;
 
-        let annotation = annotation_result.unwrap().unwrap();
+        ensure_version(&annotation, version, role, user_id)?;
 
-        // check if owned by user
-        if annotation.user_id != auth.unwrap().user_id {
-            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
-                .set_msg("you cannot delete this annotation at the moment"));
-        }
+        ensure_editable(&annotation)?;
 
         // check if label exist
         let exists = annotation
@@ -527,7 +979,8 @@ impl AnnotationController {
         // delete label
         let update_result = DB.text_annotation_collection.find_one_and_update(
             doc! {
-              "_id":annotation_oid.as_ref().unwrap()
+              "_id":annotation_oid.as_ref().unwrap(),
+              "version": annotation.version,
             },
             doc! {
               "$pull":{
@@ -536,28 +989,62 @@ impl AnnotationController {
                 },
                 "tokens": {
                   "label":{ "$in":[ label_oid.clone().unwrap() ] }
+                },
+                // the annotators' layers lose the label's tokens as well
+                "layers.$[].tokens": {
+                  "label":{ "$in":[ label_oid.clone().unwrap() ] }
                 }
-              }
+              },
+              "$set": {"updated_at": DateTime::now()},
+              "$inc": {"version": 1},
             },
             FindOneAndUpdateOptions::builder()
                 .return_document(ReturnDocument::After)
                 .build(),
         );
 
-        if update_result.as_ref().is_err() || update_result.as_ref().unwrap().is_none() {
+        if update_result.as_ref().is_err() {
             return Err(
                 ApiError::new(StatusCode::INTERNAL_SERVER_ERROR).set_msg("unable to delete label")
             );
         }
 
+        if update_result.as_ref().unwrap().is_none() {
+            return Err(find_version_conflict(
+                annotation._id.unwrap(),
+                role,
+                user_id,
+            ));
+        }
+
         let updated_annotation = update_result.unwrap().unwrap();
 
+        record_audit_event(
+            AuditEvent::from_auth(AuditAction::LabelDeleted, auth.as_ref().unwrap())
+                .set_annotation(&updated_annotation)
+                .set_target(label_oid.clone().ok()),
+        );
+
+        let event = RealtimeEvent::LabelDeleted {
+            label_id: label_oid.unwrap(),
+        };
+
+        queue_realtime_webhook_event(&updated_annotation, &event);
+
+        publish_annotation_event(
+            updated_annotation._id.unwrap(),
+            Some(user_id),
+            Some(updated_annotation.version),
+            event,
+        );
+
         Ok(updated_annotation)
     }
 
     pub fn create_token(
         annotation_id: String,
         body: Json<CreateTokenBody>,
+        version: Option<i64>,
         auth: Option<UserAuthContext>,
     ) -> Result<TextAnnotation, ApiError> {
         if auth.is_none() {
@@ -572,22 +1059,20 @@ impl AnnotationController {
                 .set_msg("unable to convert annotation id to object id"));
         }
 
-        // find annotation
-        let annotation_result = DB
-            .text_annotation_collection
-            .find_one(doc! {"_id": object_id.as_ref().unwrap()}, None);
+        let user_id = auth.as_ref().unwrap().user_id;
 
-        if annotation_result.as_ref().is_err() || annotation_result.as_ref().unwrap().is_none() {
-            return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("annotation not found"));
-        }
+        let (annotation, role) = authorize_annotation(
+            annotation_id,
+            auth.as_ref().unwrap(),
+            AnnotationRole::Annotator,
+        )?;
 
-        let annotation = annotation_result.unwrap().unwrap();
+        ensure_version(&annotation, version, role, user_id)?;
 
-        // check if owned by user
-        if annotation.user_id != auth.unwrap().user_id {
-            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
-                .set_msg("you cannot update this annotation at the moment"));
-        };
+        // annotators write in their own layer
+        let layer = get_token_layer(role, user_id);
+
+        ensure_editable(&annotation)?;
 
         // find label
         let label_oid = ObjectId::from_str(body.label.as_str());
@@ -610,64 +1095,100 @@ impl AnnotationController {
         let start = body.start.to_owned();
         let end = body.end.to_owned();
 
-        // check start > end
-        if end <= start {
-            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
-                .set_msg("token start's index cannot be greater than the end's"));
-        }
+        let layer_view = match layer {
+            Some(layer) => get_layer_view(annotation.clone(), layer),
+            None => annotation.clone(),
+        };
 
-        if start.is_negative() {
-            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
-                .set_msg("token start cannot be negative"));
-        }
+        let span_validation = validate_token_span(start, end, &layer_view);
 
-        if end >= (annotation.content.len() as i64) {
+        if span_validation.is_err() {
             return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
-                .set_msg("end token cannot be superior to the length of the content"));
+                .set_msg(span_validation.err().unwrap().description.as_str()));
         }
 
-        // check if some tokens are interlacing with this one
-        let interlacing_tokens = annotation.tokens.iter().filter(|token| {
-            return (start <= token.start && token.start <= end)
-                || (start <= token.end && token.end <= end);
-        });
-
-        if interlacing_tokens.count() != 0 {
-            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
-                .set_msg("token is interlacing with an existing one"));
-        }
+        let token = Token {
+            _id: Some(ObjectId::new()),
+            start,
+            end,
+            label: label_oid.clone().unwrap(),
+            created_at: Some(DateTime::now()),
+            created_by: Some(user_id),
+        };
 
-        let doc = doc! {
-          "_id": ObjectId::new(),
-          "start": start,
-          "end": end,
-          "label": label_oid.clone().unwrap()
+        let doc = to_bson(&token).unwrap();
+
+        let (mut filter, push) = match layer {
+            None => (
+                doc! {"_id": object_id.as_ref().unwrap()},
+                doc! {"tokens": doc},
+            ),
+            Some(layer) if annotation.layers.iter().any(|it| it.user_id == layer) => (
+                doc! {"_id": object_id.as_ref().unwrap(), "layers.user_id": layer},
+                doc! {"layers.$.tokens": doc},
+            ),
+            Some(layer) => (
+                doc! {"_id": object_id.as_ref().unwrap(), "layers.user_id": {"$ne": layer}},
+                doc! {"layers": {"user_id": layer, "tokens": [doc]}},
+            ),
         };
 
+        filter.insert("version", annotation.version);
+
         // create token
-        // create label
         let creation_result = DB.text_annotation_collection.find_one_and_update(
-            doc! {"_id": object_id.as_ref().unwrap()},
-            doc! {"$push": {
-              "tokens": doc
-            }},
+            filter,
+            doc! {
+              "$push": push,
+              "$set": {"updated_at": DateTime::now()},
+              "$inc": {"version": 1},
+            },
             FindOneAndUpdateOptions::builder()
                 .return_document(ReturnDocument::After)
                 .build(),
         );
 
-        if creation_result.as_ref().is_err() || creation_result.as_ref().unwrap().is_none() {
+        if creation_result.as_ref().is_err() {
             return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
                 .set_msg("unable to create annotation token"));
         }
 
+        if creation_result.as_ref().unwrap().is_none() {
+            return Err(find_version_conflict(
+                annotation._id.unwrap(),
+                role,
+                user_id,
+            ));
+        }
+
         let updated_annotation = creation_result.unwrap().unwrap();
 
-        Ok(updated_annotation)
+        record_audit_event(
+            AuditEvent::from_auth(AuditAction::TokenCreated, auth.as_ref().unwrap())
+                .set_annotation(&updated_annotation)
+                .set_target(token._id),
+        );
+
+        let event = RealtimeEvent::TokenCreated { token, layer };
+
+        queue_realtime_webhook_event(&updated_annotation, &event);
+
+        publish_annotation_event(
+            updated_annotation._id.unwrap(),
+            Some(user_id),
+            Some(updated_annotation.version),
+            event,
+        );
+
+        Ok(match layer {
+            Some(layer) => get_layer_view(updated_annotation, layer),
+            None => updated_annotation,
+        })
     }
 
     pub fn delete_token(
         params: web::Path<(String, String)>,
+        version: Option<i64>,
         auth: Option<UserAuthContext>,
     ) -> Result<TextAnnotation, ApiError> {
         let (annotation_id, token_id) = params.into_inner();
@@ -690,25 +1211,28 @@ impl AnnotationController {
             return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
                 .set_msg("unable to convert token id to object id"));
         }
-        // find annotation
-        let annotation_result = DB
-            .text_annotation_collection
-            .find_one(doc! {"_id": annotation_oid.as_ref().unwrap()}, None);
+        let user_id = auth.as_ref().unwrap().user_id;
 
-        if annotation_result.as_ref().is_err() || annotation_result.as_ref().unwrap().is_none() {
-            return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("annotation not found"));
-        }
+        let (annotation, role) = authorize_annotation(
+            annotation_id,
+            auth.as_ref().unwrap(),
+            AnnotationRole::Annotator,
+        )?;
 
-        let annotation = annotation_result.unwrap().unwrap();
+        ensure_version(&annotation, version, role, user_id)?;
 
-        // check if owned by user
-        if annotation.user_id != auth.unwrap().user_id {
-            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
-                .set_msg("you cannot update annotation at the moment"));
+        // annotators write in their own layer
+        let layer = get_token_layer(role, user_id);
+
+        ensure_editable(&annotation)?;
+
+        let layer_view = match layer {
+            Some(layer) => get_layer_view(annotation.clone(), layer),
+            None => annotation.clone(),
         };
 
         // find token
-        let token = annotation
+        let token = layer_view
             .tokens
             .iter()
             .find(|t| t._id.clone().unwrap() == token_oid.clone().unwrap());
@@ -717,31 +1241,236 @@ impl AnnotationController {
             return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("token not found"));
         }
 
-        // delete label
+        let (mut filter, pull) = match layer {
+            Some(layer) => (
+                doc! {"_id": annotation_oid.as_ref().unwrap(), "layers.user_id": layer},
+                doc! {"layers.$.tokens": {"_id": token_oid.clone().unwrap()}},
+            ),
+            None => (
+                doc! {"_id": annotation_oid.as_ref().unwrap()},
+                doc! {"tokens": {"_id": {"$in": [token_oid.clone().unwrap()]}}},
+            ),
+        };
+
+        filter.insert("version", annotation.version);
+
+        // delete token
         let update_result = DB.text_annotation_collection.find_one_and_update(
+            filter,
             doc! {
-              "_id":annotation_oid.as_ref().unwrap()
-            },
-            doc! {
-              "$pull":{
-                "tokens": {
-                  "_id":{ "$in": [token_oid.unwrap()]  }
-                }
-              }
+              "$pull": pull,
+              "$set": {"updated_at": DateTime::now()},
+              "$inc": {"version": 1},
             },
             FindOneAndUpdateOptions::builder()
                 .return_document(ReturnDocument::After)
                 .build(),
         );
 
-        if update_result.as_ref().is_err() || update_result.as_ref().unwrap().is_none() {
+        if update_result.as_ref().is_err() {
             return Err(
                 ApiError::new(StatusCode::INTERNAL_SERVER_ERROR).set_msg("unable to delete token")
             );
         }
 
+        if update_result.as_ref().unwrap().is_none() {
+            return Err(find_version_conflict(
+                annotation._id.unwrap(),
+                role,
+                user_id,
+            ));
+        }
+
         let updated_annotation = update_result.unwrap().unwrap();
 
-        Ok(updated_annotation)
+        record_audit_event(
+            AuditEvent::from_auth(AuditAction::TokenDeleted, auth.as_ref().unwrap())
+                .set_annotation(&updated_annotation)
+                .set_target(token_oid.clone().ok()),
+        );
+
+        let event = RealtimeEvent::TokenDeleted {
+            token_id: token_oid.unwrap(),
+            layer,
+        };
+
+        queue_realtime_webhook_event(&updated_annotation, &event);
+
+        publish_annotation_event(
+            updated_annotation._id.unwrap(),
+            Some(user_id),
+            Some(updated_annotation.version),
+            event,
+        );
+
+        Ok(match layer {
+            Some(layer) => get_layer_view(updated_annotation, layer),
+            None => updated_annotation,
+        })
     }
+
+    /// lists the annotators' layers side by side, for the owner and the reviewers.
+    pub fn get_layers(
+        id: String,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Vec<AnnotationLayer>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to get the annotation layers"));
+        }
+
+        let (annotation, _) = authorize_annotation(id, &auth.unwrap(), AnnotationRole::Editor)?;
+
+        Ok(annotation.layers)
+    }
+}
+
+/// projects whose documents are shared with the user or with their teams, and the
+/// projects the user is a member of.
+fn find_shared_projects(
+    user_id: ObjectId,
+    team_ids: &[Option<ObjectId>],
+) -> Result<Vec<Project>, ApiError> {
+    let fetch_result = DB.project_collection.find(
+        doc! {"$or": [
+          {"grants.user_id": user_id},
+          {"team_id": {"$in": team_ids}},
+          {"members.user_id": user_id},
+        ]},
+        None,
+    );
+
+    if fetch_result.is_err() {
+        return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .set_msg("unable to fetch shared projects")
+            .set_error(fetch_result.err().unwrap().to_string().as_str()));
+    }
+
+    let items: Vec<Project> = fetch_result.unwrap().filter_map(|it| it.ok()).collect();
+
+    Ok(items)
+}
+
+/// editors write the document's tokens, annotators write their own layer.
+pub fn get_token_layer(role: AnnotationRole, user_id: ObjectId) -> Option<ObjectId> {
+    match role {
+        AnnotationRole::Editor | AnnotationRole::Owner => None,
+        _ => Some(user_id),
+    }
+}
+
+/// shapes the annotation for what the role is allowed to see.
+pub fn get_role_view(
+    mut annotation: TextAnnotation,
+    role: AnnotationRole,
+    user_id: ObjectId,
+) -> TextAnnotation {
+    annotation.role = Some(role);
+
+    // only the owner manages who the document is shared with
+    if role != AnnotationRole::Owner {
+        annotation.grants = vec![];
+    }
+
+    match role {
+        // annotators only see their own layer
+        AnnotationRole::Annotator => get_layer_view(annotation, user_id),
+        AnnotationRole::Viewer => {
+            annotation.layers = vec![];
+
+            annotation
+        }
+        _ => annotation,
+    }
+}
+
+/// checks the change was made against the current version of the annotation.
+pub fn ensure_version(
+    annotation: &TextAnnotation,
+    version: Option<i64>,
+    role: AnnotationRole,
+    user_id: ObjectId,
+) -> Result<(), ApiError> {
+    if version.is_none() {
+        return Err(ApiError::new(StatusCode::PRECONDITION_REQUIRED).set_msg(
+            "annotation version is required, send it in the if-match header or the version field",
+        ));
+    }
+
+    if version != Some(annotation.version) {
+        return Err(version_conflict(annotation.clone(), role, user_id));
+    }
+
+    Ok(())
+}
+
+/// the error holds the current document so the client can redo its change on top of it.
+fn version_conflict(
+    annotation: TextAnnotation,
+    role: AnnotationRole,
+    user_id: ObjectId,
+) -> ApiError {
+    let current = get_role_view(annotation, role, user_id);
+
+    ApiError::new(StatusCode::PRECONDITION_FAILED)
+        .set_msg("annotation has changed since it was loaded")
+        .set_data(serde_json::to_value(&current).unwrap())
+}
+
+/// error of a write that lost the race against another one.
+pub fn find_version_conflict(
+    annotation_id: ObjectId,
+    role: AnnotationRole,
+    user_id: ObjectId,
+) -> ApiError {
+    let annotation_result = DB
+        .text_annotation_collection
+        .find_one(doc! {"_id": annotation_id}, None);
+
+    match annotation_result {
+        Ok(Some(annotation)) => version_conflict(annotation, role, user_id),
+        _ => ApiError::new(StatusCode::NOT_FOUND).set_msg("annotation not found"),
+    }
+}
+
+/// labels and tokens of a reviewed annotation cannot change until it is reopened.
+pub fn ensure_editable(annotation: &TextAnnotation) -> Result<(), ApiError> {
+    if annotation.status == AnnotationStatus::Reviewed {
+        return Err(ApiError::new(StatusCode::CONFLICT)
+            .set_msg("annotation is reviewed, it needs to be reopened to be edited"));
+    }
+
+    Ok(())
+}
+
+/// matches the items placed after the cursor in the given direction, ties on the
+/// sort field are broken by the id.
+fn cursor_filter(
+    sort: AnnotationSort,
+    field: Option<&str>,
+    cursor: &str,
+    ascending: bool,
+) -> Result<Document, ApiError> {
+    let invalid_cursor =
+        || ApiError::new(StatusCode::UNPROCESSABLE_ENTITY).set_msg("invalid cursor");
+
+    let cursor = decode_cursor(cursor).map_err(|_| invalid_cursor())?;
+    let id = ObjectId::from_str(cursor.id.as_str()).map_err(|_| invalid_cursor())?;
+
+    let operator = if ascending { "$gt" } else { "$lt" };
+
+    let Some(field) = field else {
+        return Ok(doc! {"_id": {operator: id}});
+    };
+
+    // dates are kept as milliseconds in the cursor
+    let value = match (sort, cursor.value.as_i64()) {
+        (AnnotationSort::Updated, Some(millis)) => Bson::DateTime(DateTime::from_millis(millis)),
+        _ => to_bson(&cursor.value).map_err(|_| invalid_cursor())?,
+    };
+
+    Ok(doc! {"$or": [
+      {field: {operator: value.clone()}},
+      {field: value, "_id": {operator: id}},
+    ]})
 }
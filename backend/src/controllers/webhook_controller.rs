@@ -0,0 +1,349 @@
+use std::str::FromStr;
+
+use actix_web::{
+    http::StatusCode,
+    web::{self, Json},
+};
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, DateTime},
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
+};
+
+use crate::{
+    controllers::project_controller::find_owned_project,
+    database::mongodb::DB,
+    helpers::webhook_helpers::{claim_redelivery, deliver, generate_webhook_secret},
+    middleware::auth_middleware::UserAuthContext,
+    models::webhook_model::{
+        CreateWebhookBody, DeliveriesQueryParams, DeliveryStatus, UpdateWebhookBody, Webhook,
+        WebhookDelivery,
+    },
+    object::{common::Message, error::ApiError},
+};
+
+static DEFAULT_DELIVERIES_LIMIT: i64 = 50;
+static MAX_DELIVERIES_LIMIT: i64 = 200;
+
+static MIN_SECRET_LENGTH: usize = 16;
+
+pub struct WebhookController;
+
+impl WebhookController {
+    pub fn create(
+        body: Json<CreateWebhookBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Webhook, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to create a webhook"));
+        }
+
+        let auth = auth.unwrap();
+
+        // project webhooks are notified of every document of the project
+        let project_id = match body.project_id.clone() {
+            Some(project_id) => find_owned_project(project_id, &auth)?._id,
+            None => None,
+        };
+
+        let secret = match body.secret.clone() {
+            Some(secret) => secret,
+            None => generate_webhook_secret().map_err(|err| {
+                ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                    .set_msg("unable to create webhook")
+                    .set_error(err.description.as_str())
+            })?,
+        };
+
+        let webhook = Webhook {
+            _id: None,
+            user_id: auth.user_id,
+            project_id,
+            name: body.name.trim().to_string(),
+            url: body.url.trim().to_string(),
+            secret,
+            events: body.events.clone(),
+            active: body.active.unwrap_or(true),
+            created_at: DateTime::now(),
+        };
+
+        validate_webhook(&webhook)?;
+
+        let result = DB.webhook_collection.insert_one(webhook, None);
+
+        if result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to create webhook")
+                .set_error(result.err().unwrap().to_string().as_str()));
+        }
+
+        let id = result.unwrap().inserted_id;
+
+        let webhook = DB.webhook_collection.find_one(doc! {"_id": id}, None);
+
+        if webhook.as_ref().is_err() || webhook.as_ref().unwrap().is_none() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to retrieve created webhook"));
+        }
+
+        Ok(webhook.unwrap().unwrap())
+    }
+
+    pub fn get_all(auth: Option<UserAuthContext>) -> Result<Vec<Webhook>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to list your webhooks"));
+        }
+
+        let fetch_result = DB
+            .webhook_collection
+            .find(doc! {"user_id": auth.unwrap().user_id}, None);
+
+        if fetch_result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to fetch webhooks"));
+        }
+
+        let items: Vec<Webhook> = fetch_result.unwrap().filter_map(|it| it.ok()).collect();
+
+        Ok(items)
+    }
+
+    pub fn update(
+        id: String,
+        body: Json<UpdateWebhookBody>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Webhook, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to update a webhook"));
+        }
+
+        let mut webhook = find_owned_webhook(id, &auth.unwrap())?;
+
+        if let Some(name) = &body.name {
+            webhook.name = name.trim().to_string();
+        }
+
+        if let Some(url) = &body.url {
+            webhook.url = url.trim().to_string();
+        }
+
+        if let Some(events) = &body.events {
+            webhook.events = events.clone();
+        }
+
+        if let Some(secret) = &body.secret {
+            webhook.secret = secret.clone();
+        }
+
+        if let Some(active) = body.active {
+            webhook.active = active;
+        }
+
+        validate_webhook(&webhook)?;
+
+        let update_result = DB.webhook_collection.find_one_and_update(
+            doc! {"_id": webhook._id.unwrap()},
+            doc! {"$set": {
+              "name": webhook.name.clone(),
+              "url": webhook.url.clone(),
+              "events": to_bson(&webhook.events).unwrap(),
+              "secret": webhook.secret.clone(),
+              "active": webhook.active,
+            }},
+            FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build(),
+        );
+
+        if update_result.as_ref().is_err() || update_result.as_ref().unwrap().is_none() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to update webhook"));
+        }
+
+        Ok(update_result.unwrap().unwrap())
+    }
+
+    pub fn delete(id: String, auth: Option<UserAuthContext>) -> Result<Message, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to delete a webhook"));
+        }
+
+        let webhook = find_owned_webhook(id, &auth.unwrap())?;
+
+        let result = DB
+            .webhook_collection
+            .delete_one(doc! {"_id": webhook._id.unwrap()}, None);
+
+        if result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to delete webhook"));
+        }
+
+        // the pending deliveries are dropped with the log
+        let deliveries_result = DB
+            .webhook_delivery_collection
+            .delete_many(doc! {"webhook_id": webhook._id.unwrap()}, None);
+
+        if let Err(err) = deliveries_result {
+            log::warn!("unable to delete the webhook deliveries: {}", err);
+        }
+
+        Ok(Message::new().set_msg("webhook deleted successfully"))
+    }
+
+    /// lists the webhook's deliveries, newest first.
+    pub fn get_deliveries(
+        id: String,
+        query_params: web::Query<DeliveriesQueryParams>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<Vec<WebhookDelivery>, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to list the webhook deliveries"));
+        }
+
+        let webhook = find_owned_webhook(id, &auth.unwrap())?;
+
+        let limit = query_params.limit.unwrap_or(DEFAULT_DELIVERIES_LIMIT);
+
+        if !(1..=MAX_DELIVERIES_LIMIT).contains(&limit) {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY).set_msg(
+                format!("limit should be between (1) and ({})", MAX_DELIVERIES_LIMIT).as_str(),
+            ));
+        }
+
+        let mut filter = doc! {"webhook_id": webhook._id.unwrap()};
+
+        if let Some(status) = query_params.status {
+            filter.insert("status", to_bson(&status).unwrap());
+        }
+
+        let fetch_result = DB.webhook_delivery_collection.find(
+            filter,
+            FindOptions::builder()
+                .sort(doc! {"_id": -1})
+                .limit(limit)
+                .build(),
+        );
+
+        if fetch_result.is_err() {
+            return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .set_msg("unable to fetch webhook deliveries"));
+        }
+
+        let items: Vec<WebhookDelivery> = fetch_result.unwrap().filter_map(|it| it.ok()).collect();
+
+        Ok(items)
+    }
+
+    /// sends a finished delivery again right away, the same payload is signed with the
+    /// current secret.
+    pub fn redeliver(
+        params: web::Path<(String, String)>,
+        auth: Option<UserAuthContext>,
+    ) -> Result<WebhookDelivery, ApiError> {
+        if auth.is_none() {
+            return Err(ApiError::new(StatusCode::UNAUTHORIZED)
+                .set_msg("you need to be signed in to redeliver a webhook delivery"));
+        }
+
+        let (webhook_id, delivery_id) = params.into_inner();
+
+        let webhook = find_owned_webhook(webhook_id, &auth.unwrap())?;
+
+        if !webhook.active {
+            return Err(ApiError::new(StatusCode::CONFLICT)
+                .set_msg("webhook needs to be active to redeliver"));
+        }
+
+        let delivery_id = ObjectId::from_str(delivery_id.as_str());
+
+        if delivery_id.is_err() {
+            return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+                .set_msg("unable to convert delivery id to object id"));
+        }
+
+        let delivery = DB.webhook_delivery_collection.find_one(
+            doc! {"_id": delivery_id.unwrap(), "webhook_id": webhook._id.unwrap()},
+            None,
+        );
+
+        if delivery.as_ref().is_err() || delivery.as_ref().unwrap().is_none() {
+            return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("delivery not found"));
+        }
+
+        let delivery = delivery.unwrap().unwrap();
+
+        if delivery.status == DeliveryStatus::Pending {
+            return Err(ApiError::new(StatusCode::CONFLICT).set_msg("delivery is already pending"));
+        }
+
+        // the worker or another request may have claimed it in the meantime
+        let delivery = claim_redelivery(&delivery);
+
+        if delivery.is_none() {
+            return Err(ApiError::new(StatusCode::CONFLICT).set_msg("delivery is already pending"));
+        }
+
+        Ok(deliver(&webhook, delivery.unwrap()))
+    }
+}
+
+fn find_owned_webhook(id: String, auth: &UserAuthContext) -> Result<Webhook, ApiError> {
+    let object_id = ObjectId::from_str(id.as_str());
+
+    if object_id.is_err() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("unable to convert webhook id to object id"));
+    }
+
+    let webhook_result = DB
+        .webhook_collection
+        .find_one(doc! {"_id": object_id.unwrap()}, None);
+
+    if webhook_result.as_ref().is_err() || webhook_result.as_ref().unwrap().is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("webhook not found"));
+    }
+
+    let webhook = webhook_result.unwrap().unwrap();
+
+    if webhook.user_id != auth.user_id {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("webhook not found"));
+    }
+
+    Ok(webhook)
+}
+
+fn validate_webhook(webhook: &Webhook) -> Result<(), ApiError> {
+    let mut validation: Vec<String> = vec![];
+
+    if webhook.name.is_empty() || webhook.name.len() > 50 {
+        validation.push("\"name\": value length should be between (1) and (50)".to_string());
+    }
+
+    if !webhook.url.starts_with("http://") && !webhook.url.starts_with("https://") {
+        validation.push("\"url\": value is not an http url".to_string());
+    }
+
+    if webhook.events.is_empty() {
+        validation.push("\"events\": at least one event is required".to_string());
+    }
+
+    if webhook.secret.len() < MIN_SECRET_LENGTH {
+        validation.push(format!(
+            "\"secret\": value length should be at least ({})",
+            MIN_SECRET_LENGTH
+        ));
+    }
+
+    if !validation.is_empty() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("failed to validate body")
+            .set_validation(validation));
+    }
+
+    Ok(())
+}
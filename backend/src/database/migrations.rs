@@ -0,0 +1,171 @@
+use mongodb::bson::{doc, from_bson, oid::ObjectId, Document};
+
+use crate::{
+    database::mongodb::DB, helpers::tagger_helpers::save_tagger_weights,
+    models::tagger_model::TaggerWeight,
+};
+
+/// brings the stored documents up to date with the models, every migration only
+/// touches the documents it has not been applied to so it is safe to run on startup.
+pub fn run_migrations() {
+    backfill_timestamps();
+    backfill_status();
+    backfill_version();
+    move_tagger_weights();
+}
+
+/// documents created before timestamps existed get the creation time of their id.
+fn backfill_timestamps() {
+    let id_date = |id: &str| doc! {"$toDate": id};
+    let keep_or = |field: &str, default: Document| doc! {"$ifNull": [field, default]};
+
+    let annotations_result = DB.text_annotation_collection.update_many(
+        doc! {"$or": [
+          {"created_at": null},
+          {"updated_at": null},
+          {"labels": {"$elemMatch": {"created_at": null}}},
+          {"tokens": {"$elemMatch": {"created_at": null}}},
+        ]},
+        vec![doc! {"$set": {
+          "created_at": keep_or("$created_at", id_date("$_id")),
+          "updated_at": keep_or("$updated_at", id_date("$_id")),
+          "labels": {"$map": {
+            "input": "$labels",
+            "as": "label",
+            "in": {"$mergeObjects": ["$$label", {
+              "created_at": keep_or("$$label.created_at", id_date("$$label._id")),
+              "updated_at": keep_or("$$label.updated_at", id_date("$$label._id")),
+            }]},
+          }},
+          "tokens": {"$map": {
+            "input": "$tokens",
+            "as": "token",
+            "in": {"$mergeObjects": ["$$token", {
+              "created_at": keep_or("$$token.created_at", id_date("$$token._id")),
+              "created_by": {"$ifNull": ["$$token.created_by", "$user_id"]},
+            }]},
+          }},
+        }}],
+        None,
+    );
+
+    match annotations_result {
+        Ok(result) => log::info!(
+            "timestamps backfilled on {} annotations",
+            result.modified_count
+        ),
+        Err(err) => log::error!("unable to backfill annotations timestamps: {}", err),
+    }
+
+    let users_result = DB.user_collection.update_many(
+        doc! {"$or": [{"created_at": null}, {"updated_at": null}]},
+        vec![doc! {"$set": {
+          "created_at": keep_or("$created_at", id_date("$_id")),
+          "updated_at": keep_or("$updated_at", id_date("$_id")),
+        }}],
+        None,
+    );
+
+    match users_result {
+        Ok(result) => log::info!("timestamps backfilled on {} users", result.modified_count),
+        Err(err) => log::error!("unable to backfill users timestamps: {}", err),
+    }
+}
+
+/// documents created before the workflow existed start in progress when they have tokens.
+fn backfill_status() {
+    let in_progress_result = DB.text_annotation_collection.update_many(
+        doc! {"status": null, "tokens.0": {"$exists": true}},
+        doc! {"$set": {"status": "in_progress"}},
+        None,
+    );
+
+    let todo_result = DB.text_annotation_collection.update_many(
+        doc! {"status": null},
+        doc! {"$set": {"status": "todo"}},
+        None,
+    );
+
+    match (in_progress_result, todo_result) {
+        (Ok(in_progress), Ok(todo)) => log::info!(
+            "status backfilled on {} annotations",
+            in_progress.modified_count + todo.modified_count
+        ),
+        (Err(err), _) | (_, Err(err)) => {
+            log::error!("unable to backfill annotations status: {}", err)
+        }
+    }
+}
+
+/// documents created before versions existed start at zero, writes only match a stored version.
+fn backfill_version() {
+    let result = DB.text_annotation_collection.update_many(
+        doc! {"version": null},
+        doc! {"$set": {"version": 0_i64}},
+        None,
+    );
+
+    match result {
+        Ok(result) => log::info!(
+            "version backfilled on {} annotations",
+            result.modified_count
+        ),
+        Err(err) => log::error!("unable to backfill annotations version: {}", err),
+    }
+
+    let teams_result = DB.team_collection.update_many(
+        doc! {"version": null},
+        doc! {"$set": {"version": 0_i64}},
+        None,
+    );
+
+    match teams_result {
+        Ok(result) => log::info!("version backfilled on {} teams", result.modified_count),
+        Err(err) => log::error!("unable to backfill teams version: {}", err),
+    }
+}
+
+/// taggers trained before the weights were stored in chunks keep them in the model.
+fn move_tagger_weights() {
+    let taggers = DB.tagger_collection.clone_with_type::<Document>();
+
+    let fetch_result = taggers.find(doc! {"weights": {"$exists": true}}, None);
+
+    if let Err(err) = fetch_result {
+        log::error!("unable to find the taggers to migrate: {}", err);
+        return;
+    }
+
+    let mut moved = 0;
+
+    for tagger in fetch_result.unwrap().filter_map(|it| it.ok()) {
+        let weights = tagger
+            .get("weights")
+            .cloned()
+            .and_then(|it| from_bson::<Vec<TaggerWeight>>(it).ok())
+            .unwrap_or_default();
+
+        let training_id = ObjectId::new();
+
+        if let Err(err) = save_tagger_weights(training_id, &weights) {
+            log::error!("unable to move the tagger weights: {}", err);
+            continue;
+        }
+
+        let update_result = taggers.update_one(
+            doc! {"_id": tagger.get("_id")},
+            doc! {
+              "$set": {"training_id": training_id, "weight_count": weights.len() as i64},
+              "$unset": {"weights": ""},
+            },
+            None,
+        );
+
+        match update_result {
+            Ok(_) => moved += 1,
+            Err(err) => log::error!("unable to move the tagger weights: {}", err),
+        }
+    }
+
+    log::info!("weights moved out of {} taggers", moved);
+}
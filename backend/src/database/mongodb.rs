@@ -1,14 +1,41 @@
 extern crate dotenv;
 
+use std::time::Duration;
+
 use crate::{
-    config::env::MONGO_URL,
-    models::{text_annotation_model::TextAnnotation, user_model::User},
+    config::env::{AUDIT_RETENTION_DAYS, MONGO_URL},
+    models::{
+        audit_model::AuditEvent,
+        comment_model::CommentThread,
+        model_backend_model::{ModelBackend, PreAnnotationJob},
+        project_model::Project,
+        tagger_model::{TaggerModel, TaggerWeightChunk},
+        team_model::Team,
+        text_annotation_model::TextAnnotation,
+        user_model::User,
+        webhook_model::{Webhook, WebhookDelivery},
+    },
+};
+use mongodb::{
+    bson::doc,
+    options::IndexOptions,
+    sync::{Client, Collection},
+    IndexModel,
 };
-use mongodb::sync::{Client, Collection};
 
 pub struct MongoRepo {
     pub user_collection: Collection<User>,
     pub text_annotation_collection: Collection<TextAnnotation>,
+    pub model_backend_collection: Collection<ModelBackend>,
+    pub pre_annotation_job_collection: Collection<PreAnnotationJob>,
+    pub project_collection: Collection<Project>,
+    pub tagger_collection: Collection<TaggerModel>,
+    pub tagger_weight_collection: Collection<TaggerWeightChunk>,
+    pub team_collection: Collection<Team>,
+    pub audit_collection: Collection<AuditEvent>,
+    pub webhook_collection: Collection<Webhook>,
+    pub webhook_delivery_collection: Collection<WebhookDelivery>,
+    pub comment_thread_collection: Collection<CommentThread>,
 }
 
 lazy_static! {
@@ -26,10 +53,121 @@ impl MongoRepo {
         // initializing collections
         let user: Collection<User> = db.collection("User");
         let text_annotation: Collection<TextAnnotation> = db.collection("TextAnnotation");
+        let model_backend: Collection<ModelBackend> = db.collection("ModelBackend");
+        let pre_annotation_job: Collection<PreAnnotationJob> = db.collection("PreAnnotationJob");
+        let project: Collection<Project> = db.collection("Project");
+        let tagger: Collection<TaggerModel> = db.collection("TaggerModel");
+        let tagger_weight: Collection<TaggerWeightChunk> = db.collection("TaggerWeightChunk");
+        let team: Collection<Team> = db.collection("Team");
+        let audit: Collection<AuditEvent> = db.collection("AuditEvent");
+        let webhook: Collection<Webhook> = db.collection("Webhook");
+        let webhook_delivery: Collection<WebhookDelivery> = db.collection("WebhookDelivery");
+        let comment_thread: Collection<CommentThread> = db.collection("CommentThread");
+
+        // text index used by the annotations search
+        let text_index = IndexModel::builder()
+            .keys(doc! {"title": "text", "content": "text"})
+            .options(
+                IndexOptions::builder()
+                    .name("annotation_text".to_string())
+                    .weights(doc! {"title": 2, "content": 1})
+                    .build(),
+            )
+            .build();
+
+        if let Err(err) = text_annotation.create_index(text_index, None) {
+            log::warn!("unable to create the annotations text index: {}", err);
+        }
+
+        // mongodb deletes the audit events once the retention period is over
+        let retention = Duration::from_secs(*AUDIT_RETENTION_DAYS * 24 * 60 * 60);
+
+        let retention_index = IndexModel::builder()
+            .keys(doc! {"created_at": 1})
+            .options(
+                IndexOptions::builder()
+                    .name("audit_retention".to_string())
+                    .expire_after(retention)
+                    .build(),
+            )
+            .build();
+
+        if audit.create_index(retention_index, None).is_err() {
+            // the index exists with a previous retention period
+            let update_result = db.run_command(
+                doc! {
+                  "collMod": "AuditEvent",
+                  "index": {"name": "audit_retention", "expireAfterSeconds": retention.as_secs() as i64},
+                },
+                None,
+            );
+
+            if let Err(err) = update_result {
+                log::warn!("unable to set the audit events retention: {}", err);
+            }
+        }
+
+        let owner_index = IndexModel::builder()
+            .keys(doc! {"owner_id": 1, "_id": -1})
+            .build();
+
+        if let Err(err) = audit.create_index(owner_index, None) {
+            log::warn!("unable to create the audit events index: {}", err);
+        }
+
+        // used by the worker looking for the deliveries to send
+        let pending_index = IndexModel::builder()
+            .keys(doc! {"status": 1, "next_attempt_at": 1})
+            .build();
+
+        let log_index = IndexModel::builder()
+            .keys(doc! {"webhook_id": 1, "_id": -1})
+            .build();
+
+        if let Err(err) = webhook_delivery.create_indexes([pending_index, log_index], None) {
+            log::warn!("unable to create the webhook deliveries indexes: {}", err);
+        }
+
+        // used by the worker looking for the pre-annotations to run
+        let job_index = IndexModel::builder()
+            .keys(doc! {"next_attempt_at": 1})
+            .build();
+
+        if let Err(err) = pre_annotation_job.create_index(job_index, None) {
+            log::warn!("unable to create the pre-annotation jobs index: {}", err);
+        }
+
+        // used to list the threads of a document and to count the open ones
+        let thread_index = IndexModel::builder()
+            .keys(doc! {"annotation_id": 1, "resolved": 1})
+            .build();
+
+        if let Err(err) = comment_thread.create_index(thread_index, None) {
+            log::warn!("unable to create the comment threads index: {}", err);
+        }
+
+        // used to load the weights of a training in order
+        let weight_index = IndexModel::builder()
+            .keys(doc! {"training_id": 1, "index": 1})
+            .build();
+
+        if let Err(err) = tagger_weight.create_index(weight_index, None) {
+            log::warn!("unable to create the tagger weights index: {}", err);
+        }
 
         MongoRepo {
             user_collection: user,
             text_annotation_collection: text_annotation,
+            model_backend_collection: model_backend,
+            pre_annotation_job_collection: pre_annotation_job,
+            project_collection: project,
+            tagger_collection: tagger,
+            tagger_weight_collection: tagger_weight,
+            team_collection: team,
+            audit_collection: audit,
+            webhook_collection: webhook,
+            webhook_delivery_collection: webhook_delivery,
+            comment_thread_collection: comment_thread,
         }
     }
 }
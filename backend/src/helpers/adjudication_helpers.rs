@@ -0,0 +1,87 @@
+use mongodb::bson::oid::ObjectId;
+
+use crate::models::{
+    adjudication_model::{AdjudicationConflict, AdjudicationSpan, ConflictVersion},
+    text_annotation_model::AnnotationLayer,
+};
+
+/// groups the overlapping spans of every layer, a group is agreed on when all the
+/// annotators gave the same spans, otherwise it is a conflict.
+pub fn merge_layers(
+    layers: &[AnnotationLayer],
+) -> (Vec<AdjudicationSpan>, Vec<AdjudicationConflict>) {
+    let mut spans: Vec<(ObjectId, AdjudicationSpan)> = layers
+        .iter()
+        .flat_map(|layer| {
+            layer.tokens.iter().map(|it| {
+                (
+                    layer.user_id,
+                    AdjudicationSpan {
+                        start: it.start,
+                        end: it.end,
+                        label: it.label,
+                    },
+                )
+            })
+        })
+        .collect();
+
+    spans.sort_by_key(|(_, it)| (it.start, it.end));
+
+    // groups of overlapping spans, `end` is inclusive
+    let mut groups: Vec<Vec<(ObjectId, AdjudicationSpan)>> = vec![];
+    let mut group_end = -1;
+
+    for (user_id, span) in spans {
+        match groups.last_mut() {
+            Some(group) if span.start <= group_end => {
+                group_end = group_end.max(span.end);
+                group.push((user_id, span));
+            }
+            _ => {
+                group_end = span.end;
+                groups.push(vec![(user_id, span)]);
+            }
+        }
+    }
+
+    let mut agreed: Vec<AdjudicationSpan> = vec![];
+    let mut conflicts: Vec<AdjudicationConflict> = vec![];
+
+    for group in groups {
+        let mut versions: Vec<ConflictVersion> = vec![];
+
+        for layer in layers.iter() {
+            let layer_spans: Vec<AdjudicationSpan> = group
+                .iter()
+                .filter(|(user_id, _)| *user_id == layer.user_id)
+                .map(|(_, it)| it.clone())
+                .collect();
+
+            match versions.iter_mut().find(|it| it.spans == layer_spans) {
+                Some(version) => version.annotators.push(layer.user_id),
+                None => versions.push(ConflictVersion {
+                    annotators: vec![layer.user_id],
+                    spans: layer_spans,
+                }),
+            }
+        }
+
+        if versions.len() == 1 {
+            agreed.extend(versions.remove(0).spans);
+            continue;
+        }
+
+        let start = group.first().map(|(_, it)| it.start).unwrap_or(0);
+        let end = group.iter().map(|(_, it)| it.end).max().unwrap_or(0);
+
+        conflicts.push(AdjudicationConflict {
+            id: format!("{}-{}", start, end),
+            start,
+            end,
+            versions,
+        });
+    }
+
+    (agreed, conflicts)
+}
@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use crate::helpers::{metrics_helpers::LabeledSpan, tagger_helpers::Word};
+
+/// tag of the words outside of any span.
+pub static NO_LABEL_TAG: &str = "O";
+
+/// tags every word with the label of the span covering it.
+pub fn spans_to_word_labels(words: &[Word], spans: &[LabeledSpan]) -> Vec<String> {
+    words
+        .iter()
+        .map(|word| {
+            spans
+                .iter()
+                .find(|it| it.start <= word.start && word.start <= it.end)
+                .map(|it| it.label.clone())
+                .unwrap_or(NO_LABEL_TAG.to_string())
+        })
+        .collect()
+}
+
+/// keeps `label` and turns every other tag into the no label tag, to compute the
+/// agreement of a single label.
+pub fn binarize_tag(tag: &str, label: &str) -> String {
+    if tag == label {
+        label.to_string()
+    } else {
+        NO_LABEL_TAG.to_string()
+    }
+}
+
+/// agreement of two annotators on the same items, corrected for chance.
+pub fn cohen_kappa(pairs: &[(String, String)]) -> f64 {
+    if pairs.is_empty() {
+        return 0.0;
+    }
+
+    let total = pairs.len() as f64;
+
+    let observed = pairs.iter().filter(|(a, b)| a == b).count() as f64 / total;
+
+    let mut first: BTreeMap<&str, f64> = BTreeMap::new();
+    let mut second: BTreeMap<&str, f64> = BTreeMap::new();
+
+    for (a, b) in pairs.iter() {
+        *first.entry(a.as_str()).or_default() += 1.0;
+        *second.entry(b.as_str()).or_default() += 1.0;
+    }
+
+    let expected: f64 = first
+        .iter()
+        .map(|(tag, count)| count / total * second.get(tag).cloned().unwrap_or(0.0) / total)
+        .sum();
+
+    chance_corrected(observed, expected)
+}
+
+/// agreement of any number of annotators, every item holds the tags given to it.
+/// items with less than two tags are ignored.
+pub fn fleiss_kappa(items: &[Vec<String>]) -> f64 {
+    let items: Vec<&Vec<String>> = items.iter().filter(|it| it.len() >= 2).collect();
+
+    if items.is_empty() {
+        return 0.0;
+    }
+
+    let mut totals: BTreeMap<&str, f64> = BTreeMap::new();
+    let mut ratings = 0.0;
+    let mut observed = 0.0;
+
+    for item in items.iter() {
+        let mut counts: BTreeMap<&str, f64> = BTreeMap::new();
+
+        for tag in item.iter() {
+            *counts.entry(tag.as_str()).or_default() += 1.0;
+            *totals.entry(tag.as_str()).or_default() += 1.0;
+        }
+
+        let n = item.len() as f64;
+
+        ratings += n;
+        observed += (counts.values().map(|it| it * it).sum::<f64>() - n) / (n * (n - 1.0));
+    }
+
+    let observed = observed / items.len() as f64;
+    let expected: f64 = totals.values().map(|it| (it / ratings).powi(2)).sum();
+
+    chance_corrected(observed, expected)
+}
+
+fn chance_corrected(observed: f64, expected: f64) -> f64 {
+    // every annotator used a single tag
+    if expected >= 1.0 {
+        return if observed >= 1.0 { 1.0 } else { 0.0 };
+    }
+
+    (observed - expected) / (1.0 - expected)
+}
@@ -0,0 +1,192 @@
+use mongodb::bson::oid::ObjectId;
+
+use crate::{
+    helpers::metrics_helpers::LabeledSpan,
+    models::{
+        realtime_model::RealtimeEvent,
+        text_annotation_model::{
+            AnnotationStatus, RejectedSuggestion, Suggestion, TextAnnotation, Token,
+        },
+    },
+    object::common::CommonError,
+};
+
+/// labels and tokens created or deleted between the two states of the annotation, for
+/// the writes replacing the whole arrays.
+pub fn get_change_events(before: &TextAnnotation, after: &TextAnnotation) -> Vec<RealtimeEvent> {
+    let mut events: Vec<RealtimeEvent> = vec![];
+
+    for label in after.labels.iter() {
+        if !before.labels.iter().any(|it| it._id == label._id) {
+            events.push(RealtimeEvent::LabelCreated {
+                label: label.clone(),
+            });
+        }
+    }
+
+    for label in before.labels.iter() {
+        if !after.labels.iter().any(|it| it._id == label._id) {
+            events.push(RealtimeEvent::LabelDeleted {
+                label_id: label._id.unwrap(),
+            });
+        }
+    }
+
+    push_token_events(&mut events, &before.tokens, &after.tokens, None);
+
+    for layer in after.layers.iter() {
+        let previous = before.layers.iter().find(|it| it.user_id == layer.user_id);
+
+        push_token_events(
+            &mut events,
+            previous.map(|it| it.tokens.as_slice()).unwrap_or_default(),
+            &layer.tokens,
+            Some(layer.user_id),
+        );
+    }
+
+    for layer in before.layers.iter() {
+        if !after.layers.iter().any(|it| it.user_id == layer.user_id) {
+            push_token_events(&mut events, &layer.tokens, &[], Some(layer.user_id));
+        }
+    }
+
+    events
+}
+
+fn push_token_events(
+    events: &mut Vec<RealtimeEvent>,
+    before: &[Token],
+    after: &[Token],
+    layer: Option<ObjectId>,
+) {
+    for token in after.iter() {
+        if !before.iter().any(|it| it._id == token._id) {
+            events.push(RealtimeEvent::TokenCreated {
+                token: token.clone(),
+                layer,
+            });
+        }
+    }
+
+    for token in before.iter() {
+        if !after.iter().any(|it| it._id == token._id) {
+            events.push(RealtimeEvent::TokenDeleted {
+                token_id: token._id.unwrap(),
+                layer,
+            });
+        }
+    }
+}
+
+pub fn validate_token_span(
+    start: i64,
+    end: i64,
+    annotation: &TextAnnotation,
+) -> Result<(), CommonError> {
+    // check start > end
+    if end <= start {
+        return Err(CommonError {
+            description: "token start's index cannot be greater than the end's".to_string(),
+        });
+    }
+
+    if start.is_negative() {
+        return Err(CommonError {
+            description: "token start cannot be negative".to_string(),
+        });
+    }
+
+    if end >= (annotation.content.len() as i64) {
+        return Err(CommonError {
+            description: "end token cannot be superior to the length of the content".to_string(),
+        });
+    }
+
+    // check if some tokens are interlacing with this one
+    let is_interlacing = annotation.tokens.iter().any(|token| {
+        (start <= token.start && token.start <= end) || (start <= token.end && token.end <= end)
+    });
+
+    if is_interlacing {
+        return Err(CommonError {
+            description: "token is interlacing with an existing one".to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// lists the status changes an annotator can do.
+pub fn is_status_transition_allowed(from: AnnotationStatus, to: AnnotationStatus) -> bool {
+    matches!(
+        (from, to),
+        (AnnotationStatus::Todo, AnnotationStatus::InProgress)
+            | (AnnotationStatus::InProgress, AnnotationStatus::Todo)
+            | (AnnotationStatus::InProgress, AnnotationStatus::Done)
+            | (AnnotationStatus::Done, AnnotationStatus::InProgress)
+            | (AnnotationStatus::Done, AnnotationStatus::Reviewed)
+            | (AnnotationStatus::Reviewed, AnnotationStatus::InProgress)
+    )
+}
+
+/// reviewing a document, and reopening a reviewed one, are left to reviewers.
+pub fn is_review_transition(from: AnnotationStatus, to: AnnotationStatus) -> bool {
+    to == AnnotationStatus::Reviewed || from == AnnotationStatus::Reviewed
+}
+
+pub fn is_suggestion_rejected(suggestion: &Suggestion, rejected: &[RejectedSuggestion]) -> bool {
+    rejected.iter().any(|it| {
+        it.start == suggestion.start
+            && it.end == suggestion.end
+            && it.label == suggestion.label
+            && it.source == suggestion.source
+    })
+}
+
+/// shows the document as seen by an annotator: their own layer in place of the
+/// tokens, without the other layers.
+pub fn get_layer_view(mut annotation: TextAnnotation, user_id: ObjectId) -> TextAnnotation {
+    annotation.tokens = annotation
+        .layers
+        .iter()
+        .find(|it| it.user_id == user_id)
+        .map(|it| it.tokens.clone())
+        .unwrap_or_default();
+    annotation.layers = vec![];
+
+    annotation
+}
+
+/// resolves the annotation's tokens into spans carrying their label name.
+pub fn get_labeled_spans(annotation: &TextAnnotation) -> Vec<LabeledSpan> {
+    annotation
+        .tokens
+        .iter()
+        .filter_map(|token| {
+            let label = annotation
+                .labels
+                .iter()
+                .find(|it| it._id == Some(token.label))?;
+
+            Some(LabeledSpan {
+                start: token.start,
+                end: token.end,
+                label: label.name.clone(),
+            })
+        })
+        .collect()
+}
+
+/// returns the characters covered by the span, `end` being inclusive.
+pub fn get_span_text(content: &str, start: i64, end: i64) -> String {
+    if start < 0 || end < start {
+        return "".to_string();
+    }
+
+    content
+        .chars()
+        .skip(start as usize)
+        .take((end - start + 1) as usize)
+        .collect()
+}
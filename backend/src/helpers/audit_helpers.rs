@@ -0,0 +1,47 @@
+use crate::{
+    config::env::ADMIN_EMAILS,
+    database::mongodb::DB,
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        audit_model::{AuditAction, AuditEvent},
+        realtime_model::RealtimeEvent,
+        text_annotation_model::TextAnnotation,
+        user_model::User,
+    },
+};
+
+/// stores the event, a failure is logged so the audited request still succeeds.
+pub fn record_audit_event(event: AuditEvent) {
+    if let Err(err) = DB.audit_collection.insert_one(event, None) {
+        log::warn!("unable to record the audit event: {}", err);
+    }
+}
+
+/// records the label and token change carried by a realtime event.
+pub fn record_realtime_audit_event(
+    auth: &UserAuthContext,
+    annotation: &TextAnnotation,
+    event: &RealtimeEvent,
+) {
+    let (action, target_id) = match event {
+        RealtimeEvent::LabelCreated { label } => (AuditAction::LabelCreated, label._id),
+        RealtimeEvent::LabelUpdated { label } => (AuditAction::LabelUpdated, label._id),
+        RealtimeEvent::LabelDeleted { label_id } => (AuditAction::LabelDeleted, Some(*label_id)),
+        RealtimeEvent::TokenCreated { token, .. } => (AuditAction::TokenCreated, token._id),
+        RealtimeEvent::TokenDeleted { token_id, .. } => {
+            (AuditAction::TokenDeleted, Some(*token_id))
+        }
+        _ => return,
+    };
+
+    record_audit_event(
+        AuditEvent::from_auth(action, auth)
+            .set_annotation(annotation)
+            .set_target(target_id),
+    );
+}
+
+/// admins are listed by email in the `ADMIN_EMAILS` variable.
+pub fn is_admin(user: &User) -> bool {
+    ADMIN_EMAILS.contains(&user.email.to_lowercase())
+}
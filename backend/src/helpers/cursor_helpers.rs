@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+use crate::object::common::CommonError;
+
+/// position of an item in a sorted listing, `value` is the item's sort key.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListCursor {
+    pub id: String,
+    pub value: serde_json::Value,
+}
+
+/// cursors are sent as an opaque hex string.
+pub fn encode_cursor(cursor: &ListCursor) -> String {
+    serde_json::to_vec(cursor)
+        .unwrap_or_default()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub fn decode_cursor(value: &str) -> Result<ListCursor, CommonError> {
+    let invalid = || CommonError {
+        description: "invalid cursor".to_string(),
+    };
+
+    if !value.len().is_multiple_of(2) || !value.is_ascii() {
+        return Err(invalid());
+    }
+
+    let bytes: Result<Vec<u8>, _> = (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16))
+        .collect();
+
+    let bytes = bytes.map_err(|_| invalid())?;
+
+    serde_json::from_slice(&bytes).map_err(|_| invalid())
+}
@@ -0,0 +1,107 @@
+use mongodb::bson::oid::ObjectId;
+use redis::{Commands, Connection, RedisResult};
+
+/// documents claimed from a project queue are leased for 30 minutes.
+pub static LEASE_TTL_SECONDS: usize = 30 * 60;
+
+/// annotators writing their own layer lease the document for themselves, so several of
+/// them can work on it at the same time.
+fn annotation_key(annotation_id: ObjectId, layer: Option<ObjectId>) -> String {
+    match layer {
+        Some(layer) => format!(
+            "lease:annotation:{}:{}",
+            annotation_id.to_hex(),
+            layer.to_hex()
+        ),
+        None => format!("lease:annotation:{}", annotation_id.to_hex()),
+    }
+}
+
+fn user_key(project_id: ObjectId, user_id: ObjectId) -> String {
+    format!("lease:user:{}:{}", project_id.to_hex(), user_id.to_hex())
+}
+
+/// claims the annotation for the user, an annotation leased by someone else is left untouched.
+pub fn acquire_lease(
+    cnx: &mut Connection,
+    project_id: ObjectId,
+    annotation_id: ObjectId,
+    user_id: ObjectId,
+    layer: Option<ObjectId>,
+) -> RedisResult<bool> {
+    let created: Option<String> = redis::cmd("SET")
+        .arg(annotation_key(annotation_id, layer))
+        .arg(user_id.to_hex())
+        .arg("NX")
+        .arg("EX")
+        .arg(LEASE_TTL_SECONDS)
+        .query(cnx)?;
+
+    if created.is_none() && get_lease_holder(cnx, annotation_id, layer)? != Some(user_id) {
+        return Ok(false);
+    }
+
+    // renewing an existing lease of the user
+    cnx.expire::<_, ()>(annotation_key(annotation_id, layer), LEASE_TTL_SECONDS)?;
+    cnx.set_ex::<_, _, ()>(
+        user_key(project_id, user_id),
+        annotation_id.to_hex(),
+        LEASE_TTL_SECONDS,
+    )?;
+
+    Ok(true)
+}
+
+pub fn get_lease_holder(
+    cnx: &mut Connection,
+    annotation_id: ObjectId,
+    layer: Option<ObjectId>,
+) -> RedisResult<Option<ObjectId>> {
+    let holder: Option<String> = cnx.get(annotation_key(annotation_id, layer))?;
+
+    Ok(holder.and_then(|it| ObjectId::parse_str(it).ok()))
+}
+
+/// annotators currently holding a lease on their layer of the annotation.
+pub fn get_layer_lease_holders(
+    cnx: &mut Connection,
+    annotation_id: ObjectId,
+) -> RedisResult<Vec<ObjectId>> {
+    let pattern = format!("{}:*", annotation_key(annotation_id, None));
+    let keys: Vec<String> = cnx.scan_match(pattern)?.collect();
+
+    Ok(keys
+        .iter()
+        .filter_map(|it| it.rsplit(':').next())
+        .filter_map(|it| ObjectId::parse_str(it).ok())
+        .collect())
+}
+
+/// remaining seconds of the lease.
+pub fn get_lease_ttl(cnx: &mut Connection, annotation_id: ObjectId) -> RedisResult<i64> {
+    cnx.ttl(annotation_key(annotation_id, None))
+}
+
+/// annotation currently leased by the user in the project.
+pub fn get_user_lease(
+    cnx: &mut Connection,
+    project_id: ObjectId,
+    user_id: ObjectId,
+) -> RedisResult<Option<ObjectId>> {
+    let annotation_id: Option<String> = cnx.get(user_key(project_id, user_id))?;
+
+    Ok(annotation_id.and_then(|it| ObjectId::parse_str(it).ok()))
+}
+
+pub fn release_lease(
+    cnx: &mut Connection,
+    project_id: ObjectId,
+    annotation_id: ObjectId,
+    layer: Option<ObjectId>,
+) -> RedisResult<()> {
+    if let Some(user_id) = get_lease_holder(cnx, annotation_id, layer)? {
+        cnx.del::<_, ()>(user_key(project_id, user_id))?;
+    }
+
+    cnx.del(annotation_key(annotation_id, layer))
+}
@@ -0,0 +1,215 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+static NO_SPAN_LABEL: &str = "-";
+
+/// a labeled span, `end` is inclusive like the annotation tokens.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabeledSpan {
+    pub start: i64,
+    pub end: i64,
+    pub label: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LabelMetrics {
+    pub label: String,
+    pub true_positives: u64,
+    pub false_positives: u64,
+    pub false_negatives: u64,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SpanMetrics {
+    pub labels: Vec<LabelMetrics>,
+    /// micro averaged over every label
+    pub overall: LabelMetrics,
+}
+
+impl LabelMetrics {
+    pub fn from_counts(label: &str, tp: u64, fp: u64, fn_: u64) -> LabelMetrics {
+        let precision = if tp + fp == 0 {
+            0.0
+        } else {
+            tp as f64 / (tp + fp) as f64
+        };
+
+        let recall = if tp + fn_ == 0 {
+            0.0
+        } else {
+            tp as f64 / (tp + fn_) as f64
+        };
+
+        let f1 = if precision + recall == 0.0 {
+            0.0
+        } else {
+            2.0 * precision * recall / (precision + recall)
+        };
+
+        LabelMetrics {
+            label: label.to_string(),
+            true_positives: tp,
+            false_positives: fp,
+            false_negatives: fn_,
+            precision,
+            recall,
+            f1,
+        }
+    }
+}
+
+/// computes exact match precision, recall and f1 per label.
+///
+/// every item of `documents` holds the gold and predicted spans of one document.
+pub fn compute_span_metrics(documents: &[(Vec<LabeledSpan>, Vec<LabeledSpan>)]) -> SpanMetrics {
+    let mut counts: BTreeMap<String, (u64, u64, u64)> = BTreeMap::new();
+
+    for (gold, predicted) in documents.iter() {
+        for span in predicted.iter() {
+            let entry = counts.entry(span.label.clone()).or_default();
+
+            if gold.contains(span) {
+                entry.0 += 1;
+            } else {
+                entry.1 += 1;
+            }
+        }
+
+        for span in gold.iter() {
+            if !predicted.contains(span) {
+                counts.entry(span.label.clone()).or_default().2 += 1;
+            }
+        }
+    }
+
+    metrics_from_counts(&counts)
+}
+
+pub fn metrics_from_counts(counts: &BTreeMap<String, (u64, u64, u64)>) -> SpanMetrics {
+    let labels: Vec<LabelMetrics> = counts
+        .iter()
+        .map(|(label, (tp, fp, fn_))| LabelMetrics::from_counts(label, *tp, *fp, *fn_))
+        .collect();
+
+    let (tp, fp, fn_) = counts.values().fold((0, 0, 0), |acc, it| {
+        (acc.0 + it.0, acc.1 + it.1, acc.2 + it.2)
+    });
+
+    SpanMetrics {
+        labels,
+        overall: LabelMetrics::from_counts("overall", tp, fp, fn_),
+    }
+}
+
+/// same as `compute_span_metrics` but a prediction overlapping a gold span with the same
+/// label is a match, every gold span can only be matched once.
+pub fn compute_partial_span_metrics(
+    documents: &[(Vec<LabeledSpan>, Vec<LabeledSpan>)],
+) -> SpanMetrics {
+    let mut counts: BTreeMap<String, (u64, u64, u64)> = BTreeMap::new();
+
+    for (gold, predicted) in documents.iter() {
+        let mut matched = vec![false; gold.len()];
+
+        for span in predicted.iter() {
+            let entry = counts.entry(span.label.clone()).or_default();
+
+            let found = gold
+                .iter()
+                .enumerate()
+                .find(|(i, it)| !matched[*i] && it.label == span.label && overlaps(it, span));
+
+            match found {
+                Some((i, _)) => {
+                    matched[i] = true;
+                    entry.0 += 1;
+                }
+                None => entry.1 += 1,
+            }
+        }
+
+        for (i, span) in gold.iter().enumerate() {
+            if !matched[i] {
+                counts.entry(span.label.clone()).or_default().2 += 1;
+            }
+        }
+    }
+
+    metrics_from_counts(&counts)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ConfusionMatrix {
+    /// rows are gold labels and columns predicted labels, the last one stands for "no span"
+    pub labels: Vec<String>,
+    pub matrix: Vec<Vec<u64>>,
+}
+
+/// pairs every gold span with the overlapping prediction sharing the most characters.
+pub fn compute_confusion_matrix(
+    documents: &[(Vec<LabeledSpan>, Vec<LabeledSpan>)],
+) -> ConfusionMatrix {
+    let mut pairs: Vec<(Option<String>, Option<String>)> = vec![];
+
+    for (gold, predicted) in documents.iter() {
+        let mut used = vec![false; predicted.len()];
+
+        for span in gold.iter() {
+            let best = predicted
+                .iter()
+                .enumerate()
+                .filter(|(i, it)| !used[*i] && overlaps(it, span))
+                .max_by_key(|(_, it)| overlap_len(it, span));
+
+            match best {
+                Some((i, it)) => {
+                    used[i] = true;
+                    pairs.push((Some(span.label.clone()), Some(it.label.clone())));
+                }
+                None => pairs.push((Some(span.label.clone()), None)),
+            }
+        }
+
+        for (i, span) in predicted.iter().enumerate() {
+            if !used[i] {
+                pairs.push((None, Some(span.label.clone())));
+            }
+        }
+    }
+
+    let mut labels: Vec<String> = pairs
+        .iter()
+        .flat_map(|(a, b)| [a.clone(), b.clone()])
+        .flatten()
+        .collect();
+    labels.sort();
+    labels.dedup();
+
+    let size = labels.len() + 1;
+    let mut matrix = vec![vec![0; size]; size];
+
+    let index_of = |label: &Option<String>| match label {
+        Some(label) => labels.iter().position(|it| it == label).unwrap(),
+        None => size - 1,
+    };
+
+    for (gold, predicted) in pairs.iter() {
+        matrix[index_of(gold)][index_of(predicted)] += 1;
+    }
+
+    labels.push(NO_SPAN_LABEL.to_string());
+
+    ConfusionMatrix { labels, matrix }
+}
+
+pub fn overlaps(a: &LabeledSpan, b: &LabeledSpan) -> bool {
+    a.start <= b.end && b.start <= a.end
+}
+
+fn overlap_len(a: &LabeledSpan, b: &LabeledSpan) -> i64 {
+    a.end.min(b.end) - a.start.max(b.start) + 1
+}
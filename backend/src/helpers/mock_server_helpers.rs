@@ -0,0 +1,128 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+/// what the mock server answers, the last response is repeated once the others are used.
+#[derive(Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub body: String,
+    pub delay: Duration,
+}
+
+impl MockResponse {
+    pub fn new(status: u16, body: &str) -> MockResponse {
+        MockResponse {
+            status,
+            body: body.to_string(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    pub fn set_delay(mut self, delay: Duration) -> MockResponse {
+        self.delay = delay;
+
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    /// header names are lowercased
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+/// a local http server standing for the model backends and the webhook receivers.
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockServer {
+    pub fn start(responses: Vec<MockResponse>) -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests: Arc<Mutex<Vec<RecordedRequest>>> = Arc::new(Mutex::new(vec![]));
+
+        let recorded = requests.clone();
+
+        thread::spawn(move || {
+            for (index, stream) in listener.incoming().enumerate() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+
+                let Some(request) = read_request(&mut stream) else {
+                    continue;
+                };
+
+                recorded.lock().unwrap().push(request);
+
+                let response = responses
+                    .get(index)
+                    .or(responses.last())
+                    .cloned()
+                    .unwrap_or(MockResponse::new(200, "{}"));
+
+                thread::sleep(response.delay);
+
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {} MOCK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.status,
+                    response.body.len(),
+                    response.body
+                );
+            }
+        });
+
+        MockServer { url, requests }
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn read_request(stream: &mut impl Read) -> Option<RecordedRequest> {
+    let mut reader = BufReader::new(stream);
+    let mut headers = HashMap::new();
+
+    // request line
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+
+    loop {
+        line.clear();
+        reader.read_line(&mut line).ok()?;
+
+        let header = line.trim_end();
+
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length: usize = headers
+        .get("content-length")
+        .and_then(|it| it.parse().ok())
+        .unwrap_or(0);
+
+    let mut body = vec![0u8; length];
+    reader.read_exact(&mut body).ok()?;
+
+    Some(RecordedRequest {
+        headers,
+        body: String::from_utf8_lossy(&body).to_string(),
+    })
+}
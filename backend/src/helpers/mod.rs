@@ -1,5 +1,22 @@
+pub mod adjudication_helpers;
+pub mod agreement_helpers;
+pub mod annotation_helpers;
+pub mod audit_helpers;
 pub mod colors_helpers;
+pub mod cursor_helpers;
 pub mod date_helpers;
+pub mod lease_helpers;
+pub mod metrics_helpers;
+#[cfg(test)]
+pub mod mock_server_helpers;
+pub mod model_backend_helpers;
 pub mod password_helpers;
+pub mod realtime_helpers;
 pub mod request_helpers;
+pub mod share_helpers;
+pub mod sync_helpers;
+pub mod tagger_helpers;
+pub mod text_helpers;
 pub mod token_helpers;
+pub mod version_helpers;
+pub mod webhook_helpers;
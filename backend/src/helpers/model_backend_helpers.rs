@@ -0,0 +1,249 @@
+use std::time::Duration;
+
+use serde_json::{Map, Value};
+
+use crate::{
+    models::{
+        model_backend_model::{DroppedPrediction, ModelBackend, ModelBackendContract},
+        text_annotation_model::CreateSuggestionBody,
+    },
+    object::common::CommonError,
+};
+
+/// sends the content to the backend, the spans missing a field are dropped and returned
+/// apart so one bad span does not lose the others.
+pub fn request_predictions(
+    backend: &ModelBackend,
+    content: &str,
+) -> Result<(Vec<CreateSuggestionBody>, Vec<DroppedPrediction>), CommonError> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_millis(backend.timeout_ms as u64))
+        .build();
+
+    let mut payload = Map::new();
+    payload.insert(
+        backend.contract.content_field.clone(),
+        Value::String(content.to_string()),
+    );
+
+    let response = agent
+        .post(backend.url.as_str())
+        .send_json(Value::Object(payload));
+
+    if response.is_err() {
+        let description = match response.err().unwrap() {
+            ureq::Error::Status(code, _) => {
+                format!("model backend responded with status {}", code)
+            }
+            ureq::Error::Transport(transport) => {
+                format!("unable to reach model backend: {}", transport)
+            }
+        };
+
+        return Err(CommonError { description });
+    }
+
+    let body = response.unwrap().into_json::<Value>();
+
+    if body.is_err() {
+        return Err(CommonError {
+            description: "model backend did not respond with json".to_string(),
+        });
+    }
+
+    parse_predictions(&body.unwrap(), &backend.name, &backend.contract)
+}
+
+fn parse_predictions(
+    body: &Value,
+    source: &str,
+    contract: &ModelBackendContract,
+) -> Result<(Vec<CreateSuggestionBody>, Vec<DroppedPrediction>), CommonError> {
+    let mut spans = Some(body);
+
+    for key in contract.spans_field.split('.').filter(|it| !it.is_empty()) {
+        spans = spans.and_then(|it| it.get(key));
+    }
+
+    let items = spans.and_then(|it| it.as_array());
+
+    if items.is_none() {
+        return Err(CommonError {
+            description: format!(
+                "model backend response has no spans array at \"{}\"",
+                contract.spans_field
+            ),
+        });
+    }
+
+    let mut predictions: Vec<CreateSuggestionBody> = vec![];
+    let mut dropped: Vec<DroppedPrediction> = vec![];
+
+    for item in items.unwrap() {
+        let start = item.get(&contract.start_field).and_then(|it| it.as_i64());
+        let end = item.get(&contract.end_field).and_then(|it| it.as_i64());
+        let label = item.get(&contract.label_field).and_then(|it| it.as_str());
+        let score = item
+            .get(&contract.score_field)
+            .and_then(|it| it.as_f64())
+            .unwrap_or(1.0);
+
+        if start.is_none() || end.is_none() || label.is_none() {
+            dropped.push(DroppedPrediction {
+                span: item.clone(),
+                reason: "span is missing its start, end or label".to_string(),
+            });
+            continue;
+        }
+
+        predictions.push(CreateSuggestionBody {
+            start: start.unwrap(),
+            end: end.unwrap(),
+            label: label.unwrap().to_string(),
+            source: source.to_string(),
+            score,
+        });
+    }
+
+    Ok((predictions, dropped))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use mongodb::bson::oid::ObjectId;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        helpers::mock_server_helpers::{MockResponse, MockServer},
+        models::model_backend_model::PredictionTarget,
+    };
+
+    fn backend(url: &str) -> ModelBackend {
+        ModelBackend {
+            _id: Some(ObjectId::new()),
+            user_id: ObjectId::new(),
+            name: "ner".to_string(),
+            url: url.to_string(),
+            timeout_ms: 500,
+            contract: ModelBackendContract::default(),
+            target: PredictionTarget::Suggestions,
+            run_on_create: false,
+        }
+    }
+
+    #[test]
+    fn request_predictions_parses_the_spans() {
+        let server = MockServer::start(vec![MockResponse::new(
+            200,
+            r#"{"spans": [{"start": 0, "end": 4, "label": "PER", "score": 0.9}, {"start": 9, "end": 14, "label": "LOC"}]}"#,
+        )]);
+
+        let (predictions, dropped) =
+            request_predictions(&backend(&server.url), "Alice in Paris").unwrap();
+
+        assert!(dropped.is_empty());
+
+        assert_eq!(predictions.len(), 2);
+        assert_eq!(predictions[0].start, 0);
+        assert_eq!(predictions[0].end, 4);
+        assert_eq!(predictions[0].label, "PER");
+        assert_eq!(predictions[0].source, "ner");
+        assert_eq!(predictions[0].score, 0.9);
+        // spans without a score are considered certain
+        assert_eq!(predictions[1].score, 1.0);
+
+        let requests = server.requests();
+
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].headers["content-type"], "application/json");
+        assert_eq!(
+            serde_json::from_str::<Value>(&requests[0].body).unwrap(),
+            json!({"content": "Alice in Paris"})
+        );
+    }
+
+    #[test]
+    fn request_predictions_follows_the_contract() {
+        let server = MockServer::start(vec![MockResponse::new(
+            200,
+            r#"{"result": {"entities": [{"from": 2, "to": 3, "tag": "X"}]}}"#,
+        )]);
+
+        let mut backend = backend(&server.url);
+        backend.contract = ModelBackendContract {
+            content_field: "text".to_string(),
+            spans_field: "result.entities".to_string(),
+            start_field: "from".to_string(),
+            end_field: "to".to_string(),
+            label_field: "tag".to_string(),
+            score_field: "confidence".to_string(),
+        };
+
+        let (predictions, _) = request_predictions(&backend, "abcd").unwrap();
+
+        assert_eq!(predictions.len(), 1);
+        assert_eq!((predictions[0].start, predictions[0].end), (2, 3));
+        assert_eq!(predictions[0].label, "X");
+        assert_eq!(
+            serde_json::from_str::<Value>(&server.requests()[0].body).unwrap(),
+            json!({"text": "abcd"})
+        );
+    }
+
+    #[test]
+    fn request_predictions_fails_on_malformed_payload() {
+        let server = MockServer::start(vec![MockResponse::new(200, "not json")]);
+
+        let err = request_predictions(&backend(&server.url), "abc").unwrap_err();
+
+        assert_eq!(err.description, "model backend did not respond with json");
+
+        let server = MockServer::start(vec![MockResponse::new(200, r#"{"items": []}"#)]);
+
+        let err = request_predictions(&backend(&server.url), "abc").unwrap_err();
+
+        assert_eq!(
+            err.description,
+            "model backend response has no spans array at \"spans\""
+        );
+    }
+
+    #[test]
+    fn parse_predictions_drops_span_without_label() {
+        let body = json!({"spans": [{"start": 0, "end": 1}, {"start": 2, "end": 3, "label": "X"}]});
+
+        let (predictions, dropped) =
+            parse_predictions(&body, "ner", &ModelBackendContract::default()).unwrap();
+
+        assert_eq!(predictions.len(), 1);
+        assert_eq!(predictions[0].label, "X");
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].span, json!({"start": 0, "end": 1}));
+    }
+
+    #[test]
+    fn request_predictions_fails_on_error_status() {
+        let server = MockServer::start(vec![MockResponse::new(500, r#"{"error": "boom"}"#)]);
+
+        let err = request_predictions(&backend(&server.url), "abc").unwrap_err();
+
+        assert_eq!(err.description, "model backend responded with status 500");
+    }
+
+    #[test]
+    fn request_predictions_fails_on_timeout() {
+        let server = MockServer::start(vec![
+            MockResponse::new(200, r#"{"spans": []}"#).set_delay(Duration::from_secs(2))
+        ]);
+
+        let mut backend = backend(&server.url);
+        backend.timeout_ms = 100;
+
+        let err = request_predictions(&backend, "abc").unwrap_err();
+
+        assert!(err.description.starts_with("unable to reach model backend"));
+    }
+}
@@ -0,0 +1,241 @@
+use std::{collections::HashMap, sync::Mutex, sync::Once, thread, time::Duration};
+
+use chrono::Utc;
+use futures::channel::mpsc::UnboundedSender;
+use mongodb::bson::oid::ObjectId;
+use redis::{Commands, Connection, RedisResult};
+
+use crate::{
+    database::redis::CACHE_DB,
+    models::{
+        realtime_model::{PresenceViewer, RealtimeEvent, RealtimeMessage},
+        text_annotation_model::AnnotationRole,
+    },
+};
+
+/// viewers that did not send anything for a minute are considered gone.
+pub static PRESENCE_TTL_SECONDS: usize = 60;
+
+static EVENTS_CHANNEL_PATTERN: &str = "realtime:annotation:*";
+
+/// a websocket opened on this server instance, the messages are filtered by the role
+/// the user has when they are sent.
+pub struct RealtimeConnection {
+    pub connection_id: ObjectId,
+    pub sender: UnboundedSender<RealtimeMessage>,
+}
+
+lazy_static! {
+    static ref CONNECTIONS: Mutex<HashMap<ObjectId, Vec<RealtimeConnection>>> =
+        Mutex::new(HashMap::new());
+    /// connection shared by the publishes of this instance, opened again once it fails
+    static ref PUBLISHER: Mutex<Option<Connection>> = Mutex::new(None);
+}
+
+static SUBSCRIBER: Once = Once::new();
+
+fn events_channel(annotation_id: ObjectId) -> String {
+    format!("realtime:annotation:{}", annotation_id.to_hex())
+}
+
+fn presence_key(annotation_id: ObjectId) -> String {
+    format!("presence:annotation:{}", annotation_id.to_hex())
+}
+
+fn viewer_key(annotation_id: ObjectId, connection_id: ObjectId) -> String {
+    format!(
+        "presence:viewer:{}:{}",
+        annotation_id.to_hex(),
+        connection_id.to_hex()
+    )
+}
+
+pub fn register_connection(annotation_id: ObjectId, connection: RealtimeConnection) {
+    // every instance listens to the events published by the others
+    SUBSCRIBER.call_once(|| {
+        thread::spawn(run_subscriber);
+    });
+
+    CONNECTIONS
+        .lock()
+        .unwrap()
+        .entry(annotation_id)
+        .or_default()
+        .push(connection);
+}
+
+pub fn unregister_connection(annotation_id: ObjectId, connection_id: ObjectId) {
+    let mut connections = CONNECTIONS.lock().unwrap();
+
+    if let Some(items) = connections.get_mut(&annotation_id) {
+        items.retain(|it| it.connection_id != connection_id);
+
+        if items.is_empty() {
+            connections.remove(&annotation_id);
+        }
+    }
+}
+
+/// broadcasts the event to the clients of every server instance, changes are already saved
+/// so a failure is only logged.
+pub fn publish_annotation_event(
+    annotation_id: ObjectId,
+    user_id: Option<ObjectId>,
+    version: Option<i64>,
+    event: RealtimeEvent,
+) {
+    let message = RealtimeMessage {
+        annotation_id,
+        user_id,
+        version,
+        sent_at: Utc::now().timestamp_millis(),
+        event,
+    };
+
+    let payload = serde_json::to_string(&message).unwrap();
+
+    if let Err(err) = publish(events_channel(annotation_id), payload) {
+        log::warn!("unable to publish the annotation event: {}", err);
+
+        // the clients of this instance still get it
+        dispatch_message(&message);
+    }
+}
+
+fn publish(channel: String, payload: String) -> RedisResult<()> {
+    let mut publisher = PUBLISHER.lock().unwrap();
+
+    if publisher.is_none() {
+        *publisher = Some(CACHE_DB.client.get_connection()?);
+    }
+
+    let published = publisher
+        .as_mut()
+        .unwrap()
+        .publish::<_, _, ()>(channel, payload);
+
+    if published.is_err() {
+        *publisher = None;
+    }
+
+    published
+}
+
+/// hands the message to the local connections of the annotation.
+fn dispatch_message(message: &RealtimeMessage) {
+    let mut connections = CONNECTIONS.lock().unwrap();
+
+    if let Some(items) = connections.get_mut(&message.annotation_id) {
+        items.retain(|it| it.sender.unbounded_send(message.clone()).is_ok());
+    }
+}
+
+/// layers stay hidden from the other annotators.
+pub fn can_receive(message: &RealtimeMessage, role: AnnotationRole, user_id: ObjectId) -> bool {
+    match message.layer() {
+        Some(layer) => role >= AnnotationRole::Editor || user_id == layer,
+        None => true,
+    }
+}
+
+fn run_subscriber() {
+    loop {
+        if let Err(err) = listen_events() {
+            log::warn!("realtime subscriber disconnected: {}", err);
+        }
+
+        thread::sleep(Duration::from_secs(1));
+    }
+}
+
+fn listen_events() -> RedisResult<()> {
+    let mut cnx = CACHE_DB.client.get_connection()?;
+    let mut pubsub = cnx.as_pubsub();
+
+    pubsub.psubscribe(EVENTS_CHANNEL_PATTERN)?;
+
+    loop {
+        let payload: String = pubsub.get_message()?.get_payload()?;
+
+        match serde_json::from_str::<RealtimeMessage>(payload.as_str()) {
+            Ok(message) => dispatch_message(&message),
+            Err(err) => log::warn!("unable to read the annotation event: {}", err),
+        }
+    }
+}
+
+pub fn join_presence(
+    cnx: &mut Connection,
+    annotation_id: ObjectId,
+    viewer: &PresenceViewer,
+) -> RedisResult<()> {
+    cnx.set_ex::<_, _, ()>(
+        viewer_key(annotation_id, viewer.connection_id),
+        serde_json::to_string(viewer).unwrap(),
+        PRESENCE_TTL_SECONDS,
+    )?;
+
+    cnx.sadd(presence_key(annotation_id), viewer.connection_id.to_hex())
+}
+
+/// keeps the viewer listed, returns false when it had already expired.
+pub fn refresh_presence(
+    cnx: &mut Connection,
+    annotation_id: ObjectId,
+    connection_id: ObjectId,
+) -> RedisResult<bool> {
+    cnx.expire(
+        viewer_key(annotation_id, connection_id),
+        PRESENCE_TTL_SECONDS,
+    )
+}
+
+pub fn leave_presence(
+    cnx: &mut Connection,
+    annotation_id: ObjectId,
+    connection_id: ObjectId,
+) -> RedisResult<()> {
+    cnx.srem::<_, _, ()>(presence_key(annotation_id), connection_id.to_hex())?;
+
+    cnx.del(viewer_key(annotation_id, connection_id))
+}
+
+/// lists who is viewing the annotation, expired viewers are forgotten on the way.
+pub fn get_presence(
+    cnx: &mut Connection,
+    annotation_id: ObjectId,
+) -> RedisResult<Vec<PresenceViewer>> {
+    let ids: Vec<String> = cnx.smembers(presence_key(annotation_id))?;
+
+    let mut viewers: Vec<PresenceViewer> = vec![];
+
+    for id in ids {
+        let viewer: Option<String> = match ObjectId::parse_str(id.as_str()) {
+            Ok(connection_id) => cnx.get(viewer_key(annotation_id, connection_id))?,
+            Err(_) => None,
+        };
+
+        match viewer.and_then(|it| serde_json::from_str(it.as_str()).ok()) {
+            Some(viewer) => viewers.push(viewer),
+            None => cnx.srem::<_, _, ()>(presence_key(annotation_id), id)?,
+        }
+    }
+
+    viewers.sort_by_key(|it: &PresenceViewer| it.connected_at);
+
+    Ok(viewers)
+}
+
+/// publishes the current viewers of the annotation.
+pub fn publish_presence(cnx: &mut Connection, annotation_id: ObjectId) -> RedisResult<()> {
+    let viewers = get_presence(cnx, annotation_id)?;
+
+    publish_annotation_event(
+        annotation_id,
+        None,
+        None,
+        RealtimeEvent::Presence { viewers },
+    );
+
+    Ok(())
+}
@@ -1,8 +1,36 @@
-use actix_web::{HttpMessage, HttpRequest};
+use std::net::SocketAddr;
 
-use crate::middleware::auth_middleware::UserAuthContext;
+use actix_web::{http::header, HttpMessage, HttpRequest};
+
+use crate::{
+    config::env::TRUSTED_PROXIES, helpers::version_helpers::parse_etag_version,
+    middleware::auth_middleware::UserAuthContext,
+};
 
 pub fn get_auth_ctx(req: &HttpRequest) -> Option<UserAuthContext> {
     let ext = req.extensions();
     ext.get::<UserAuthContext>().cloned()
 }
+
+/// address of the client, see `resolve_client_ip`.
+pub fn get_client_ip(req: &HttpRequest) -> Option<String> {
+    resolve_client_ip(req.peer_addr(), req.connection_info().realip_remote_addr())
+}
+
+/// address of the connection's peer, the `forwarded` address is only taken when the
+/// peer is one of the trusted proxies since anyone can send the headers.
+pub fn resolve_client_ip(peer: Option<SocketAddr>, forwarded: Option<&str>) -> Option<String> {
+    let peer = peer?.ip();
+
+    match forwarded {
+        Some(forwarded) if TRUSTED_PROXIES.contains(&peer) => Some(forwarded.to_string()),
+        _ => Some(peer.to_string()),
+    }
+}
+
+/// version the client made its change against, taken from the `If-Match` header.
+pub fn get_if_match_version(req: &HttpRequest) -> Option<i64> {
+    let value = req.headers().get(header::IF_MATCH)?.to_str().ok()?;
+
+    parse_etag_version(value)
+}
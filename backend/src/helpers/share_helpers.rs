@@ -0,0 +1,93 @@
+use mongodb::bson::oid::ObjectId;
+use redis::{Commands, Connection, RedisResult};
+
+use crate::{
+    helpers::token_helpers::{create_token_string, get_token_claims},
+    models::share_model::ShareLink,
+};
+
+pub static DEFAULT_SHARE_LINK_TTL_SECONDS: i64 = 7 * 24 * 60 * 60;
+pub static MAX_SHARE_LINK_TTL_SECONDS: i64 = 90 * 24 * 60 * 60;
+
+/// prefix of the share tokens subject, so user tokens cannot be used as share links.
+static SHARE_SUBJECT_PREFIX: &str = "share:";
+
+fn link_key(link_id: ObjectId) -> String {
+    format!("share:link:{}", link_id.to_hex())
+}
+
+fn annotation_key(annotation_id: ObjectId) -> String {
+    format!("share:annotation:{}", annotation_id.to_hex())
+}
+
+/// signs the link id, the token expires with the link.
+pub fn create_share_token(link_id: ObjectId, expires_at: i64) -> Option<String> {
+    create_token_string(
+        format!("{}{}", SHARE_SUBJECT_PREFIX, link_id.to_hex()),
+        expires_at,
+    )
+    .ok()
+}
+
+/// returns the link id of a valid share token.
+pub fn get_share_token_link(token: String) -> Option<ObjectId> {
+    let claims = get_token_claims(token).ok()?;
+
+    let link_id = claims.sub.strip_prefix(SHARE_SUBJECT_PREFIX)?;
+
+    ObjectId::parse_str(link_id).ok()
+}
+
+pub fn store_share_link(cnx: &mut Connection, link: &ShareLink) -> RedisResult<()> {
+    let ttl = (link.expires_at - link.created_at).max(1) as usize;
+
+    cnx.set_ex::<_, _, ()>(
+        link_key(link._id),
+        serde_json::to_string(link).unwrap(),
+        ttl,
+    )?;
+
+    cnx.sadd(annotation_key(link.annotation_id), link._id.to_hex())
+}
+
+pub fn get_share_link(cnx: &mut Connection, link_id: ObjectId) -> RedisResult<Option<ShareLink>> {
+    let value: Option<String> = cnx.get(link_key(link_id))?;
+
+    Ok(value.and_then(|it| serde_json::from_str(it.as_str()).ok()))
+}
+
+/// lists the active links of the annotation, expired ones are forgotten on the way.
+pub fn get_annotation_share_links(
+    cnx: &mut Connection,
+    annotation_id: ObjectId,
+) -> RedisResult<Vec<ShareLink>> {
+    let ids: Vec<String> = cnx.smembers(annotation_key(annotation_id))?;
+
+    let mut links: Vec<ShareLink> = vec![];
+
+    for id in ids {
+        let link = match ObjectId::parse_str(id.as_str()) {
+            Ok(link_id) => get_share_link(cnx, link_id)?,
+            Err(_) => None,
+        };
+
+        match link {
+            Some(link) => links.push(link),
+            None => cnx.srem::<_, _, ()>(annotation_key(annotation_id), id)?,
+        }
+    }
+
+    links.sort_by_key(|it| it.created_at);
+
+    Ok(links)
+}
+
+pub fn delete_share_link(
+    cnx: &mut Connection,
+    annotation_id: ObjectId,
+    link_id: ObjectId,
+) -> RedisResult<()> {
+    cnx.srem::<_, _, ()>(annotation_key(annotation_id), link_id.to_hex())?;
+
+    cnx.del(link_key(link_id))
+}
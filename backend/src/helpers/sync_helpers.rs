@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::{
+    helpers::{
+        annotation_helpers::validate_token_span,
+        colors_helpers::{get_next_valid_color, is_color_used, is_valid_color},
+    },
+    models::{
+        realtime_model::RealtimeEvent,
+        sync_model::SyncOperationKind,
+        text_annotation_model::{Label, TextAnnotation, Token},
+    },
+};
+
+/// what the operations of a batch share while they are replayed.
+pub struct SyncContext {
+    pub user_id: ObjectId,
+    /// set when the tokens are the user's layer
+    pub layer: Option<ObjectId>,
+    pub can_edit_labels: bool,
+    /// server ids of the items created in the batch, by `ref_id`
+    pub references: HashMap<String, ObjectId>,
+}
+
+impl SyncContext {
+    fn resolve_id(&self, id: &str) -> Result<ObjectId, String> {
+        if let Some(id) = self.references.get(id) {
+            return Ok(*id);
+        }
+
+        ObjectId::parse_str(id).map_err(|_| format!("unknown reference ({})", id))
+    }
+}
+
+/// replays the operation on the current state of the document.
+///
+/// operations keep their intent rather than their base state: a token is created unless
+/// it overlaps the current tokens, deleting what is already gone succeeds, and updating
+/// what was deleted in the meantime is rejected. returns the change to broadcast, none
+/// when the document already was in the expected state.
+pub fn apply_operation(
+    view: &mut TextAnnotation,
+    operation: &SyncOperationKind,
+    ctx: &mut SyncContext,
+) -> Result<Option<RealtimeEvent>, String> {
+    match operation {
+        SyncOperationKind::CreateToken {
+            ref_id,
+            start,
+            end,
+            label,
+        } => {
+            let label = find_label_id(view, ctx, label)?;
+
+            let existing = view
+                .tokens
+                .iter()
+                .find(|it| it.start == *start && it.end == *end && it.label == label);
+
+            // the same span was already added, by a previous sync or someone else
+            if let Some(token) = existing {
+                remember(ctx, ref_id, token._id.unwrap());
+
+                return Ok(None);
+            }
+
+            validate_token_span(*start, *end, view).map_err(|err| err.description)?;
+
+            let token = Token {
+                _id: Some(ObjectId::new()),
+                start: *start,
+                end: *end,
+                label,
+                created_at: Some(DateTime::now()),
+                created_by: Some(ctx.user_id),
+            };
+
+            remember(ctx, ref_id, token._id.unwrap());
+            view.tokens.push(token.clone());
+
+            Ok(Some(RealtimeEvent::TokenCreated {
+                token,
+                layer: ctx.layer,
+            }))
+        }
+        SyncOperationKind::UpdateToken {
+            token_id,
+            start,
+            end,
+            label,
+        } => {
+            let token_id = ctx.resolve_id(token_id)?;
+
+            let index = view
+                .tokens
+                .iter()
+                .position(|it| it._id == Some(token_id))
+                .ok_or("token was deleted")?;
+
+            let mut token = view.tokens[index].clone();
+
+            if let Some(label) = label {
+                token.label = find_label_id(view, ctx, label)?;
+            }
+
+            token.start = start.unwrap_or(token.start);
+            token.end = end.unwrap_or(token.end);
+
+            // the span is checked against the other tokens only
+            let previous = view.tokens.remove(index);
+
+            if let Err(err) = validate_token_span(token.start, token.end, view) {
+                view.tokens.insert(index, previous);
+
+                return Err(err.description);
+            }
+
+            view.tokens.insert(index, token.clone());
+
+            Ok(Some(RealtimeEvent::TokenUpdated {
+                token,
+                layer: ctx.layer,
+            }))
+        }
+        SyncOperationKind::DeleteToken { token_id } => {
+            let token_id = ctx.resolve_id(token_id)?;
+
+            let count = view.tokens.len();
+            view.tokens.retain(|it| it._id != Some(token_id));
+
+            if view.tokens.len() == count {
+                return Ok(None);
+            }
+
+            Ok(Some(RealtimeEvent::TokenDeleted {
+                token_id,
+                layer: ctx.layer,
+            }))
+        }
+        SyncOperationKind::CreateLabel {
+            ref_id,
+            name,
+            color,
+        } => {
+            ensure_labels_editable(ctx)?;
+
+            // labels created offline by several users are merged by name
+            if let Some(label) = view.labels.iter().find(|it| it.name == *name) {
+                remember(ctx, ref_id, label._id.unwrap());
+
+                return Ok(None);
+            }
+
+            let color = match color {
+                Some(color) if !is_valid_color(color.clone()) => {
+                    return Err("invalid label color".to_string())
+                }
+                // the color may have been taken by a concurrent label
+                Some(color) if !is_color_used(color.clone(), &view.labels) => color.clone(),
+                _ => get_next_valid_color(&view.labels).map_err(|err| err.description)?,
+            };
+
+            let label = Label {
+                _id: Some(ObjectId::new()),
+                name: name.clone(),
+                color,
+                created_at: Some(DateTime::now()),
+                updated_at: Some(DateTime::now()),
+            };
+
+            remember(ctx, ref_id, label._id.unwrap());
+            view.labels.push(label.clone());
+
+            Ok(Some(RealtimeEvent::LabelCreated { label }))
+        }
+        SyncOperationKind::UpdateLabel {
+            label_id,
+            name,
+            color,
+        } => {
+            ensure_labels_editable(ctx)?;
+
+            let label_id = ctx.resolve_id(label_id)?;
+
+            let others: Vec<Label> = view
+                .labels
+                .iter()
+                .filter(|it| it._id != Some(label_id))
+                .cloned()
+                .collect();
+
+            let index = view
+                .labels
+                .iter()
+                .position(|it| it._id == Some(label_id))
+                .ok_or("label was deleted")?;
+
+            // changes are applied once every field is checked
+            let mut label = view.labels[index].clone();
+
+            if let Some(name) = name {
+                if others.iter().any(|it| it.name == *name) {
+                    return Err("label with the same name already exist".to_string());
+                }
+
+                label.name = name.clone();
+            }
+
+            if let Some(color) = color {
+                if !is_valid_color(color.clone()) {
+                    return Err("invalid label color".to_string());
+                }
+
+                if is_color_used(color.clone(), &others) {
+                    return Err("label with the same color already exist".to_string());
+                }
+
+                label.color = color.clone();
+            }
+
+            label.updated_at = Some(DateTime::now());
+            view.labels[index] = label.clone();
+
+            Ok(Some(RealtimeEvent::LabelUpdated { label }))
+        }
+        SyncOperationKind::DeleteLabel { label_id } => {
+            ensure_labels_editable(ctx)?;
+
+            let label_id = ctx.resolve_id(label_id)?;
+
+            if !view.labels.iter().any(|it| it._id == Some(label_id)) {
+                return Ok(None);
+            }
+
+            view.labels.retain(|it| it._id != Some(label_id));
+            view.tokens.retain(|it| it.label != label_id);
+
+            Ok(Some(RealtimeEvent::LabelDeleted { label_id }))
+        }
+    }
+}
+
+fn find_label_id(
+    view: &TextAnnotation,
+    ctx: &SyncContext,
+    label: &str,
+) -> Result<ObjectId, String> {
+    let label_id = ctx.resolve_id(label)?;
+
+    if !view.labels.iter().any(|it| it._id == Some(label_id)) {
+        return Err("label was deleted".to_string());
+    }
+
+    Ok(label_id)
+}
+
+fn ensure_labels_editable(ctx: &SyncContext) -> Result<(), String> {
+    if !ctx.can_edit_labels {
+        return Err("only a reviewer can change the labels".to_string());
+    }
+
+    Ok(())
+}
+
+fn remember(ctx: &mut SyncContext, ref_id: &Option<String>, id: ObjectId) {
+    if let Some(ref_id) = ref_id {
+        ctx.references.insert(ref_id.clone(), id);
+    }
+}
@@ -0,0 +1,488 @@
+use std::collections::HashMap;
+
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    error::Result as MongoResult,
+    options::FindOptions,
+};
+
+use crate::{
+    database::mongodb::DB,
+    helpers::metrics_helpers::LabeledSpan,
+    models::tagger_model::{TaggerWeight, TaggerWeightChunk},
+};
+
+static OUTSIDE_TAG: &str = "O";
+static START_TAG: &str = "-START-";
+
+/// weights per stored chunk, a chunk stays around a megabyte.
+static WEIGHT_CHUNK_SIZE: usize = 10_000;
+
+/// stores the weights of a training, they are only used once the tagger points to it.
+pub fn save_tagger_weights(training_id: ObjectId, weights: &[TaggerWeight]) -> MongoResult<()> {
+    let chunks: Vec<TaggerWeightChunk> = weights
+        .chunks(WEIGHT_CHUNK_SIZE)
+        .enumerate()
+        .map(|(index, weights)| TaggerWeightChunk {
+            _id: None,
+            training_id,
+            index: index as i64,
+            weights: weights.to_vec(),
+        })
+        .collect();
+
+    if chunks.is_empty() {
+        return Ok(());
+    }
+
+    DB.tagger_weight_collection.insert_many(chunks, None)?;
+
+    Ok(())
+}
+
+pub fn find_tagger_weights(training_id: ObjectId) -> MongoResult<Vec<TaggerWeight>> {
+    let chunks = DB.tagger_weight_collection.find(
+        doc! {"training_id": training_id},
+        FindOptions::builder().sort(doc! {"index": 1}).build(),
+    )?;
+
+    let mut weights: Vec<TaggerWeight> = vec![];
+
+    for chunk in chunks {
+        weights.extend(chunk?.weights);
+    }
+
+    Ok(weights)
+}
+
+pub fn delete_tagger_weights(training_id: ObjectId) {
+    let result = DB
+        .tagger_weight_collection
+        .delete_many(doc! {"training_id": training_id}, None);
+
+    if let Err(err) = result {
+        log::warn!("unable to delete the tagger weights: {}", err);
+    }
+}
+
+/// a word of the content, offsets are character indices and `end` is inclusive.
+#[derive(Debug, Clone)]
+pub struct Word {
+    pub text: String,
+    pub start: i64,
+    pub end: i64,
+}
+
+/// splits the indexes of `count` samples into the training and the held-out ones.
+///
+/// every k-th sample is held out, with `k = round(1 / test_ratio)`. when there are
+/// fewer than k samples the last one is held out, so two samples always give both sets.
+pub fn split_held_out(count: usize, test_ratio: f64) -> (Vec<usize>, Vec<usize>) {
+    let k = ((1.0 / test_ratio).round() as usize).max(2);
+
+    let (mut test, mut train): (Vec<usize>, Vec<usize>) =
+        (0..count).partition(|it| it % k == k - 1);
+
+    if test.is_empty() && train.len() >= 2 {
+        test.extend(train.pop());
+    }
+
+    (train, test)
+}
+
+/// splits the content into runs of alphanumeric characters, every other non space
+/// character is a word on its own.
+pub fn split_words(content: &str) -> Vec<Word> {
+    let mut words: Vec<Word> = vec![];
+    let mut current: Option<Word> = None;
+
+    for (index, c) in content.chars().enumerate() {
+        let index = index as i64;
+
+        if c.is_alphanumeric() {
+            match current.as_mut() {
+                Some(word) => {
+                    word.text.push(c);
+                    word.end = index;
+                }
+                None => {
+                    current = Some(Word {
+                        text: c.to_string(),
+                        start: index,
+                        end: index,
+                    })
+                }
+            }
+
+            continue;
+        }
+
+        if let Some(word) = current.take() {
+            words.push(word);
+        }
+
+        if !c.is_whitespace() {
+            words.push(Word {
+                text: c.to_string(),
+                start: index,
+                end: index,
+            });
+        }
+    }
+
+    if let Some(word) = current.take() {
+        words.push(word);
+    }
+
+    words
+}
+
+/// tags every word with `B-<label>`, `I-<label>` or `O` according to the spans covering it.
+pub fn spans_to_tags(words: &[Word], spans: &[LabeledSpan]) -> Vec<String> {
+    let mut tags: Vec<String> = vec![];
+    let mut previous: Option<&LabeledSpan> = None;
+
+    for word in words.iter() {
+        let span = spans
+            .iter()
+            .find(|it| it.start <= word.start && word.start <= it.end);
+
+        match span {
+            Some(span) => {
+                let prefix = if previous == Some(span) { "I" } else { "B" };
+
+                tags.push(format!("{}-{}", prefix, span.label));
+            }
+            None => tags.push(OUTSIDE_TAG.to_string()),
+        }
+
+        previous = span;
+    }
+
+    tags
+}
+
+pub fn tags_to_spans(words: &[Word], tags: &[String]) -> Vec<LabeledSpan> {
+    let mut spans: Vec<LabeledSpan> = vec![];
+    let mut current: Option<LabeledSpan> = None;
+
+    for (word, tag) in words.iter().zip(tags.iter()) {
+        let (prefix, label) = tag.split_once('-').unwrap_or((OUTSIDE_TAG, ""));
+
+        let continues =
+            prefix == "I" && current.as_ref().map(|it| it.label.as_str()) == Some(label);
+
+        if continues {
+            current.as_mut().unwrap().end = word.end;
+            continue;
+        }
+
+        if let Some(span) = current.take() {
+            spans.push(span);
+        }
+
+        if prefix != OUTSIDE_TAG {
+            current = Some(LabeledSpan {
+                start: word.start,
+                end: word.end,
+                label: label.to_string(),
+            });
+        }
+    }
+
+    if let Some(span) = current.take() {
+        spans.push(span);
+    }
+
+    spans
+}
+
+/// greedy averaged perceptron tagger working on word features.
+#[derive(Debug, Default)]
+pub struct Perceptron {
+    pub tags: Vec<String>,
+    weights: HashMap<String, HashMap<String, f64>>,
+    totals: HashMap<(String, String), f64>,
+    timestamps: HashMap<(String, String), u64>,
+    instances: u64,
+}
+
+impl Perceptron {
+    /// trains a model on `(words, tags)` samples.
+    pub fn train(samples: &[(Vec<Word>, Vec<String>)], iterations: usize) -> Perceptron {
+        let mut model = Perceptron::default();
+
+        let mut tags: Vec<String> = samples
+            .iter()
+            .flat_map(|(_, tags)| tags.iter().cloned())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        model.tags = tags;
+
+        let mut order: Vec<usize> = (0..samples.len()).collect();
+
+        for iteration in 0..iterations {
+            shuffle(&mut order, iteration as u64 + 1);
+
+            for index in order.iter() {
+                let (words, truth) = &samples[*index];
+
+                let mut previous = START_TAG.to_string();
+                let mut previous2 = START_TAG.to_string();
+
+                for (i, tag) in truth.iter().enumerate().take(words.len()) {
+                    let features = extract_features(words, i, &previous, &previous2);
+                    let guess = model.best_tag(&features);
+
+                    model.update(tag, &guess, &features);
+
+                    // train on the gold history like it is done at prediction with guesses
+                    previous2 = previous;
+                    previous = tag.clone();
+                }
+            }
+        }
+
+        model.average();
+
+        model
+    }
+
+    pub fn predict(&self, words: &[Word]) -> Vec<String> {
+        let mut tags: Vec<String> = vec![];
+
+        let mut previous = START_TAG.to_string();
+        let mut previous2 = START_TAG.to_string();
+
+        for i in 0..words.len() {
+            let features = extract_features(words, i, &previous, &previous2);
+            let guess = self.best_tag(&features);
+
+            previous2 = previous;
+            previous = guess.clone();
+
+            tags.push(guess);
+        }
+
+        tags
+    }
+
+    pub fn to_weights(&self) -> Vec<TaggerWeight> {
+        let mut out: Vec<TaggerWeight> = vec![];
+
+        for (feature, tags) in self.weights.iter() {
+            for (tag, weight) in tags.iter() {
+                if *weight != 0.0 {
+                    out.push(TaggerWeight {
+                        feature: feature.clone(),
+                        tag: tag.clone(),
+                        weight: *weight,
+                    });
+                }
+            }
+        }
+
+        out
+    }
+
+    pub fn from_weights(tags: &[String], weights: &[TaggerWeight]) -> Perceptron {
+        let mut model = Perceptron {
+            tags: tags.to_vec(),
+            ..Perceptron::default()
+        };
+
+        for item in weights.iter() {
+            model
+                .weights
+                .entry(item.feature.clone())
+                .or_default()
+                .insert(item.tag.clone(), item.weight);
+        }
+
+        model
+    }
+
+    fn best_tag(&self, features: &[String]) -> String {
+        let mut scores: HashMap<&str, f64> = HashMap::new();
+
+        for feature in features.iter() {
+            if let Some(tags) = self.weights.get(feature) {
+                for (tag, weight) in tags.iter() {
+                    *scores.entry(tag.as_str()).or_default() += weight;
+                }
+            }
+        }
+
+        // ties are resolved by the tags order to keep predictions stable
+        let mut best = OUTSIDE_TAG;
+        let mut best_score = f64::MIN;
+
+        for tag in self.tags.iter() {
+            let score = scores.get(tag.as_str()).cloned().unwrap_or(0.0);
+
+            if score > best_score {
+                best = tag.as_str();
+                best_score = score;
+            }
+        }
+
+        best.to_string()
+    }
+
+    fn update(&mut self, truth: &str, guess: &str, features: &[String]) {
+        self.instances += 1;
+
+        if truth == guess {
+            return;
+        }
+
+        for feature in features.iter() {
+            self.update_weight(feature, truth, 1.0);
+            self.update_weight(feature, guess, -1.0);
+        }
+    }
+
+    fn update_weight(&mut self, feature: &str, tag: &str, delta: f64) {
+        let key = (feature.to_string(), tag.to_string());
+
+        let weight = self
+            .weights
+            .entry(feature.to_string())
+            .or_default()
+            .entry(tag.to_string())
+            .or_default();
+
+        let since = self.instances - self.timestamps.get(&key).cloned().unwrap_or(0);
+
+        *self.totals.entry(key.clone()).or_default() += since as f64 * *weight;
+        *weight += delta;
+
+        self.timestamps.insert(key, self.instances);
+    }
+
+    fn average(&mut self) {
+        if self.instances == 0 {
+            return;
+        }
+
+        for (feature, tags) in self.weights.iter_mut() {
+            for (tag, weight) in tags.iter_mut() {
+                let key = (feature.clone(), tag.clone());
+
+                let since = self.instances - self.timestamps.get(&key).cloned().unwrap_or(0);
+                let total = self.totals.get(&key).cloned().unwrap_or(0.0) + since as f64 * *weight;
+
+                *weight = total / self.instances as f64;
+            }
+        }
+
+        self.totals.clear();
+        self.timestamps.clear();
+    }
+}
+
+fn extract_features(words: &[Word], i: usize, previous: &str, previous2: &str) -> Vec<String> {
+    let word = words[i].text.as_str();
+    let lower = word.to_lowercase();
+    let chars: Vec<char> = lower.chars().collect();
+
+    let suffix: String = chars[chars.len().saturating_sub(3)..].iter().collect();
+    let prefix: String = chars[..chars.len().min(2)].iter().collect();
+
+    let shape: String = word
+        .chars()
+        .map(|c| {
+            if c.is_uppercase() {
+                'X'
+            } else if c.is_lowercase() {
+                'x'
+            } else if c.is_numeric() {
+                'd'
+            } else {
+                c
+            }
+        })
+        .collect();
+
+    let previous_word = if i > 0 {
+        words[i - 1].text.to_lowercase()
+    } else {
+        START_TAG.to_string()
+    };
+
+    let next_word = if i + 1 < words.len() {
+        words[i + 1].text.to_lowercase()
+    } else {
+        "-END-".to_string()
+    };
+
+    vec![
+        "bias".to_string(),
+        format!("w={}", lower),
+        format!("suffix={}", suffix),
+        format!("prefix={}", prefix),
+        format!("shape={}", shape),
+        format!(
+            "title={}",
+            word.chars().next().is_some_and(|c| c.is_uppercase())
+        ),
+        format!("prev_tag={}", previous),
+        format!("prev_tags={}+{}", previous2, previous),
+        format!("prev_tag_w={}+{}", previous, lower),
+        format!("prev_w={}", previous_word),
+        format!("next_w={}", next_word),
+    ]
+}
+
+/// deterministic fisher-yates shuffle, so trainings on the same data give the same model.
+fn shuffle(items: &mut [usize], seed: u64) {
+    let mut state = seed
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+
+    for i in (1..items.len()).rev() {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+
+        let j = (state >> 33) as usize % (i + 1);
+
+        items.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_held_out_keeps_every_kth_sample() {
+        let (train, test) = split_held_out(10, 0.2);
+
+        assert_eq!(test, vec![4, 9]);
+        assert_eq!(train, vec![0, 1, 2, 3, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn split_held_out_holds_one_sample_out_of_a_small_corpus() {
+        // k is 5, no index reaches it
+        let (train, test) = split_held_out(3, 0.2);
+
+        assert_eq!(test, vec![2]);
+        assert_eq!(train, vec![0, 1]);
+
+        let (train, test) = split_held_out(2, 0.1);
+
+        assert_eq!(test, vec![1]);
+        assert_eq!(train, vec![0]);
+    }
+
+    #[test]
+    fn split_held_out_needs_two_samples() {
+        let (train, test) = split_held_out(1, 0.5);
+
+        assert_eq!(train, vec![0]);
+        assert!(test.is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+/// finds the character indices where `term` starts in `content`.
+pub fn find_occurrences(content: &[char], term: &[char], ignore_case: bool) -> Vec<usize> {
+    if term.is_empty() || term.len() > content.len() {
+        return vec![];
+    }
+
+    (0..=(content.len() - term.len()))
+        .filter(|start| {
+            term.iter().enumerate().all(|(i, c)| match ignore_case {
+                true => content[start + i].to_lowercase().eq(c.to_lowercase()),
+                false => content[start + i] == *c,
+            })
+        })
+        .collect()
+}
+
+/// extracts the words of a mongodb text search, excluded words and quotes are ignored.
+pub fn get_search_terms(query: &str) -> Vec<String> {
+    query
+        .split_whitespace()
+        .filter(|it| !it.starts_with('-'))
+        .map(|it| it.trim_matches('"').to_string())
+        .filter(|it| !it.is_empty())
+        .collect()
+}
+
+/// builds up to `max` excerpts of the content around the terms, matches are wrapped
+/// in `<mark>` tags and the content is html escaped.
+pub fn build_snippets(content: &str, terms: &[String], context: usize, max: usize) -> Vec<String> {
+    let chars: Vec<char> = content.chars().collect();
+
+    let mut matches: Vec<(usize, usize)> = terms
+        .iter()
+        .flat_map(|term| {
+            let term: Vec<char> = term.chars().collect();
+
+            find_occurrences(&chars, &term, true)
+                .into_iter()
+                .map(move |start| (start, start + term.len()))
+        })
+        .collect();
+
+    matches.sort();
+
+    // drop matches nested in a previous one
+    let mut kept: Vec<(usize, usize)> = vec![];
+
+    for item in matches {
+        if kept.last().is_none_or(|last| item.0 >= last.1) {
+            kept.push(item);
+        }
+    }
+
+    // group matches whose context windows overlap, as (from, to, matches)
+    type Window = (usize, usize, Vec<(usize, usize)>);
+
+    let mut windows: Vec<Window> = vec![];
+
+    for (start, end) in kept {
+        let from = start.saturating_sub(context);
+        let to = (end + context).min(chars.len());
+
+        match windows.last_mut() {
+            Some(window) if from <= window.1 => {
+                window.1 = to;
+                window.2.push((start, end));
+            }
+            _ => windows.push((from, to, vec![(start, end)])),
+        }
+    }
+
+    windows
+        .into_iter()
+        .take(max)
+        .map(|(from, to, items)| {
+            let mut snippet = String::new();
+            let mut cursor = from;
+
+            if from > 0 {
+                snippet.push_str("...");
+            }
+
+            for (start, end) in items {
+                snippet.push_str(&escape_html(&chars[cursor..start]));
+                snippet.push_str("<mark>");
+                snippet.push_str(&escape_html(&chars[start..end]));
+                snippet.push_str("</mark>");
+                cursor = end;
+            }
+
+            snippet.push_str(&escape_html(&chars[cursor..to]));
+
+            if to < chars.len() {
+                snippet.push_str("...");
+            }
+
+            snippet
+        })
+        .collect()
+}
+
+/// escapes the characters having a meaning in html.
+pub fn escape_html(chars: &[char]) -> String {
+    let mut escaped = String::with_capacity(chars.len());
+
+    for char in chars {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            _ => escaped.push(*char),
+        }
+    }
+
+    escaped
+}
+
+/// checks that the characters around `start..end` are not alphanumeric.
+pub fn is_whole_word(content: &[char], start: usize, end: usize) -> bool {
+    let before = start == 0 || !content[start - 1].is_alphanumeric();
+    let after = end >= content.len() || !content[end].is_alphanumeric();
+
+    before && after
+}
+
+/// quotes a csv field when it contains a separator, a quote or a line break.
+pub fn escape_csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        return format!("\"{}\"", value.replace('"', "\"\""));
+    }
+
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_snippets_escapes_the_content() {
+        let snippets = build_snippets(
+            "<img src=x onerror=alert(1)> a <b>term</b> & more",
+            &["term".to_string()],
+            100,
+            1,
+        );
+
+        assert_eq!(
+            snippets,
+            vec![
+                "&lt;img src=x onerror=alert(1)&gt; a &lt;b&gt;<mark>term</mark>&lt;/b&gt; &amp; more"
+                    .to_string()
+            ]
+        );
+    }
+}
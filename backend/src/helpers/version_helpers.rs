@@ -0,0 +1,12 @@
+/// etag of an annotation version, quoted as the header expects.
+pub fn format_etag(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+/// reads the version of an `If-Match` header value, weak tags are accepted.
+pub fn parse_etag_version(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let value = value.strip_prefix("W/").unwrap_or(value);
+
+    value.trim_matches('"').parse::<i64>().ok()
+}
@@ -0,0 +1,543 @@
+use std::{
+    sync::Once,
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, Bson, DateTime},
+    options::{FindOneAndUpdateOptions, ReturnDocument},
+};
+use ring::{
+    hmac,
+    rand::{SecureRandom, SystemRandom},
+};
+use serde_json::{json, Value};
+
+use crate::{
+    database::mongodb::DB,
+    models::{
+        realtime_model::RealtimeEvent,
+        text_annotation_model::TextAnnotation,
+        webhook_model::{DeliveryAttempt, DeliveryStatus, Webhook, WebhookDelivery, WebhookEvent},
+    },
+    object::common::CommonError,
+};
+
+static DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// delays between the attempts of a delivery, it fails after the last one.
+static RETRY_DELAYS_SECONDS: [i64; 5] = [10, 60, 5 * 60, 30 * 60, 2 * 60 * 60];
+
+/// a claimed delivery is retried by any instance once this is over, in case the
+/// instance sending it stopped.
+static CLAIM_SECONDS: i64 = 60;
+
+static WORKER_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+static WORKER: Once = Once::new();
+
+pub fn generate_webhook_secret() -> Result<String, CommonError> {
+    let mut bytes = [0u8; 32];
+
+    if SystemRandom::new().fill(&mut bytes).is_err() {
+        return Err(CommonError {
+            description: "unable to generate webhook secret".to_string(),
+        });
+    }
+
+    Ok(to_hex(&bytes))
+}
+
+/// hmac-sha256 of `<timestamp>.<payload>`, the timestamp lets receivers refuse replays.
+pub fn sign_payload(secret: &str, timestamp: i64, payload: &str) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let tag = hmac::sign(&key, format!("{}.{}", timestamp, payload).as_bytes());
+
+    format!("sha256={}", to_hex(tag.as_ref()))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|it| format!("{:02x}", it)).collect()
+}
+
+fn claim_until() -> DateTime {
+    DateTime::from_millis(Utc::now().timestamp_millis() + CLAIM_SECONDS * 1000)
+}
+
+/// queues a delivery for every active webhook listening to the event on the annotation,
+/// they are sent by the worker so the request is not slowed down by the receivers.
+pub fn queue_webhook_event(event: WebhookEvent, annotation: &TextAnnotation, data: Value) {
+    let mut scopes = vec![doc! {"user_id": annotation.user_id, "project_id": Bson::Null}];
+
+    if let Some(project_id) = annotation.project_id {
+        scopes.push(doc! {"project_id": project_id});
+    }
+
+    let fetch_result = DB.webhook_collection.find(
+        doc! {
+          "active": true,
+          "events": to_bson(&event).unwrap(),
+          "$or": scopes,
+        },
+        None,
+    );
+
+    if let Err(err) = fetch_result {
+        log::warn!("unable to find the webhooks to notify: {}", err);
+        return;
+    }
+
+    let webhooks: Vec<Webhook> = fetch_result.unwrap().filter_map(|it| it.ok()).collect();
+
+    if webhooks.is_empty() {
+        return;
+    }
+
+    let deliveries: Vec<WebhookDelivery> = webhooks
+        .iter()
+        .map(|webhook| {
+            let id = ObjectId::new();
+
+            let payload = json!({
+                "id": id,
+                "event": event,
+                "webhook_id": webhook._id,
+                "created_at": Utc::now().timestamp_millis(),
+                "annotation": {
+                    "_id": annotation._id,
+                    "title": annotation.title,
+                    "status": annotation.status,
+                    "user_id": annotation.user_id,
+                    "project_id": annotation.project_id,
+                    "version": annotation.version,
+                },
+                "data": data,
+            });
+
+            WebhookDelivery {
+                _id: Some(id),
+                webhook_id: webhook._id.unwrap(),
+                event,
+                payload: payload.to_string(),
+                status: DeliveryStatus::Pending,
+                attempt_count: 0,
+                attempts: vec![],
+                next_attempt_at: Some(DateTime::now()),
+                created_at: DateTime::now(),
+                updated_at: DateTime::now(),
+            }
+        })
+        .collect();
+
+    if let Err(err) = DB.webhook_delivery_collection.insert_many(deliveries, None) {
+        log::warn!("unable to queue the webhook deliveries: {}", err);
+    }
+}
+
+/// queues the label and token changes, the event is sent as the delivery data.
+pub fn queue_realtime_webhook_event(annotation: &TextAnnotation, event: &RealtimeEvent) {
+    let webhook_event = match event {
+        RealtimeEvent::LabelCreated { .. } => WebhookEvent::LabelCreated,
+        RealtimeEvent::LabelUpdated { .. } => WebhookEvent::LabelUpdated,
+        RealtimeEvent::LabelDeleted { .. } => WebhookEvent::LabelDeleted,
+        RealtimeEvent::TokenCreated { .. } => WebhookEvent::TokenCreated,
+        RealtimeEvent::TokenUpdated { .. } => WebhookEvent::TokenUpdated,
+        RealtimeEvent::TokenDeleted { .. } => WebhookEvent::TokenDeleted,
+        RealtimeEvent::TitleUpdated { .. }
+        | RealtimeEvent::SuggestionsUpdated
+        | RealtimeEvent::AssigneeUpdated { .. }
+        | RealtimeEvent::Presence { .. } => return,
+    };
+
+    let mut data = serde_json::to_value(event).unwrap_or_default();
+
+    if let Some(data) = data.as_object_mut() {
+        data.remove("type");
+    }
+
+    queue_webhook_event(webhook_event, annotation, data);
+}
+
+/// starts sending the pending deliveries in the background, once per instance.
+pub fn start_webhook_worker() {
+    WORKER.call_once(|| {
+        thread::spawn(run_worker);
+    });
+}
+
+fn run_worker() {
+    loop {
+        while let Some(delivery) = claim_due_delivery() {
+            let webhook = DB
+                .webhook_collection
+                .find_one(doc! {"_id": delivery.webhook_id}, None);
+
+            match webhook {
+                Ok(Some(webhook)) => {
+                    deliver(&webhook, delivery);
+                }
+                // the deliveries of a deleted webhook are removed with it
+                Ok(None) => {}
+                Err(err) => {
+                    log::warn!("unable to find the delivery webhook: {}", err);
+                    break;
+                }
+            }
+        }
+
+        thread::sleep(WORKER_POLL_INTERVAL);
+    }
+}
+
+fn claim_due_delivery() -> Option<WebhookDelivery> {
+    let claim_result = DB.webhook_delivery_collection.find_one_and_update(
+        doc! {
+          "status": to_bson(&DeliveryStatus::Pending).unwrap(),
+          "next_attempt_at": {"$lte": DateTime::now()},
+        },
+        doc! {"$set": {"next_attempt_at": claim_until()}},
+        FindOneAndUpdateOptions::builder()
+            .sort(doc! {"next_attempt_at": 1})
+            .return_document(ReturnDocument::After)
+            .build(),
+    );
+
+    match claim_result {
+        Ok(delivery) => delivery,
+        Err(err) => {
+            log::warn!("unable to claim a webhook delivery: {}", err);
+            None
+        }
+    }
+}
+
+/// resets a finished delivery so it is sent again with a full set of retries.
+pub fn claim_redelivery(delivery: &WebhookDelivery) -> Option<WebhookDelivery> {
+    DB.webhook_delivery_collection
+        .find_one_and_update(
+            doc! {
+              "_id": delivery._id.unwrap(),
+              "status": {"$ne": to_bson(&DeliveryStatus::Pending).unwrap()},
+            },
+            doc! {"$set": {
+              "status": to_bson(&DeliveryStatus::Pending).unwrap(),
+              "attempt_count": 0,
+              "next_attempt_at": claim_until(),
+              "updated_at": DateTime::now(),
+            }},
+            FindOneAndUpdateOptions::builder()
+                .return_document(ReturnDocument::After)
+                .build(),
+        )
+        .ok()
+        .flatten()
+}
+
+/// sends a claimed delivery and records the attempt, a failed attempt is scheduled
+/// again until the retries run out.
+pub fn deliver(webhook: &Webhook, delivery: WebhookDelivery) -> WebhookDelivery {
+    let attempt = match webhook.active {
+        true => send_delivery(webhook, &delivery),
+        false => DeliveryAttempt {
+            attempted_at: DateTime::now(),
+            status_code: None,
+            error: Some("webhook is disabled".to_string()),
+            duration_ms: 0,
+        },
+    };
+
+    let attempt_count = delivery.attempt_count + 1;
+
+    let (status, next_attempt_at) =
+        next_delivery_state(&attempt, delivery.attempt_count, webhook.active);
+
+    let next_attempt_at = match next_attempt_at {
+        Some(next_attempt_at) => Bson::DateTime(next_attempt_at),
+        None => Bson::Null,
+    };
+
+    let update_result = DB.webhook_delivery_collection.find_one_and_update(
+        doc! {"_id": delivery._id.unwrap()},
+        doc! {
+          "$set": {
+            "status": to_bson(&status).unwrap(),
+            "attempt_count": attempt_count,
+            "next_attempt_at": next_attempt_at,
+            "updated_at": DateTime::now(),
+          },
+          "$push": {"attempts": to_bson(&attempt).unwrap()},
+        },
+        FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build(),
+    );
+
+    match update_result {
+        Ok(Some(updated)) => updated,
+        Ok(None) => delivery,
+        Err(err) => {
+            log::warn!("unable to record the webhook delivery attempt: {}", err);
+            delivery
+        }
+    }
+}
+
+/// a failed attempt is retried after the delay following the attempts already made.
+fn next_delivery_state(
+    attempt: &DeliveryAttempt,
+    previous_attempts: i64,
+    active: bool,
+) -> (DeliveryStatus, Option<DateTime>) {
+    let retry_delay = RETRY_DELAYS_SECONDS.get(previous_attempts as usize);
+
+    match (attempt.error.is_none(), retry_delay) {
+        (true, _) => (DeliveryStatus::Succeeded, None),
+        (false, Some(delay)) if active => (
+            DeliveryStatus::Pending,
+            Some(DateTime::from_millis(
+                Utc::now().timestamp_millis() + delay * 1000,
+            )),
+        ),
+        (false, _) => (DeliveryStatus::Failed, None),
+    }
+}
+
+fn send_delivery(webhook: &Webhook, delivery: &WebhookDelivery) -> DeliveryAttempt {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(DELIVERY_TIMEOUT)
+        .redirects(0)
+        .build();
+
+    let timestamp = Utc::now().timestamp();
+    let event = to_bson(&delivery.event).unwrap();
+
+    let started = Instant::now();
+
+    let response = agent
+        .post(webhook.url.as_str())
+        .set("Content-Type", "application/json")
+        .set("X-Webhook-Id", webhook._id.unwrap().to_hex().as_str())
+        .set(
+            "X-Webhook-Delivery",
+            delivery._id.unwrap().to_hex().as_str(),
+        )
+        .set("X-Webhook-Event", event.as_str().unwrap_or_default())
+        .set("X-Webhook-Timestamp", timestamp.to_string().as_str())
+        .set(
+            "X-Webhook-Signature",
+            sign_payload(&webhook.secret, timestamp, &delivery.payload).as_str(),
+        )
+        .send_string(delivery.payload.as_str());
+
+    let duration_ms = started.elapsed().as_millis() as i64;
+
+    let (status_code, error) = match response {
+        Ok(response) if (200..300).contains(&response.status()) => {
+            (Some(response.status() as i64), None)
+        }
+        Ok(response) => (
+            Some(response.status() as i64),
+            Some(format!(
+                "receiver responded with status {}",
+                response.status()
+            )),
+        ),
+        Err(ureq::Error::Status(code, _)) => (
+            Some(code as i64),
+            Some(format!("receiver responded with status {}", code)),
+        ),
+        Err(ureq::Error::Transport(transport)) => (
+            None,
+            Some(format!("unable to reach receiver: {}", transport)),
+        ),
+    };
+
+    DeliveryAttempt {
+        attempted_at: DateTime::now(),
+        status_code,
+        error,
+        duration_ms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::mock_server_helpers::{MockResponse, MockServer};
+
+    fn webhook(url: &str) -> Webhook {
+        Webhook {
+            _id: Some(ObjectId::new()),
+            user_id: ObjectId::new(),
+            project_id: None,
+            name: "receiver".to_string(),
+            url: url.to_string(),
+            secret: "0123456789abcdef".to_string(),
+            events: vec![WebhookEvent::TokenCreated],
+            active: true,
+            created_at: DateTime::now(),
+        }
+    }
+
+    fn delivery(webhook: &Webhook, status: DeliveryStatus, attempt_count: i64) -> WebhookDelivery {
+        WebhookDelivery {
+            _id: Some(ObjectId::new()),
+            webhook_id: webhook._id.unwrap(),
+            event: WebhookEvent::TokenCreated,
+            payload: json!({"event": "token_created", "data": {"value": "Paris"}}).to_string(),
+            status,
+            attempt_count,
+            attempts: vec![],
+            next_attempt_at: None,
+            created_at: DateTime::now(),
+            updated_at: DateTime::now(),
+        }
+    }
+
+    fn from_hex(value: &str) -> Vec<u8> {
+        (0..value.len())
+            .step_by(2)
+            .map(|it| u8::from_str_radix(&value[it..it + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn send_delivery_signs_the_timestamp_and_payload() {
+        let server = MockServer::start(vec![MockResponse::new(204, "")]);
+        let webhook = webhook(&server.url);
+        let delivery = delivery(&webhook, DeliveryStatus::Pending, 0);
+
+        let attempt = send_delivery(&webhook, &delivery);
+
+        assert_eq!(attempt.status_code, Some(204));
+        assert!(attempt.error.is_none());
+
+        let requests = server.requests();
+        let request = &requests[0];
+
+        assert_eq!(request.body, delivery.payload);
+        assert_eq!(
+            request.headers["x-webhook-delivery"],
+            delivery._id.unwrap().to_hex()
+        );
+        assert_eq!(request.headers["x-webhook-event"], "token_created");
+
+        // checked the way a receiver would, from the headers and the raw body
+        let timestamp = &request.headers["x-webhook-timestamp"];
+        let signature = request.headers["x-webhook-signature"]
+            .strip_prefix("sha256=")
+            .unwrap();
+
+        let key = hmac::Key::new(hmac::HMAC_SHA256, webhook.secret.as_bytes());
+        let message = format!("{}.{}", timestamp, request.body);
+
+        assert!(hmac::verify(&key, message.as_bytes(), &from_hex(signature)).is_ok());
+
+        let other_key = hmac::Key::new(hmac::HMAC_SHA256, b"another secret");
+
+        assert!(hmac::verify(&other_key, message.as_bytes(), &from_hex(signature)).is_err());
+    }
+
+    #[test]
+    fn failed_attempt_is_retried_after_the_next_delay() {
+        let server = MockServer::start(vec![MockResponse::new(503, "")]);
+        let webhook = webhook(&server.url);
+
+        for (previous_attempts, delay) in RETRY_DELAYS_SECONDS.iter().enumerate() {
+            let delivery = delivery(&webhook, DeliveryStatus::Pending, previous_attempts as i64);
+
+            let attempt = send_delivery(&webhook, &delivery);
+
+            assert_eq!(attempt.status_code, Some(503));
+            assert_eq!(
+                attempt.error.as_deref(),
+                Some("receiver responded with status 503")
+            );
+
+            let before = Utc::now().timestamp_millis();
+            let (status, next_attempt_at) =
+                next_delivery_state(&attempt, delivery.attempt_count, webhook.active);
+            let after = Utc::now().timestamp_millis();
+
+            assert_eq!(status, DeliveryStatus::Pending);
+
+            let next_attempt_at = next_attempt_at.unwrap().timestamp_millis();
+
+            assert!(next_attempt_at >= before + delay * 1000);
+            assert!(next_attempt_at <= after + delay * 1000);
+        }
+
+        // the retries ran out
+        let delivery = delivery(
+            &webhook,
+            DeliveryStatus::Pending,
+            RETRY_DELAYS_SECONDS.len() as i64,
+        );
+        let attempt = send_delivery(&webhook, &delivery);
+
+        assert_eq!(
+            next_delivery_state(&attempt, delivery.attempt_count, webhook.active),
+            (DeliveryStatus::Failed, None)
+        );
+        assert_eq!(server.requests().len(), RETRY_DELAYS_SECONDS.len() + 1);
+    }
+
+    #[test]
+    fn failed_attempt_of_disabled_webhook_is_not_retried() {
+        let attempt = DeliveryAttempt {
+            attempted_at: DateTime::now(),
+            status_code: None,
+            error: Some("webhook is disabled".to_string()),
+            duration_ms: 0,
+        };
+
+        assert_eq!(
+            next_delivery_state(&attempt, 0, false),
+            (DeliveryStatus::Failed, None)
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a mongodb at MONGO_URL"]
+    fn claim_redelivery_resets_and_resends_failed_delivery() {
+        let server = MockServer::start(vec![MockResponse::new(200, "")]);
+        let webhook = webhook(&server.url);
+
+        let mut failed = delivery(
+            &webhook,
+            DeliveryStatus::Failed,
+            RETRY_DELAYS_SECONDS.len() as i64,
+        );
+        failed.attempts = vec![DeliveryAttempt {
+            attempted_at: DateTime::now(),
+            status_code: Some(500),
+            error: Some("receiver responded with status 500".to_string()),
+            duration_ms: 1,
+        }];
+
+        DB.webhook_delivery_collection
+            .insert_one(&failed, None)
+            .unwrap();
+
+        let claimed = claim_redelivery(&failed).unwrap();
+
+        assert_eq!(claimed.status, DeliveryStatus::Pending);
+        assert_eq!(claimed.attempt_count, 0);
+        // already pending, it can not be claimed twice
+        assert!(claim_redelivery(&claimed).is_none());
+
+        let delivered = deliver(&webhook, claimed);
+
+        assert_eq!(delivered.status, DeliveryStatus::Succeeded);
+        assert_eq!(delivered.attempt_count, 1);
+        assert_eq!(delivered.attempts.len(), 2);
+        assert_eq!(server.requests().len(), 1);
+        assert_eq!(server.requests()[0].body, failed.payload);
+
+        DB.webhook_delivery_collection
+            .delete_one(doc! {"_id": failed._id.unwrap()}, None)
+            .unwrap();
+    }
+}
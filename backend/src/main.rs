@@ -5,18 +5,25 @@ mod helpers;
 mod middleware;
 mod models;
 mod object;
+mod policies;
 mod routes;
 mod validators;
 
 use actix_multipart::form::tempfile::TempFileConfig;
 use actix_web::{dev::Service, middleware::Logger, App, HttpServer};
 use config::cors::create_cors;
-use database::files::upload_files;
+use controllers::model_backend_controller::start_pre_annotation_worker;
+use database::{files::upload_files, migrations::run_migrations};
 use futures_util::future::FutureExt;
+use helpers::webhook_helpers::start_webhook_worker;
 
 use routes::{
-    auth_routes::auth_routes, data_routes::data_routes, text_annotation_routes::annotation_routes,
-    user_routes::user_routes,
+    audit_routes::audit_routes, auth_routes::auth_routes, data_routes::data_routes,
+    evaluation_routes::evaluation_routes, model_backend_routes::model_backend_routes,
+    project_routes::project_routes, share_routes::share_routes, stats_routes::stats_routes,
+    tagger_routes::tagger_routes, team_routes::team_routes,
+    text_annotation_routes::annotation_routes, user_routes::user_routes,
+    webhook_routes::webhook_routes,
 };
 
 use crate::middleware::auth_middleware::use_auth_middleware;
@@ -30,12 +37,27 @@ async fn main() -> std::io::Result<()> {
 
     std::fs::create_dir_all("./tmp")?;
 
+    run_migrations();
+
+    start_webhook_worker();
+
+    start_pre_annotation_worker();
+
     HttpServer::new(move || {
         App::new()
             .service(user_routes())
             .service(auth_routes())
+            .service(audit_routes())
             .service(annotation_routes())
             .service(data_routes())
+            .service(evaluation_routes())
+            .service(model_backend_routes())
+            .service(project_routes())
+            .service(share_routes())
+            .service(stats_routes())
+            .service(tagger_routes())
+            .service(team_routes())
+            .service(webhook_routes())
             .app_data(TempFileConfig::default().directory("./tmp"))
             .service(upload_files)
             .wrap_fn(|req, srv| {
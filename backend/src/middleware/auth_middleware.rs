@@ -6,7 +6,10 @@ use mongodb::bson::doc;
 
 use crate::{
     database::mongodb::DB,
-    helpers::token_helpers::{get_token_claims, get_token_from_auth_string},
+    helpers::{
+        request_helpers::resolve_client_ip,
+        token_helpers::{get_token_claims, get_token_from_auth_string},
+    },
     models::user_model::User,
 };
 
@@ -15,6 +18,8 @@ pub struct UserAuthContext {
     pub user: User,
     pub user_id: ObjectId,
     pub token: String,
+    /// address of the client, recorded in the audit events
+    pub ip: Option<String>,
 }
 
 pub fn use_auth_middleware(req: &ServiceRequest) {
@@ -40,10 +45,21 @@ pub fn use_auth_middleware(req: &ServiceRequest) {
         return;
     }
 
-    let claim_result = get_token_claims(token.as_ref().unwrap().clone());
+    let ctx = get_auth_from_token(token.unwrap());
+
+    if let Some(mut ctx) = ctx {
+        ctx.ip = resolve_client_ip(req.peer_addr(), req.connection_info().realip_remote_addr());
+
+        req.extensions_mut().insert::<UserAuthContext>(ctx);
+    }
+}
+
+/// resolves the user of a bearer token.
+pub fn get_auth_from_token(token: String) -> Option<UserAuthContext> {
+    let claim_result = get_token_claims(token.clone());
 
     if claim_result.is_err() {
-        return;
+        return None;
     }
 
     let id = claim_result.unwrap().sub;
@@ -51,7 +67,7 @@ pub fn use_auth_middleware(req: &ServiceRequest) {
     let user_id = ObjectId::parse_str(&id);
 
     if user_id.as_ref().is_err() {
-        return;
+        return None;
     }
 
     // find user
@@ -60,16 +76,15 @@ pub fn use_auth_middleware(req: &ServiceRequest) {
         .find_one(doc! {"_id":user_id.clone().unwrap()}, None);
 
     if user_result.as_ref().is_err() || user_result.as_ref().unwrap().is_none() {
-        return;
+        return None;
     }
 
     let user = user_result.unwrap().unwrap();
 
-    let ctx = UserAuthContext {
-        token: token.unwrap(),
+    Some(UserAuthContext {
+        token,
         user_id: user_id.unwrap(),
         user,
-    };
-
-    req.extensions_mut().insert::<UserAuthContext>(ctx);
+        ip: None,
+    })
 }
@@ -0,0 +1,67 @@
+use actix_web::{body::BoxBody, HttpResponse, Responder};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+/// a span of a layer, `label` references a label of the document.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AdjudicationSpan {
+    pub start: i64,
+    pub end: i64,
+    pub label: ObjectId,
+}
+
+/// spans given by a group of annotators in the conflict's range, empty when they
+/// did not annotate it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConflictVersion {
+    pub annotators: Vec<ObjectId>,
+    pub spans: Vec<AdjudicationSpan>,
+}
+
+/// overlapping spans the annotators disagree on.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdjudicationConflict {
+    /// built from the range, as `start-end`
+    pub id: String,
+    pub start: i64,
+    pub end: i64,
+    pub versions: Vec<ConflictVersion>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Adjudication {
+    pub annotation_id: ObjectId,
+    pub annotators: Vec<ObjectId>,
+    /// spans every annotator agrees on
+    pub agreed: Vec<AdjudicationSpan>,
+    pub conflicts: Vec<AdjudicationConflict>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdjudicationSpanBody {
+    pub start: i64,
+    pub end: i64,
+    pub label: String,
+}
+
+/// keeps the version of `user_id`, or the custom `spans` when no annotator is given.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConflictResolution {
+    pub conflict_id: String,
+    pub user_id: Option<String>,
+    pub spans: Option<Vec<AdjudicationSpanBody>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdjudicateBody {
+    pub resolutions: Vec<ConflictResolution>,
+    pub version: Option<i64>,
+}
+
+impl Responder for Adjudication {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
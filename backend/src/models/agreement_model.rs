@@ -0,0 +1,65 @@
+use actix_web::{body::BoxBody, HttpResponse, Responder};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgreementQueryParams {
+    /// number of documents with the lowest agreement to list
+    pub lowest: Option<i64>,
+}
+
+/// token level kappas are computed on word tags, f1 scores on spans.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct AgreementScores {
+    /// averaged over the annotator pairs
+    pub cohen_kappa: f64,
+    pub fleiss_kappa: f64,
+    pub exact_f1: f64,
+    pub partial_f1: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LabelAgreement {
+    pub label: String,
+    pub scores: AgreementScores,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PairAgreement {
+    pub first: ObjectId,
+    pub second: ObjectId,
+    /// documents annotated by both
+    pub documents: i64,
+    pub cohen_kappa: f64,
+    pub exact_f1: f64,
+    pub partial_f1: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DocumentAgreement {
+    pub annotation_id: ObjectId,
+    pub title: String,
+    pub annotators: i64,
+    pub cohen_kappa: f64,
+    pub fleiss_kappa: f64,
+    pub exact_f1: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgreementReport {
+    /// documents with at least two annotator layers
+    pub documents: i64,
+    pub annotators: Vec<ObjectId>,
+    pub overall: AgreementScores,
+    pub labels: Vec<LabelAgreement>,
+    pub pairs: Vec<PairAgreement>,
+    pub lowest_documents: Vec<DocumentAgreement>,
+}
+
+impl Responder for AgreementReport {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
@@ -0,0 +1,130 @@
+use actix_web::{body::BoxBody, HttpResponse, Responder};
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    middleware::auth_middleware::UserAuthContext, models::text_annotation_model::TextAnnotation,
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    SignUp,
+    SignIn,
+    SignInFailed,
+    AnnotationCreated,
+    AnnotationUpdated,
+    AnnotationStatusUpdated,
+    AnnotationDeleted,
+    LabelCreated,
+    LabelUpdated,
+    LabelDeleted,
+    TokenCreated,
+    TokenDeleted,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuditEvent {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub _id: Option<ObjectId>,
+    pub action: AuditAction,
+    /// user who did the action, none for a failed sign in on an unknown login
+    pub actor_id: Option<ObjectId>,
+    /// user the event belongs to: the annotation's owner or the account's user,
+    /// kept so events stay readable after the annotation is deleted
+    pub owner_id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotation_id: Option<ObjectId>,
+    /// label or token the action was made on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_id: Option<ObjectId>,
+    pub ip: Option<String>,
+    /// the login of a failed sign in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    pub created_at: DateTime,
+}
+
+impl AuditEvent {
+    pub fn new(action: AuditAction, actor_id: Option<ObjectId>, ip: Option<String>) -> AuditEvent {
+        AuditEvent {
+            _id: None,
+            action,
+            actor_id,
+            owner_id: None,
+            annotation_id: None,
+            target_id: None,
+            ip,
+            details: None,
+            created_at: DateTime::now(),
+        }
+    }
+
+    pub fn from_auth(action: AuditAction, auth: &UserAuthContext) -> AuditEvent {
+        AuditEvent::new(action, Some(auth.user_id), auth.ip.clone())
+    }
+
+    pub fn set_owner(mut self, owner_id: Option<ObjectId>) -> AuditEvent {
+        self.owner_id = owner_id;
+
+        self
+    }
+
+    pub fn set_annotation(mut self, annotation: &TextAnnotation) -> AuditEvent {
+        self.annotation_id = annotation._id;
+        self.owner_id = Some(annotation.user_id);
+
+        self
+    }
+
+    pub fn set_target(mut self, target_id: Option<ObjectId>) -> AuditEvent {
+        self.target_id = target_id;
+
+        self
+    }
+
+    pub fn set_details(mut self, details: &str) -> AuditEvent {
+        self.details = Some(details.to_string());
+
+        self
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditQueryParams {
+    pub action: Option<AuditAction>,
+    pub actor_id: Option<String>,
+    pub annotation_id: Option<String>,
+    pub target_id: Option<String>,
+    /// only for admins, the others can only read their own events
+    pub owner_id: Option<String>,
+    /// timestamps in milliseconds
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    /// id of the last event of the previous page
+    pub before: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditEventList {
+    pub results: Vec<AuditEvent>,
+    /// pass it as `before` to get the next page
+    pub next_cursor: Option<ObjectId>,
+}
+
+impl Responder for AuditEvent {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
+
+impl Responder for AuditEventList {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
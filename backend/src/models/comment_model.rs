@@ -0,0 +1,62 @@
+use actix_web::{body::BoxBody, HttpResponse, Responder};
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+/// a discussion on a document, or on one of its tokens when `token_id` is set.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommentThread {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub _id: Option<ObjectId>,
+    pub annotation_id: ObjectId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_id: Option<ObjectId>,
+    /// set when the token belongs to an annotator's layer, only its owner and the
+    /// reviewers can read the thread
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer: Option<ObjectId>,
+    /// user who opened the thread
+    pub user_id: ObjectId,
+    /// the first comment opens the thread, the next ones are replies
+    pub comments: Vec<Comment>,
+    pub resolved: bool,
+    pub resolved_by: Option<ObjectId>,
+    pub resolved_at: Option<DateTime>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Comment {
+    #[serde(rename = "_id")]
+    pub _id: ObjectId,
+    pub user_id: ObjectId,
+    pub content: String,
+    pub created_at: DateTime,
+    /// set once the author edits the comment
+    pub edited_at: Option<DateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateThreadBody {
+    pub content: String,
+    pub token_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommentBody {
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ThreadsQueryParams {
+    pub resolved: Option<bool>,
+    pub token_id: Option<String>,
+}
+
+impl Responder for CommentThread {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
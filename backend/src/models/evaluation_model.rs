@@ -0,0 +1,53 @@
+use actix_multipart::form::{tempfile::TempFile, MultipartForm};
+use actix_web::{body::BoxBody, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::metrics_helpers::{ConfusionMatrix, SpanMetrics};
+
+/// a line of the uploaded predictions file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PredictionLine {
+    pub annotation_id: String,
+    pub spans: Vec<PredictedSpan>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PredictedSpan {
+    pub start: i64,
+    pub end: i64,
+    pub label: String,
+}
+
+#[derive(Debug, MultipartForm)]
+pub struct EvaluationForm {
+    /// jsonl file, one `PredictionLine` per line
+    #[multipart(rename = "file")]
+    pub file: TempFile,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EvaluationSpan {
+    pub annotation_id: String,
+    pub start: i64,
+    pub end: i64,
+    pub label: String,
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EvaluationReport {
+    pub documents: i64,
+    pub exact: SpanMetrics,
+    pub partial: SpanMetrics,
+    pub confusion_matrix: ConfusionMatrix,
+    pub false_positives: Vec<EvaluationSpan>,
+    pub false_negatives: Vec<EvaluationSpan>,
+}
+
+impl Responder for EvaluationReport {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
@@ -0,0 +1,30 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::models::text_annotation_model::AnnotationRole;
+
+/// access given by the owner of a document or a project to another user.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AccessGrant {
+    pub user_id: ObjectId,
+    /// viewer, annotator or editor, the owner role cannot be granted
+    pub role: AnnotationRole,
+    #[serde(default)]
+    pub created_at: Option<DateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateGrantBody {
+    /// username or email of the user
+    pub login: String,
+    pub role: AnnotationRole,
+    /// version of the annotation the grant is changed on, when the `If-Match` header
+    /// is not sent
+    pub version: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateGrantBody {
+    pub role: AnnotationRole,
+    pub version: Option<i64>,
+}
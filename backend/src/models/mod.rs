@@ -1,3 +1,20 @@
+pub mod adjudication_model;
+pub mod agreement_model;
+pub mod audit_model;
+pub mod comment_model;
 pub mod common_models;
+pub mod evaluation_model;
+pub mod grant_model;
+pub mod model_backend_model;
+pub mod project_model;
+pub mod queue_model;
+pub mod realtime_model;
+pub mod search_model;
+pub mod share_model;
+pub mod stats_model;
+pub mod sync_model;
+pub mod tagger_model;
+pub mod team_model;
 pub mod text_annotation_model;
 pub mod user_model;
+pub mod webhook_model;
@@ -0,0 +1,126 @@
+use actix_web::{body::BoxBody, http::header, HttpResponse, Responder};
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{helpers::version_helpers::format_etag, models::text_annotation_model::TextAnnotation};
+
+/// an external http service returning predicted spans for a document content.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelBackend {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub _id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    /// used as the suggestions' source
+    pub name: String,
+    pub url: String,
+    pub timeout_ms: i64,
+    pub contract: ModelBackendContract,
+    pub target: PredictionTarget,
+    /// run the backend on every annotation created by the user
+    pub run_on_create: bool,
+}
+
+/// describes the json exchanged with the backend.
+///
+/// the request is `{ <content_field>: "..." }`, the response holds an array of spans
+/// at `spans_field` (a dot separated path, empty when the response is the array itself).
+///
+/// `start` and `end` are the offsets of the first and the last character of a span, both
+/// included: "abc" at the beginning of the content is `{"start": 0, "end": 2}`. spans
+/// out of the content or missing a field are dropped and reported in the run response.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelBackendContract {
+    pub content_field: String,
+    pub spans_field: String,
+    pub start_field: String,
+    pub end_field: String,
+    pub label_field: String,
+    /// spans without a score are considered certain
+    pub score_field: String,
+}
+
+impl Default for ModelBackendContract {
+    fn default() -> Self {
+        ModelBackendContract {
+            content_field: "content".to_string(),
+            spans_field: "spans".to_string(),
+            start_field: "start".to_string(),
+            end_field: "end".to_string(),
+            label_field: "label".to_string(),
+            score_field: "score".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PredictionTarget {
+    Suggestions,
+    Tokens,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateModelBackendBody {
+    pub name: String,
+    pub url: String,
+    pub timeout_ms: Option<i64>,
+    pub contract: Option<ModelBackendContract>,
+    pub target: Option<PredictionTarget>,
+    pub run_on_create: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateModelBackendBody {
+    pub name: Option<String>,
+    pub url: Option<String>,
+    pub timeout_ms: Option<i64>,
+    pub contract: Option<ModelBackendContract>,
+    pub target: Option<PredictionTarget>,
+    pub run_on_create: Option<bool>,
+}
+
+/// a span returned by the backend that could not be queued.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DroppedPrediction {
+    /// the span as the backend returned it
+    pub span: Value,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PreAnnotationResponse {
+    pub annotation: TextAnnotation,
+    pub dropped: Vec<DroppedPrediction>,
+}
+
+/// pre-annotation of a created annotation, run by the worker so the creation does not
+/// wait for the backends.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PreAnnotationJob {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub _id: Option<ObjectId>,
+    pub annotation_id: ObjectId,
+    /// runs so far, a run is repeated when the annotation changed while it was saved
+    pub attempt_count: i64,
+    pub next_attempt_at: DateTime,
+    pub created_at: DateTime,
+}
+
+impl Responder for ModelBackend {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
+
+impl Responder for PreAnnotationResponse {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok()
+            .insert_header((header::ETAG, format_etag(self.annotation.version)))
+            .json(self)
+    }
+}
@@ -0,0 +1,89 @@
+use actix_web::{body::BoxBody, HttpResponse, Responder};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{grant_model::AccessGrant, text_annotation_model::AnnotationRole};
+
+/// groups text annotations of the same user.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Project {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub _id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub name: String,
+    pub description: String,
+    /// team the project belongs to, its members work on the project
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team_id: Option<ObjectId>,
+    /// users working on the project besides its owner
+    #[serde(default)]
+    pub members: Vec<ProjectMember>,
+    /// users the owner shared the project's documents with
+    #[serde(default)]
+    pub grants: Vec<AccessGrant>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectRole {
+    Annotator,
+    /// annotator who can also review documents
+    Reviewer,
+    /// reviewer who can also manage members and assignments
+    Manager,
+}
+
+impl ProjectRole {
+    pub fn can_review(&self) -> bool {
+        *self != ProjectRole::Annotator
+    }
+
+    /// access given to the documents of the project, reviewers edit their gold tokens.
+    pub fn annotation_role(&self) -> AnnotationRole {
+        match self {
+            ProjectRole::Annotator => AnnotationRole::Annotator,
+            ProjectRole::Reviewer | ProjectRole::Manager => AnnotationRole::Editor,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProjectMember {
+    pub user_id: ObjectId,
+    pub role: ProjectRole,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateProjectBody {
+    pub name: String,
+    pub description: Option<String>,
+    pub team_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateProjectBody {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// moves the project to this team, an empty string moves it out of its team
+    pub team_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddProjectMemberBody {
+    /// username or email of the user
+    pub login: String,
+    pub role: ProjectRole,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateProjectMemberBody {
+    pub role: ProjectRole,
+}
+
+impl Responder for Project {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
@@ -0,0 +1,37 @@
+use actix_web::{body::BoxBody, HttpResponse, Responder};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use crate::models::text_annotation_model::AnnotationStatus;
+
+/// an unfinished document of a project queue.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueueItem {
+    pub annotation_id: ObjectId,
+    pub title: String,
+    pub status: AnnotationStatus,
+    pub assigned_to: Option<ObjectId>,
+    /// user currently holding the document's lease
+    pub leased_by: Option<ObjectId>,
+    /// remaining seconds of the lease
+    pub lease_ttl: Option<i64>,
+    /// annotators currently working on their own layer of the document
+    pub annotated_by: Vec<ObjectId>,
+    /// annotators who finished their layer
+    pub completed_by: Vec<ObjectId>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssignAnnotationBody {
+    /// member the document is assigned to, the document is unassigned when missing
+    pub user_id: Option<String>,
+    pub version: Option<i64>,
+}
+
+impl Responder for QueueItem {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
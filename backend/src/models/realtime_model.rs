@@ -0,0 +1,86 @@
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use crate::models::text_annotation_model::{Label, Token};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PresenceViewer {
+    /// id of the websocket connection, a user can have several tabs opened
+    pub connection_id: ObjectId,
+    pub user_id: ObjectId,
+    pub username: String,
+    pub firstname: String,
+    pub lastname: String,
+    pub connected_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RealtimeEvent {
+    TitleUpdated {
+        title: String,
+    },
+    LabelCreated {
+        label: Label,
+    },
+    LabelUpdated {
+        label: Label,
+    },
+    /// the tokens using the label are deleted with it
+    LabelDeleted {
+        label_id: ObjectId,
+    },
+    /// `layer` is set when the token belongs to an annotator's layer
+    TokenCreated {
+        token: Token,
+        layer: Option<ObjectId>,
+    },
+    TokenUpdated {
+        token: Token,
+        layer: Option<ObjectId>,
+    },
+    TokenDeleted {
+        token_id: ObjectId,
+        layer: Option<ObjectId>,
+    },
+    /// the suggestion queue changed, clients fetch the annotation again to see it
+    SuggestionsUpdated,
+    /// the annotation was assigned in its project queue, or unassigned
+    AssigneeUpdated {
+        assigned_to: Option<ObjectId>,
+    },
+    Presence {
+        viewers: Vec<PresenceViewer>,
+    },
+}
+
+/// payload sent over the pub/sub channel and to the websocket clients.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RealtimeMessage {
+    pub annotation_id: ObjectId,
+    /// user who made the change, none for presence updates
+    pub user_id: Option<ObjectId>,
+    /// version of the annotation after the change, none for presence updates
+    pub version: Option<i64>,
+    pub sent_at: i64,
+    #[serde(flatten)]
+    pub event: RealtimeEvent,
+}
+
+impl RealtimeMessage {
+    /// the layer the event is about, only its owner and the reviewers should receive it.
+    pub fn layer(&self) -> Option<ObjectId> {
+        match &self.event {
+            RealtimeEvent::TokenCreated { layer, .. } => *layer,
+            RealtimeEvent::TokenUpdated { layer, .. } => *layer,
+            RealtimeEvent::TokenDeleted { layer, .. } => *layer,
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RealtimeQueryParams {
+    /// browsers cannot set the authorization header on websockets
+    pub token: Option<String>,
+}
@@ -0,0 +1,159 @@
+use actix_web::{body::BoxBody, HttpResponse, Responder};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchQueryParams {
+    /// mongodb text search, `"quoted phrases"` and `-excluded` words are supported
+    pub q: String,
+    /// only documents having at least one token with this label
+    pub label: Option<String>,
+    pub has_tokens: Option<bool>,
+    pub project_id: Option<String>,
+    pub page: Option<i64>,
+    pub count: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TextMatchMode {
+    Exact,
+    Prefix,
+    Regex,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpanQueryParams {
+    pub label: Option<String>,
+    pub text: Option<String>,
+    /// how `text` is matched against the span's text, defaults to exact
+    pub mode: Option<TextMatchMode>,
+    pub ignore_case: Option<bool>,
+    pub min_length: Option<i64>,
+    pub max_length: Option<i64>,
+    pub project_id: Option<String>,
+    /// number of characters of context on each side of the span
+    pub context: Option<i64>,
+    pub page: Option<i64>,
+    pub count: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResult {
+    #[serde(rename = "_id")]
+    pub _id: ObjectId,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<ObjectId>,
+    pub score: f64,
+    pub tokens: i64,
+    /// excerpts of the content with the matches wrapped in `<mark>` tags
+    pub snippets: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResponse {
+    pub total: u64,
+    pub results: Vec<SearchResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KwicQueryParams {
+    /// word or phrase to look for
+    pub q: String,
+    /// number of characters of context on each side of the occurrence
+    pub context: Option<i64>,
+    /// defaults to true
+    pub ignore_case: Option<bool>,
+    pub whole_word: Option<bool>,
+    pub project_id: Option<String>,
+    /// pagination is ignored by the csv export
+    pub page: Option<i64>,
+    pub count: Option<i64>,
+}
+
+/// how an occurrence is covered by the document's tokens.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum KwicCoverage {
+    /// a token has the same span as the occurrence
+    Exact,
+    /// a token overlaps the occurrence
+    Partial,
+    None,
+}
+
+impl KwicCoverage {
+    pub fn as_str(&self) -> &str {
+        match self {
+            KwicCoverage::Exact => "exact",
+            KwicCoverage::Partial => "partial",
+            KwicCoverage::None => "none",
+        }
+    }
+}
+
+/// an occurrence of the keyword, `end` being inclusive like on tokens.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KwicLine {
+    pub annotation_id: ObjectId,
+    pub title: String,
+    pub start: i64,
+    pub end: i64,
+    pub left: String,
+    pub keyword: String,
+    pub right: String,
+    pub coverage: KwicCoverage,
+    /// label of the covering token
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KwicResponse {
+    pub total: u64,
+    pub results: Vec<KwicLine>,
+}
+
+impl Responder for KwicResponse {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
+
+/// a token of a document, `end` being inclusive like on tokens.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpanResult {
+    pub annotation_id: ObjectId,
+    pub title: String,
+    pub token_id: ObjectId,
+    pub start: i64,
+    pub end: i64,
+    pub label: String,
+    pub text: String,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpanSearchResponse {
+    pub total: u64,
+    pub results: Vec<SpanResult>,
+}
+
+impl Responder for SpanSearchResponse {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
+
+impl Responder for SearchResponse {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
@@ -0,0 +1,71 @@
+use actix_web::{body::BoxBody, HttpResponse, Responder};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use crate::models::text_annotation_model::{Label, Token};
+
+/// read-only link to an annotation, kept in the cache db until it expires.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShareLink {
+    pub _id: ObjectId,
+    pub annotation_id: ObjectId,
+    pub created_by: ObjectId,
+    /// signed token to put in the link
+    pub token: String,
+    /// unix timestamps, in seconds
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateShareLinkBody {
+    /// lifetime of the link in seconds, 7 days when missing
+    pub expires_in: Option<i64>,
+}
+
+/// what the visitors of a share link see, without any user information.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SharedAnnotation {
+    pub title: String,
+    pub content: String,
+    pub labels: Vec<Label>,
+    pub tokens: Vec<SharedToken>,
+    pub expires_at: i64,
+}
+
+/// a token of a shared annotation, without who created it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SharedToken {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub _id: Option<ObjectId>,
+    pub start: i64,
+    pub end: i64,
+    pub label: ObjectId,
+}
+
+impl From<Token> for SharedToken {
+    fn from(token: Token) -> Self {
+        SharedToken {
+            _id: token._id,
+            start: token.start,
+            end: token.end,
+            label: token.label,
+        }
+    }
+}
+
+impl Responder for ShareLink {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
+
+impl Responder for SharedAnnotation {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
@@ -0,0 +1,53 @@
+use actix_web::{body::BoxBody, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnnotationStats {
+    pub documents: i64,
+    /// documents without any token
+    pub unlabeled_documents: i64,
+    pub spans: i64,
+    pub characters: i64,
+    pub covered_characters: i64,
+    /// percentage of the content covered by tokens
+    pub coverage: f64,
+    pub labels: Vec<LabelStats>,
+    pub length_histogram: Vec<LengthBucket>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LabelStats {
+    pub label: String,
+    pub spans: i64,
+    pub covered_characters: i64,
+    pub average_length: f64,
+    pub top_texts: Vec<SpanTextCount>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpanTextCount {
+    pub text: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LengthBucket {
+    pub min: i64,
+    /// `None` for the last, unbounded, bucket
+    pub max: Option<i64>,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatsQueryParams {
+    /// amount of most frequent span texts returned per label
+    pub top: Option<i64>,
+}
+
+impl Responder for AnnotationStats {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
@@ -0,0 +1,88 @@
+use actix_web::{body::BoxBody, http::header, HttpResponse, Responder};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use crate::{helpers::version_helpers::format_etag, models::text_annotation_model::TextAnnotation};
+
+/// a change made by the client, ids are either server ids or the `ref_id` of an item
+/// created earlier in the same batch.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncOperationKind {
+    CreateToken {
+        ref_id: Option<String>,
+        start: i64,
+        end: i64,
+        label: String,
+    },
+    UpdateToken {
+        token_id: String,
+        start: Option<i64>,
+        end: Option<i64>,
+        label: Option<String>,
+    },
+    DeleteToken {
+        token_id: String,
+    },
+    CreateLabel {
+        ref_id: Option<String>,
+        name: String,
+        color: Option<String>,
+    },
+    UpdateLabel {
+        label_id: String,
+        name: Option<String>,
+        color: Option<String>,
+    },
+    DeleteLabel {
+        label_id: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SyncOperation {
+    /// given by the client to match the rejected operations
+    pub id: String,
+    #[serde(flatten)]
+    pub kind: SyncOperationKind,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncBody {
+    /// version of the annotation the operations were made against
+    pub base_version: i64,
+    pub operations: Vec<SyncOperation>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RejectedOperation {
+    pub id: String,
+    pub reason: String,
+}
+
+/// server id of an item the client created with a `ref_id`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncReference {
+    pub ref_id: String,
+    pub id: ObjectId,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncResponse {
+    pub annotation: TextAnnotation,
+    /// true when the operations were replayed on a newer version than their base
+    pub rebased: bool,
+    pub applied: Vec<String>,
+    pub rejected: Vec<RejectedOperation>,
+    pub references: Vec<SyncReference>,
+}
+
+impl Responder for SyncResponse {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok()
+            .insert_header((header::ETAG, format_etag(self.annotation.version)))
+            .json(self)
+    }
+}
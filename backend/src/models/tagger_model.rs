@@ -0,0 +1,85 @@
+use actix_web::{body::BoxBody, HttpResponse, Responder};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+
+use crate::{helpers::metrics_helpers::SpanMetrics, models::model_backend_model::PredictionTarget};
+
+/// an averaged perceptron trained on a user's corpus or on a single project.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaggerModel {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub _id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    pub project_id: Option<ObjectId>,
+    /// BIO tags known by the model
+    pub tags: Vec<String>,
+    /// training the weights belong to, they are stored in chunks apart from the model
+    /// so large corpora do not hit the document size limit
+    pub training_id: ObjectId,
+    pub weight_count: i64,
+    pub iterations: i64,
+    pub train_documents: i64,
+    pub test_documents: i64,
+    /// metrics computed on the held-out documents
+    pub metrics: SpanMetrics,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaggerWeight {
+    pub feature: String,
+    pub tag: String,
+    pub weight: f64,
+}
+
+/// a slice of the weights of a training.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaggerWeightChunk {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub _id: Option<ObjectId>,
+    pub training_id: ObjectId,
+    pub index: i64,
+    pub weights: Vec<TaggerWeight>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrainTaggerBody {
+    pub project_id: Option<String>,
+    pub iterations: Option<i64>,
+    /// share of the annotated documents kept for the evaluation
+    pub test_ratio: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PredictTaggerBody {
+    pub project_id: Option<String>,
+    /// annotations to predict, defaults to the ones without tokens
+    pub annotation_ids: Option<Vec<String>>,
+    pub target: Option<PredictionTarget>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TaggerQueryParams {
+    pub project_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PredictTaggerResponse {
+    pub annotations: i64,
+    pub spans: i64,
+}
+
+impl Responder for TaggerModel {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
+
+impl Responder for PredictTaggerResponse {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
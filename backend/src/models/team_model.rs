@@ -0,0 +1,126 @@
+use actix_web::{body::BoxBody, HttpResponse, Responder};
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{project_model::ProjectRole, text_annotation_model::AnnotationRole};
+
+/// group of users sharing projects and documents.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Team {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub _id: Option<ObjectId>,
+    pub name: String,
+    pub description: String,
+    pub members: Vec<TeamMember>,
+    /// invitations waiting for an answer
+    #[serde(default)]
+    pub invitations: Vec<TeamInvitation>,
+    #[serde(default)]
+    pub created_at: Option<DateTime>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime>,
+    /// incremented by every change, a change is only saved over the version it was made on
+    #[serde(default)]
+    pub version: i64,
+}
+
+/// every role can do what the previous ones can.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum TeamRole {
+    Viewer,
+    Annotator,
+    /// manages the members and the team's projects
+    Manager,
+    Owner,
+}
+
+impl TeamRole {
+    pub fn can_manage(&self) -> bool {
+        *self >= TeamRole::Manager
+    }
+
+    /// access given to the documents of the team and of its projects.
+    pub fn annotation_role(&self) -> AnnotationRole {
+        match self {
+            TeamRole::Viewer => AnnotationRole::Viewer,
+            TeamRole::Annotator => AnnotationRole::Annotator,
+            TeamRole::Manager | TeamRole::Owner => AnnotationRole::Editor,
+        }
+    }
+
+    /// role in the team's projects, viewers are not part of their workflow.
+    pub fn project_role(&self) -> Option<ProjectRole> {
+        match self {
+            TeamRole::Viewer => None,
+            TeamRole::Annotator => Some(ProjectRole::Annotator),
+            TeamRole::Manager | TeamRole::Owner => Some(ProjectRole::Manager),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TeamMember {
+    pub user_id: ObjectId,
+    pub role: TeamRole,
+    #[serde(default)]
+    pub joined_at: Option<DateTime>,
+}
+
+/// invitation of a user, or of an email address that has no account yet.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TeamInvitation {
+    #[serde(rename = "_id")]
+    pub _id: ObjectId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    pub role: TeamRole,
+    pub invited_by: ObjectId,
+    #[serde(default)]
+    pub created_at: Option<DateTime>,
+}
+
+/// an invitation received by the user.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PendingInvitation {
+    pub _id: ObjectId,
+    pub team_id: ObjectId,
+    pub team_name: String,
+    pub role: TeamRole,
+    pub invited_by: ObjectId,
+    pub created_at: Option<DateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateTeamBody {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateTeamBody {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InviteTeamMemberBody {
+    /// username or email of the user, an email without account is invited as is
+    pub login: String,
+    pub role: TeamRole,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateTeamMemberBody {
+    pub role: TeamRole,
+}
+
+impl Responder for Team {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
@@ -1,16 +1,111 @@
-use actix_web::{body::BoxBody, HttpResponse, Responder};
-use mongodb::bson::oid::ObjectId;
+use actix_web::{body::BoxBody, http::header, HttpResponse, Responder};
+use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
 
+use crate::{helpers::version_helpers::format_etag, models::grant_model::AccessGrant};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TextAnnotation {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
     pub _id: Option<ObjectId>,
     pub content: String,
     pub user_id: ObjectId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<ObjectId>,
+    /// team the document belongs to, its members can access it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team_id: Option<ObjectId>,
     pub tokens: Vec<Token>,
+    /// tokens of the project members annotating the document independently
+    #[serde(default)]
+    pub layers: Vec<AnnotationLayer>,
     pub labels: Vec<Label>,
     pub title: String,
+    #[serde(default)]
+    pub status: AnnotationStatus,
+    /// project member the document is assigned to in the project queue
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assigned_to: Option<ObjectId>,
+    /// annotators who finished their layer, the document stays in the queue of the others
+    #[serde(default)]
+    pub completed_by: Vec<ObjectId>,
+    /// spans proposed by automated sources, waiting for an annotator's decision
+    #[serde(default)]
+    pub suggestions: Vec<Suggestion>,
+    /// suggestions rejected by an annotator, kept so they are not proposed again
+    #[serde(default)]
+    pub rejected_suggestions: Vec<RejectedSuggestion>,
+    #[serde(default)]
+    pub created_at: Option<DateTime>,
+    /// changed on every write to the document, its labels or tokens
+    #[serde(default)]
+    pub updated_at: Option<DateTime>,
+    /// users the owner shared the document with
+    #[serde(default)]
+    pub grants: Vec<AccessGrant>,
+    /// role of the user reading the document, it is never stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<AnnotationRole>,
+    /// unresolved comment threads the user can read, only set in the listing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_thread_count: Option<i64>,
+    /// incremented by every write, clients send it back to update the document
+    #[serde(default)]
+    pub version: i64,
+}
+
+/// workflow of a document, a reviewed document is read-only until it is reopened.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationStatus {
+    #[default]
+    Todo,
+    InProgress,
+    Done,
+    Reviewed,
+}
+
+impl AnnotationStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AnnotationStatus::Todo => "todo",
+            AnnotationStatus::InProgress => "in_progress",
+            AnnotationStatus::Done => "done",
+            AnnotationStatus::Reviewed => "reviewed",
+        }
+    }
+}
+
+/// access of a user to a document, every role can do what the previous ones can.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationRole {
+    /// reads the document
+    Viewer,
+    /// writes their own layer of tokens
+    Annotator,
+    /// writes the document's title, labels and tokens
+    Editor,
+    /// deletes the document
+    Owner,
+}
+
+impl AnnotationRole {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AnnotationRole::Viewer => "viewer",
+            AnnotationRole::Annotator => "annotator",
+            AnnotationRole::Editor => "editor",
+            AnnotationRole::Owner => "owner",
+        }
+    }
+}
+
+/// tokens of a single annotator, using the labels of the document.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnnotationLayer {
+    pub user_id: ObjectId,
+    pub tokens: Vec<Token>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -21,6 +116,11 @@ pub struct Token {
     pub end: i64,
     /// reference a label in the labels array
     pub label: ObjectId,
+    #[serde(default)]
+    pub created_at: Option<DateTime>,
+    /// user who created the token, or accepted the suggestion it comes from
+    #[serde(default)]
+    pub created_by: Option<ObjectId>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -29,23 +129,63 @@ pub struct Label {
     pub _id: Option<ObjectId>,
     pub name: String,
     pub color: String,
+    #[serde(default)]
+    pub created_at: Option<DateTime>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Suggestion {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub _id: Option<ObjectId>,
+    pub start: i64,
+    pub end: i64,
+    /// name of the suggested label, created on accept if missing
+    pub label: String,
+    /// name of the rule, model or memory that proposed the span
+    pub source: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RejectedSuggestion {
+    pub start: i64,
+    pub end: i64,
+    pub label: String,
+    pub source: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateTextAnnotationBody {
     pub content: String,
     pub title: String,
+    pub project_id: Option<String>,
+    pub team_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateTextAnnotationBody {
     pub title: String,
+    /// version the change was made against, when the `If-Match` header is not sent
+    pub version: Option<i64>,
+    /// moves the annotation to this project, an empty string moves it out of its project
+    pub project_id: Option<String>,
+    /// moves the annotation to this team, an empty string moves it out of its team
+    pub team_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateAnnotationStatusBody {
+    pub status: AnnotationStatus,
+    pub version: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateLabelBody {
     pub name: String,
     pub color: Option<String>,
+    pub version: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -53,6 +193,7 @@ pub struct UpdateLabelBody {
     pub name: Option<String>,
     pub color: Option<String>,
     pub title: Option<String>,
+    pub version: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -60,11 +201,130 @@ pub struct CreateTokenBody {
     pub start: i64,
     pub end: i64,
     pub label: String,
+    pub version: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateSuggestionBody {
+    pub start: i64,
+    pub end: i64,
+    pub label: String,
+    pub source: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateSuggestionsBody {
+    pub suggestions: Vec<CreateSuggestionBody>,
+    pub version: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionAction {
+    Accept,
+    Reject,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkSuggestionBody {
+    pub action: SuggestionAction,
+    /// only apply to suggestions coming from this source
+    pub source: Option<String>,
+    /// only apply to suggestions with a score greater or equal to this one
+    pub min_score: Option<f64>,
+    /// only apply to suggestions with a score lower than this one
+    pub max_score: Option<f64>,
+    pub version: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BulkSuggestionResponse {
+    pub annotation: TextAnnotation,
+    /// suggestions left in the queue since their span overlaps a token or is out of
+    /// the content
+    pub skipped: Vec<ObjectId>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationSort {
+    Title,
+    /// creation order
+    Created,
+    /// last modification
+    Updated,
+    /// number of tokens
+    Tokens,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListAnnotationsQueryParams {
+    /// defaults to created
+    pub sort: Option<AnnotationSort>,
+    /// defaults to desc
+    pub order: Option<SortOrder>,
+    /// only documents whose title contains this text, ignoring the case
+    pub title: Option<String>,
+    pub has_tokens: Option<bool>,
+    /// only documents having at least one token with this label
+    pub label: Option<String>,
+    pub project_id: Option<String>,
+    pub status: Option<AnnotationStatus>,
+    pub limit: Option<i64>,
+    /// cursor of the item after which the page starts
+    pub after: Option<String>,
+    /// cursor of the item before which the page ends
+    pub before: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TextAnnotationList {
+    /// number of documents matching the filters
+    pub total: u64,
+    pub results: Vec<TextAnnotation>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+impl Responder for TextAnnotationList {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
 }
 
 impl Responder for TextAnnotation {
     type Body = BoxBody;
 
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok()
+            .insert_header((header::ETAG, format_etag(self.version)))
+            .json(self)
+    }
+}
+
+impl Responder for BulkSuggestionResponse {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok()
+            .insert_header((header::ETAG, format_etag(self.annotation.version)))
+            .json(self)
+    }
+}
+
+impl Responder for AnnotationLayer {
+    type Body = BoxBody;
+
     fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
         HttpResponse::Ok().json(self)
     }
@@ -1,6 +1,6 @@
 use actix_web::{body::BoxBody, HttpResponse, Responder};
 
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -12,6 +12,10 @@ pub struct User {
     pub password: String,
     pub email: String,
     pub username: String,
+    #[serde(default)]
+    pub created_at: Option<DateTime>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -21,6 +25,8 @@ pub struct PublicUser {
     pub lastname: String,
     pub email: String,
     pub username: String,
+    pub created_at: Option<DateTime>,
+    pub updated_at: Option<DateTime>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -74,6 +80,8 @@ impl From<User> for PublicUser {
             lastname: value.lastname,
             email: value.email,
             username: value.username,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
         }
     }
 }
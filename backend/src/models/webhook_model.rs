@@ -0,0 +1,114 @@
+use actix_web::{body::BoxBody, HttpResponse, Responder};
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    AnnotationCreated,
+    AnnotationDeleted,
+    AnnotationStatusUpdated,
+    /// sent along `annotation_status_updated` when the document reaches `done`
+    AnnotationDone,
+    TokenCreated,
+    TokenUpdated,
+    TokenDeleted,
+    LabelCreated,
+    LabelUpdated,
+    LabelDeleted,
+}
+
+/// an http endpoint notified of the events on the user's documents, or on the documents
+/// of a project when `project_id` is set.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Webhook {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub _id: Option<ObjectId>,
+    pub user_id: ObjectId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<ObjectId>,
+    pub name: String,
+    pub url: String,
+    /// key of the `X-Webhook-Signature` hmac
+    pub secret: String,
+    pub events: Vec<WebhookEvent>,
+    pub active: bool,
+    pub created_at: DateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeliveryAttempt {
+    pub attempted_at: DateTime,
+    /// none when the receiver could not be reached
+    pub status_code: Option<i64>,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookDelivery {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub _id: Option<ObjectId>,
+    pub webhook_id: ObjectId,
+    pub event: WebhookEvent,
+    /// json body sent to the receiver, kept as is so redeliveries match the signature
+    pub payload: String,
+    pub status: DeliveryStatus,
+    /// attempts since the delivery was created or redelivered
+    pub attempt_count: i64,
+    pub attempts: Vec<DeliveryAttempt>,
+    /// set while the delivery is pending
+    pub next_attempt_at: Option<DateTime>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateWebhookBody {
+    pub name: String,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub project_id: Option<String>,
+    /// generated when not given
+    pub secret: Option<String>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateWebhookBody {
+    pub name: Option<String>,
+    pub url: Option<String>,
+    pub events: Option<Vec<WebhookEvent>>,
+    pub secret: Option<String>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeliveriesQueryParams {
+    pub status: Option<DeliveryStatus>,
+    pub limit: Option<i64>,
+}
+
+impl Responder for Webhook {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
+
+impl Responder for WebhookDelivery {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok().json(self)
+    }
+}
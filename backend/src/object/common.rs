@@ -1,4 +1,8 @@
-use actix_web::{body::BoxBody, http::StatusCode, HttpResponse, Responder};
+use actix_web::{
+    body::BoxBody,
+    http::{header, StatusCode},
+    HttpResponse, Responder,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -44,8 +48,23 @@ impl Responder for Message {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct PaginationQueryParams {
-    pub page: i64,
-    pub count: i64,
+/// csv content sent as a file download.
+#[derive(Debug)]
+pub struct CsvFile {
+    pub filename: String,
+    pub content: String,
+}
+
+impl Responder for CsvFile {
+    type Body = BoxBody;
+
+    fn respond_to(self, _req: &actix_web::HttpRequest) -> HttpResponse<Self::Body> {
+        HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", self.filename),
+            ))
+            .body(self.content)
+    }
 }
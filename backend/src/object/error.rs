@@ -1,8 +1,14 @@
 use derive_more::{Display, Error};
 use std::fmt::Debug;
 
-use actix_web::{self, body::BoxBody, http::StatusCode, HttpResponse, Responder, ResponseError};
+use actix_web::{
+    self,
+    body::BoxBody,
+    http::{header::ContentType, StatusCode},
+    HttpResponse, Responder, ResponseError,
+};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Display, Debug, Serialize, Deserialize, Error)]
 #[display(
@@ -17,6 +23,9 @@ pub struct ApiError {
     pub msg: String,
     pub error: String,
     pub validation: Vec<String>,
+    /// resource returned with the error, like the current document of a conflict
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Box<Value>>,
 }
 
 impl ApiError {
@@ -26,6 +35,7 @@ impl ApiError {
             msg: "".to_string(),
             error: "".to_string(),
             validation: vec![],
+            data: None,
         }
     }
 
@@ -46,6 +56,12 @@ impl ApiError {
 
         self
     }
+
+    pub fn set_data(mut self, data: Value) -> ApiError {
+        self.data = Some(Box::new(data));
+
+        self
+    }
 }
 
 impl Responder for ApiError {
@@ -60,6 +76,18 @@ impl ResponseError for ApiError {
     fn status_code(&self) -> StatusCode {
         StatusCode::from_u16(self.status).unwrap()
     }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        let mut res = HttpResponse::build(self.status_code());
+
+        // the display format has no room for the data
+        if self.data.is_some() {
+            return res.json(self);
+        }
+
+        res.insert_header(ContentType::plaintext())
+            .body(self.to_string())
+    }
 }
 
 impl From<ApiError> for HttpResponse {
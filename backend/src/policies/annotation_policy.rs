@@ -0,0 +1,148 @@
+use std::str::FromStr;
+
+use actix_web::http::StatusCode;
+use mongodb::bson::{doc, oid::ObjectId};
+
+use crate::{
+    controllers::{
+        project_controller::get_project_role,
+        team_controller::{find_user_teams, get_team_role},
+    },
+    database::mongodb::DB,
+    middleware::auth_middleware::UserAuthContext,
+    models::{
+        project_model::Project,
+        team_model::Team,
+        text_annotation_model::{AnnotationRole, TextAnnotation},
+    },
+    object::error::ApiError,
+};
+
+/// role of the user on the annotation, from its ownership, its grants, its team or
+/// its project.
+pub fn get_annotation_role(
+    annotation: &TextAnnotation,
+    user_id: ObjectId,
+) -> Option<AnnotationRole> {
+    if annotation.user_id == user_id {
+        return Some(AnnotationRole::Owner);
+    }
+
+    let project = annotation.project_id.and_then(|id| {
+        DB.project_collection
+            .find_one(doc! {"_id": id}, None)
+            .ok()?
+    });
+
+    let teams = find_user_teams(user_id);
+
+    resolve_annotation_role(annotation, project.as_ref(), &teams, user_id)
+}
+
+/// same as `get_annotation_role` with the annotation's project and the user's teams
+/// already fetched, the highest role wins.
+pub fn resolve_annotation_role(
+    annotation: &TextAnnotation,
+    project: Option<&Project>,
+    teams: &[Team],
+    user_id: ObjectId,
+) -> Option<AnnotationRole> {
+    if annotation.user_id == user_id {
+        return Some(AnnotationRole::Owner);
+    }
+
+    let granted = annotation
+        .grants
+        .iter()
+        .find(|it| it.user_id == user_id)
+        .map(|it| it.role);
+
+    let team = get_team_access(annotation.team_id, teams, user_id);
+
+    granted
+        .max(team)
+        .max(project.and_then(|it| get_project_access(it, teams, user_id)))
+}
+
+/// role given on the documents of a project by its membership, its grants or its team.
+pub fn get_project_access(
+    project: &Project,
+    teams: &[Team],
+    user_id: ObjectId,
+) -> Option<AnnotationRole> {
+    let member = get_project_role(project, user_id).map(|it| it.annotation_role());
+
+    let granted = project
+        .grants
+        .iter()
+        .find(|it| it.user_id == user_id)
+        .map(|it| it.role);
+
+    member
+        .max(granted)
+        .max(get_team_access(project.team_id, teams, user_id))
+}
+
+fn get_team_access(
+    team_id: Option<ObjectId>,
+    teams: &[Team],
+    user_id: ObjectId,
+) -> Option<AnnotationRole> {
+    let team = teams
+        .iter()
+        .find(|it| team_id.is_some() && it._id == team_id)?;
+
+    get_team_role(team, user_id).map(|it| it.annotation_role())
+}
+
+/// finds the annotation and checks the user has at least the `required` role on it.
+///
+/// users without any access get a not found, so the annotation's existence is not
+/// leaked, users with a lower role get a forbidden.
+pub fn authorize_annotation(
+    id: String,
+    auth: &UserAuthContext,
+    required: AnnotationRole,
+) -> Result<(TextAnnotation, AnnotationRole), ApiError> {
+    let object_id = ObjectId::from_str(id.as_str());
+
+    if object_id.is_err() {
+        return Err(ApiError::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .set_msg("unable to convert annotation id to object id"));
+    }
+
+    let annotation_result = DB
+        .text_annotation_collection
+        .find_one(doc! {"_id": object_id.unwrap()}, None);
+
+    if annotation_result.is_err() {
+        return Err(ApiError::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .set_msg("unable to fetch the annotation")
+            .set_error(annotation_result.err().unwrap().to_string().as_str()));
+    }
+
+    let annotation = annotation_result.unwrap();
+
+    let role = annotation
+        .as_ref()
+        .and_then(|it| get_annotation_role(it, auth.user_id));
+
+    if role.is_none() {
+        return Err(ApiError::new(StatusCode::NOT_FOUND).set_msg("annotation not found"));
+    }
+
+    let role = role.unwrap();
+
+    if role < required {
+        return Err(ApiError::new(StatusCode::FORBIDDEN).set_msg(
+            format!(
+                "this action needs the ({}) role on the annotation, you are ({})",
+                required.as_str(),
+                role.as_str()
+            )
+            .as_str(),
+        ));
+    }
+
+    Ok((annotation.unwrap(), role))
+}
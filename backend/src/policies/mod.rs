@@ -0,0 +1 @@
+pub mod annotation_policy;
@@ -0,0 +1,39 @@
+use actix_web::{
+    get,
+    web::{self},
+    HttpRequest, Result, Scope,
+};
+
+use crate::{
+    controllers::audit_controller::AuditController,
+    helpers::request_helpers::get_auth_ctx,
+    models::audit_model::{AuditEvent, AuditEventList, AuditQueryParams},
+    object::error::ApiError,
+};
+
+#[get("")]
+async fn get_audit_events(
+    query_params: web::Query<AuditQueryParams>,
+    req: HttpRequest,
+) -> Result<AuditEventList, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = AuditController::get_all(query_params, auth);
+
+    res
+}
+
+#[get("/{id}")]
+async fn get_audit_event(id: web::Path<String>, req: HttpRequest) -> Result<AuditEvent, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = AuditController::get(id.to_string(), auth);
+
+    res
+}
+
+pub fn audit_routes() -> Scope {
+    web::scope("/audit")
+        .service(get_audit_events)
+        .service(get_audit_event)
+}
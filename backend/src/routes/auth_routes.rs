@@ -1,18 +1,22 @@
 use actix_web::{
     post,
     web::{self},
-    Result, Scope,
+    HttpRequest, Result, Scope,
 };
 
 use crate::{
     controllers::auth_controller::AuthController,
+    helpers::request_helpers::get_client_ip,
     models::user_model::{CreateUserBody, SignInBody, UserAuthResponse},
     object::error::ApiError,
 };
 
 #[post("/sign-up")]
-pub async fn sign_up(body: web::Json<CreateUserBody>) -> Result<UserAuthResponse, ApiError> {
-    let res = AuthController::sign_up(body);
+pub async fn sign_up(
+    body: web::Json<CreateUserBody>,
+    req: HttpRequest,
+) -> Result<UserAuthResponse, ApiError> {
+    let res = AuthController::sign_up(body, get_client_ip(&req));
 
     if res.is_err() {
         return Err(res.err().unwrap());
@@ -22,8 +26,11 @@ pub async fn sign_up(body: web::Json<CreateUserBody>) -> Result<UserAuthResponse
 }
 
 #[post("/sign-in")]
-pub async fn sign_in(body: web::Json<SignInBody>) -> Result<UserAuthResponse, ApiError> {
-    let res = AuthController::sign_in(body);
+pub async fn sign_in(
+    body: web::Json<SignInBody>,
+    req: HttpRequest,
+) -> Result<UserAuthResponse, ApiError> {
+    let res = AuthController::sign_in(body, get_client_ip(&req));
 
     if res.is_err() {
         return Err(res.err().unwrap());
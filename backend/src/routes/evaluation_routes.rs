@@ -0,0 +1,29 @@
+use actix_multipart::form::MultipartForm;
+use actix_web::{
+    post,
+    web::{self},
+    HttpRequest, Result, Scope,
+};
+
+use crate::{
+    controllers::evaluation_controller::EvaluationController,
+    helpers::request_helpers::get_auth_ctx,
+    models::evaluation_model::{EvaluationForm, EvaluationReport},
+    object::error::ApiError,
+};
+
+#[post("/")]
+async fn evaluate_predictions(
+    MultipartForm(form): MultipartForm<EvaluationForm>,
+    req: HttpRequest,
+) -> Result<EvaluationReport, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = EvaluationController::evaluate(form, auth);
+
+    res
+}
+
+pub fn evaluation_routes() -> Scope {
+    web::scope("/evaluation").service(evaluate_predictions)
+}
@@ -1,4 +1,13 @@
+pub mod audit_routes;
 pub mod auth_routes;
 pub mod data_routes;
+pub mod evaluation_routes;
+pub mod model_backend_routes;
+pub mod project_routes;
+pub mod share_routes;
+pub mod stats_routes;
+pub mod tagger_routes;
+pub mod team_routes;
 pub mod text_annotation_routes;
 pub mod user_routes;
+pub mod webhook_routes;
@@ -0,0 +1,70 @@
+use actix_web::{
+    delete, get, post, put,
+    web::{self, Json},
+    HttpRequest, Result, Scope,
+};
+
+use crate::{
+    controllers::model_backend_controller::ModelBackendController,
+    helpers::request_helpers::get_auth_ctx,
+    models::model_backend_model::{CreateModelBackendBody, ModelBackend, UpdateModelBackendBody},
+    object::{common::Message, error::ApiError},
+};
+
+#[post("/")]
+async fn create_model_backend(
+    body: web::Json<CreateModelBackendBody>,
+    req: HttpRequest,
+) -> Result<ModelBackend, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = ModelBackendController::create(body, auth);
+
+    res
+}
+
+#[get("/")]
+async fn get_model_backends(req: HttpRequest) -> Result<Json<Vec<ModelBackend>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = ModelBackendController::get_all(auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[put("/{id}")]
+async fn update_model_backend(
+    body: web::Json<UpdateModelBackendBody>,
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<ModelBackend, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = ModelBackendController::update(id.clone(), body, auth);
+
+    res
+}
+
+#[delete("/{id}")]
+async fn delete_model_backend(
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Message, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = ModelBackendController::delete(id.to_string(), auth);
+
+    res
+}
+
+pub fn model_backend_routes() -> Scope {
+    web::scope("/model-backends")
+        .service(create_model_backend)
+        .service(get_model_backends)
+        .service(update_model_backend)
+        .service(delete_model_backend)
+}
@@ -0,0 +1,280 @@
+use actix_web::{
+    delete, get, post, put,
+    web::{self, Json},
+    HttpRequest, Result, Scope,
+};
+
+use crate::{
+    controllers::{
+        agreement_controller::AgreementController, grant_controller::GrantController,
+        project_controller::ProjectController, queue_controller::QueueController,
+        stats_controller::StatsController,
+    },
+    helpers::request_helpers::{get_auth_ctx, get_if_match_version},
+    models::{
+        agreement_model::{AgreementQueryParams, AgreementReport},
+        grant_model::{AccessGrant, CreateGrantBody, UpdateGrantBody},
+        project_model::{
+            AddProjectMemberBody, CreateProjectBody, Project, UpdateProjectBody,
+            UpdateProjectMemberBody,
+        },
+        queue_model::{AssignAnnotationBody, QueueItem},
+        stats_model::{AnnotationStats, StatsQueryParams},
+        text_annotation_model::TextAnnotation,
+    },
+    object::{common::Message, error::ApiError},
+};
+
+#[post("/")]
+async fn create_project(
+    body: web::Json<CreateProjectBody>,
+    req: HttpRequest,
+) -> Result<Project, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = ProjectController::create(body, auth);
+
+    res
+}
+
+#[get("/")]
+async fn get_projects(req: HttpRequest) -> Result<Json<Vec<Project>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = ProjectController::get_all(auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[get("/{id}")]
+async fn get_project(id: web::Path<String>, req: HttpRequest) -> Result<Project, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = ProjectController::get(id.to_string(), auth);
+
+    res
+}
+
+#[get("/{id}/stats")]
+async fn get_project_stats(
+    id: web::Path<String>,
+    query_params: web::Query<StatsQueryParams>,
+    req: HttpRequest,
+) -> Result<AnnotationStats, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = StatsController::get_project_stats(id.to_string(), query_params, auth);
+
+    res
+}
+
+#[put("/{id}")]
+async fn update_project(
+    body: web::Json<UpdateProjectBody>,
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Project, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = ProjectController::update(id.clone(), body, auth);
+
+    res
+}
+
+#[delete("/{id}")]
+async fn delete_project(id: web::Path<String>, req: HttpRequest) -> Result<Message, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = ProjectController::delete(id.to_string(), auth);
+
+    res
+}
+
+#[post("/{id}/members")]
+async fn add_project_member(
+    body: web::Json<AddProjectMemberBody>,
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Project, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = ProjectController::add_member(id.to_string(), body, auth);
+
+    res
+}
+
+#[put("/{id}/members/{user_id}")]
+async fn update_project_member(
+    body: web::Json<UpdateProjectMemberBody>,
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<Project, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = ProjectController::update_member(params, body, auth);
+
+    res
+}
+
+#[delete("/{id}/members/{user_id}")]
+async fn remove_project_member(
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<Project, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = ProjectController::remove_member(params, auth);
+
+    res
+}
+
+#[get("/{id}/grants")]
+async fn get_project_grants(
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Json<Vec<AccessGrant>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = GrantController::get_project_grants(id.to_string(), auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[post("/{id}/grants")]
+async fn add_project_grant(
+    body: web::Json<CreateGrantBody>,
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Json<Vec<AccessGrant>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = GrantController::add_project_grant(id.to_string(), body, auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[put("/{id}/grants/{user_id}")]
+async fn update_project_grant(
+    body: web::Json<UpdateGrantBody>,
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<Json<Vec<AccessGrant>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = GrantController::update_project_grant(params, body, auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[delete("/{id}/grants/{user_id}")]
+async fn revoke_project_grant(
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<Json<Vec<AccessGrant>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = GrantController::revoke_project_grant(params, auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[post("/{id}/queue/next")]
+async fn get_next_annotation(
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<TextAnnotation, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = QueueController::next(id.to_string(), auth);
+
+    res
+}
+
+#[get("/{id}/queue")]
+async fn get_queue(
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Json<Vec<QueueItem>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = QueueController::get(id.to_string(), auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[put("/{id}/queue/{annotation_id}")]
+async fn assign_annotation(
+    body: web::Json<AssignAnnotationBody>,
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<TextAnnotation, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let version = get_if_match_version(&req).or(body.version);
+
+    let res = QueueController::assign(params, body, version, auth);
+
+    res
+}
+
+#[get("/{id}/agreement")]
+async fn get_project_agreement(
+    id: web::Path<String>,
+    query_params: web::Query<AgreementQueryParams>,
+    req: HttpRequest,
+) -> Result<AgreementReport, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = AgreementController::get_project_agreement(id.to_string(), query_params, auth);
+
+    res
+}
+
+pub fn project_routes() -> Scope {
+    web::scope("/projects")
+        .service(create_project)
+        .service(get_projects)
+        .service(get_project)
+        .service(get_project_stats)
+        .service(update_project)
+        .service(delete_project)
+        // members
+        .service(add_project_member)
+        .service(update_project_member)
+        .service(remove_project_member)
+        // grants
+        .service(get_project_grants)
+        .service(add_project_grant)
+        .service(update_project_grant)
+        .service(revoke_project_grant)
+        // queue
+        .service(get_next_annotation)
+        .service(get_queue)
+        .service(assign_annotation)
+        // agreement
+        .service(get_project_agreement)
+}
@@ -0,0 +1,21 @@
+use actix_web::{
+    get,
+    web::{self},
+    Result, Scope,
+};
+
+use crate::{
+    controllers::share_controller::ShareController, models::share_model::SharedAnnotation,
+    object::error::ApiError,
+};
+
+#[get("/{token}")]
+async fn get_shared_annotation(token: web::Path<String>) -> Result<SharedAnnotation, ApiError> {
+    let res = ShareController::get_shared(token.to_string());
+
+    res
+}
+
+pub fn share_routes() -> Scope {
+    web::scope("/shared").service(get_shared_annotation)
+}
@@ -0,0 +1,28 @@
+use actix_web::{
+    get,
+    web::{self},
+    HttpRequest, Result, Scope,
+};
+
+use crate::{
+    controllers::stats_controller::StatsController,
+    helpers::request_helpers::get_auth_ctx,
+    models::stats_model::{AnnotationStats, StatsQueryParams},
+    object::error::ApiError,
+};
+
+#[get("/")]
+async fn get_corpus_stats(
+    query_params: web::Query<StatsQueryParams>,
+    req: HttpRequest,
+) -> Result<AnnotationStats, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = StatsController::get_corpus_stats(query_params, auth);
+
+    res
+}
+
+pub fn stats_routes() -> Scope {
+    web::scope("/stats").service(get_corpus_stats)
+}
@@ -0,0 +1,57 @@
+use actix_web::{
+    get, post,
+    web::{self},
+    HttpRequest, Result, Scope,
+};
+
+use crate::{
+    controllers::tagger_controller::TaggerController,
+    helpers::request_helpers::get_auth_ctx,
+    models::tagger_model::{
+        PredictTaggerBody, PredictTaggerResponse, TaggerModel, TaggerQueryParams, TrainTaggerBody,
+    },
+    object::error::ApiError,
+};
+
+#[post("/train")]
+async fn train_tagger(
+    body: web::Json<TrainTaggerBody>,
+    req: HttpRequest,
+) -> Result<TaggerModel, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = TaggerController::train(body, auth);
+
+    res
+}
+
+#[post("/predict")]
+async fn predict_tagger(
+    body: web::Json<PredictTaggerBody>,
+    req: HttpRequest,
+) -> Result<PredictTaggerResponse, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = TaggerController::predict(body, auth);
+
+    res
+}
+
+#[get("/")]
+async fn get_tagger(
+    query_params: web::Query<TaggerQueryParams>,
+    req: HttpRequest,
+) -> Result<TaggerModel, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = TaggerController::get(query_params, auth);
+
+    res
+}
+
+pub fn tagger_routes() -> Scope {
+    web::scope("/tagger")
+        .service(train_tagger)
+        .service(predict_tagger)
+        .service(get_tagger)
+}
@@ -0,0 +1,173 @@
+use actix_web::{
+    delete, get, post, put,
+    web::{self, Json},
+    HttpRequest, Result, Scope,
+};
+
+use crate::{
+    controllers::team_controller::TeamController,
+    helpers::request_helpers::get_auth_ctx,
+    models::team_model::{
+        CreateTeamBody, InviteTeamMemberBody, PendingInvitation, Team, UpdateTeamBody,
+        UpdateTeamMemberBody,
+    },
+    object::{common::Message, error::ApiError},
+};
+
+#[post("/")]
+async fn create_team(body: web::Json<CreateTeamBody>, req: HttpRequest) -> Result<Team, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = TeamController::create(body, auth);
+
+    res
+}
+
+#[get("/")]
+async fn get_teams(req: HttpRequest) -> Result<Json<Vec<Team>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = TeamController::get_all(auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[get("/invitations")]
+async fn get_invitations(req: HttpRequest) -> Result<Json<Vec<PendingInvitation>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = TeamController::get_invitations(auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[post("/invitations/{invitation_id}/accept")]
+async fn accept_invitation(
+    invitation_id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Team, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = TeamController::accept_invitation(invitation_id.to_string(), auth);
+
+    res
+}
+
+#[post("/invitations/{invitation_id}/decline")]
+async fn decline_invitation(
+    invitation_id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Message, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = TeamController::decline_invitation(invitation_id.to_string(), auth);
+
+    res
+}
+
+#[get("/{id}")]
+async fn get_team(id: web::Path<String>, req: HttpRequest) -> Result<Team, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = TeamController::get(id.to_string(), auth);
+
+    res
+}
+
+#[put("/{id}")]
+async fn update_team(
+    body: web::Json<UpdateTeamBody>,
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Team, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = TeamController::update(id.to_string(), body, auth);
+
+    res
+}
+
+#[delete("/{id}")]
+async fn delete_team(id: web::Path<String>, req: HttpRequest) -> Result<Message, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = TeamController::delete(id.to_string(), auth);
+
+    res
+}
+
+#[post("/{id}/invitations")]
+async fn invite_team_member(
+    body: web::Json<InviteTeamMemberBody>,
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Team, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = TeamController::invite(id.to_string(), body, auth);
+
+    res
+}
+
+#[delete("/{id}/invitations/{invitation_id}")]
+async fn cancel_invitation(
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<Team, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = TeamController::cancel_invitation(params, auth);
+
+    res
+}
+
+#[put("/{id}/members/{user_id}")]
+async fn update_team_member(
+    body: web::Json<UpdateTeamMemberBody>,
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<Team, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = TeamController::update_member(params, body, auth);
+
+    res
+}
+
+#[delete("/{id}/members/{user_id}")]
+async fn remove_team_member(
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<Team, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = TeamController::remove_member(params, auth);
+
+    res
+}
+
+pub fn team_routes() -> Scope {
+    web::scope("/teams")
+        .service(create_team)
+        .service(get_teams)
+        // invitations must be registered before the team's id
+        .service(get_invitations)
+        .service(accept_invitation)
+        .service(decline_invitation)
+        .service(get_team)
+        .service(update_team)
+        .service(delete_team)
+        // members
+        .service(invite_team_member)
+        .service(cancel_invitation)
+        .service(update_team_member)
+        .service(remove_team_member)
+}
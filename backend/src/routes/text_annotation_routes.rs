@@ -1,18 +1,42 @@
 use actix_web::{
     delete, get, post, put,
     web::{self, Json},
-    HttpRequest, Result, Scope,
+    HttpRequest, HttpResponse, Result, Scope,
 };
 
 use crate::{
-    controllers::text_annotation_controller::AnnotationController,
-    helpers::request_helpers::get_auth_ctx,
-    models::text_annotation_model::{
-        CreateLabelBody, CreateTextAnnotationBody, CreateTokenBody, TextAnnotation,
-        UpdateLabelBody, UpdateTextAnnotationBody,
+    controllers::{
+        adjudication_controller::AdjudicationController, comment_controller::CommentController,
+        grant_controller::GrantController, model_backend_controller::ModelBackendController,
+        realtime_controller::RealtimeController, search_controller::SearchController,
+        share_controller::ShareController, stats_controller::StatsController,
+        suggestion_controller::SuggestionController, sync_controller::SyncController,
+        text_annotation_controller::AnnotationController,
+    },
+    helpers::request_helpers::{get_auth_ctx, get_if_match_version},
+    middleware::auth_middleware::get_auth_from_token,
+    models::{
+        adjudication_model::{AdjudicateBody, Adjudication},
+        comment_model::{CommentBody, CommentThread, CreateThreadBody, ThreadsQueryParams},
+        grant_model::{AccessGrant, CreateGrantBody, UpdateGrantBody},
+        model_backend_model::PreAnnotationResponse,
+        realtime_model::RealtimeQueryParams,
+        search_model::{
+            KwicQueryParams, KwicResponse, SearchQueryParams, SearchResponse, SpanQueryParams,
+            SpanSearchResponse,
+        },
+        share_model::{CreateShareLinkBody, ShareLink},
+        stats_model::{AnnotationStats, StatsQueryParams},
+        sync_model::{SyncBody, SyncResponse},
+        text_annotation_model::{
+            AnnotationLayer, BulkSuggestionBody, BulkSuggestionResponse, CreateLabelBody,
+            CreateSuggestionsBody, CreateTextAnnotationBody, CreateTokenBody,
+            ListAnnotationsQueryParams, TextAnnotation, TextAnnotationList,
+            UpdateAnnotationStatusBody, UpdateLabelBody, UpdateTextAnnotationBody,
+        },
     },
     object::{
-        common::{Message, PaginationQueryParams},
+        common::{CsvFile, Message},
         error::ApiError,
     },
 };
@@ -37,7 +61,300 @@ async fn update_annotation(
 ) -> Result<TextAnnotation, ApiError> {
     let auth = get_auth_ctx(&req);
 
-    let res = AnnotationController::update(id.clone(), auth, body);
+    let version = get_if_match_version(&req).or(body.version);
+
+    let res = AnnotationController::update(id.clone(), auth, body, version);
+
+    res
+}
+
+#[put("/{id}/status")]
+async fn update_annotation_status(
+    body: web::Json<UpdateAnnotationStatusBody>,
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<TextAnnotation, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let version = get_if_match_version(&req).or(body.version);
+
+    let res = AnnotationController::update_status(id.to_string(), body, version, auth);
+
+    res
+}
+
+#[get("/{id}/layers")]
+async fn get_annotation_layers(
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Json<Vec<AnnotationLayer>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = AnnotationController::get_layers(id.to_string(), auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[get("/{id}/adjudication")]
+async fn get_adjudication(
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Adjudication, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = AdjudicationController::get(id.to_string(), auth);
+
+    res
+}
+
+#[post("/{id}/adjudication")]
+async fn adjudicate_annotation(
+    body: web::Json<AdjudicateBody>,
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<TextAnnotation, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let version = get_if_match_version(&req).or(body.version);
+
+    let res = AdjudicationController::resolve(id.to_string(), body, version, auth);
+
+    res
+}
+
+#[get("/{id}/ws")]
+async fn connect_annotation(
+    id: web::Path<String>,
+    query_params: web::Query<RealtimeQueryParams>,
+    body: web::Payload,
+    req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    let auth =
+        get_auth_ctx(&req).or_else(|| query_params.token.clone().and_then(get_auth_from_token));
+
+    let res = RealtimeController::connect(id.to_string(), req, body, auth);
+
+    res
+}
+
+#[get("/{id}/grants")]
+async fn get_annotation_grants(
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Json<Vec<AccessGrant>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = GrantController::get_annotation_grants(id.to_string(), auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[post("/{id}/grants")]
+async fn add_annotation_grant(
+    body: web::Json<CreateGrantBody>,
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Json<Vec<AccessGrant>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let version = get_if_match_version(&req).or(body.version);
+
+    let res = GrantController::add_annotation_grant(id.to_string(), body, version, auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[put("/{id}/grants/{user_id}")]
+async fn update_annotation_grant(
+    body: web::Json<UpdateGrantBody>,
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<Json<Vec<AccessGrant>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let version = get_if_match_version(&req).or(body.version);
+
+    let res = GrantController::update_annotation_grant(params, body, version, auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[delete("/{id}/grants/{user_id}")]
+async fn revoke_annotation_grant(
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<Json<Vec<AccessGrant>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let version = get_if_match_version(&req);
+
+    let res = GrantController::revoke_annotation_grant(params, version, auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[get("/{id}/share-links")]
+async fn get_share_links(
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Json<Vec<ShareLink>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = ShareController::get_all(id.to_string(), auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[post("/{id}/share-links")]
+async fn create_share_link(
+    body: web::Json<CreateShareLinkBody>,
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<ShareLink, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = ShareController::create(id.to_string(), body, auth);
+
+    res
+}
+
+#[delete("/{id}/share-links/{link_id}")]
+async fn revoke_share_link(
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<Message, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = ShareController::revoke(params, auth);
+
+    res
+}
+
+#[get("/{id}/threads")]
+async fn get_comment_threads(
+    id: web::Path<String>,
+    query_params: web::Query<ThreadsQueryParams>,
+    req: HttpRequest,
+) -> Result<Json<Vec<CommentThread>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = CommentController::get_all(id.to_string(), query_params, auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[post("/{id}/threads")]
+async fn create_comment_thread(
+    body: web::Json<CreateThreadBody>,
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<CommentThread, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = CommentController::create(id.to_string(), body, auth);
+
+    res
+}
+
+#[post("/{id}/threads/{thread_id}/comments")]
+async fn reply_comment_thread(
+    body: web::Json<CommentBody>,
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<CommentThread, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = CommentController::reply(params, body, auth);
+
+    res
+}
+
+#[put("/{id}/threads/{thread_id}/comments/{comment_id}")]
+async fn update_comment(
+    body: web::Json<CommentBody>,
+    params: web::Path<(String, String, String)>,
+    req: HttpRequest,
+) -> Result<CommentThread, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = CommentController::update_comment(params, body, auth);
+
+    res
+}
+
+#[delete("/{id}/threads/{thread_id}/comments/{comment_id}")]
+async fn delete_comment(
+    params: web::Path<(String, String, String)>,
+    req: HttpRequest,
+) -> Result<Message, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = CommentController::delete_comment(params, auth);
+
+    res
+}
+
+#[post("/{id}/threads/{thread_id}/resolve")]
+async fn resolve_comment_thread(
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<CommentThread, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = CommentController::set_resolved(params, true, auth);
+
+    res
+}
+
+#[post("/{id}/threads/{thread_id}/unresolve")]
+async fn unresolve_comment_thread(
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<CommentThread, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = CommentController::set_resolved(params, false, auth);
+
+    res
+}
+
+#[post("/{id}/sync")]
+async fn sync_annotation(
+    body: web::Json<SyncBody>,
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<SyncResponse, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = SyncController::sync(id.to_string(), body, auth);
 
     res
 }
@@ -50,7 +367,9 @@ async fn create_label(
 ) -> Result<TextAnnotation, ApiError> {
     let auth = get_auth_ctx(&req);
 
-    let res = AnnotationController::create_label(id.clone(), body, auth);
+    let version = get_if_match_version(&req).or(body.version);
+
+    let res = AnnotationController::create_label(id.clone(), body, version, auth);
 
     res
 }
@@ -63,7 +382,9 @@ async fn update_label(
 ) -> Result<TextAnnotation, ApiError> {
     let auth = get_auth_ctx(&req);
 
-    let res = AnnotationController::update_label(body, auth, params);
+    let version = get_if_match_version(&req).or(body.version);
+
+    let res = AnnotationController::update_label(body, auth, params, version);
 
     res
 }
@@ -75,7 +396,9 @@ async fn delete_label(
 ) -> Result<TextAnnotation, ApiError> {
     let auth = get_auth_ctx(&req);
 
-    let res = AnnotationController::delete_label(auth, params);
+    let version = get_if_match_version(&req);
+
+    let res = AnnotationController::delete_label(auth, params, version);
 
     res
 }
@@ -88,7 +411,9 @@ async fn create_token(
 ) -> Result<TextAnnotation, ApiError> {
     let auth = get_auth_ctx(&req);
 
-    let res = AnnotationController::create_token(id.clone(), body, auth);
+    let version = get_if_match_version(&req).or(body.version);
+
+    let res = AnnotationController::create_token(id.clone(), body, version, auth);
 
     res
 }
@@ -100,7 +425,142 @@ async fn delete_token(
 ) -> Result<TextAnnotation, ApiError> {
     let auth = get_auth_ctx(&req);
 
-    let res = AnnotationController::delete_token(params, auth);
+    let version = get_if_match_version(&req);
+
+    let res = AnnotationController::delete_token(params, version, auth);
+
+    res
+}
+
+#[post("/{id}/suggestions")]
+async fn create_suggestions(
+    body: web::Json<CreateSuggestionsBody>,
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<TextAnnotation, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let version = get_if_match_version(&req).or(body.version);
+
+    let res = SuggestionController::create_many(id.clone(), body, version, auth);
+
+    res
+}
+
+#[post("/{id}/suggestions/bulk")]
+async fn bulk_suggestions(
+    body: web::Json<BulkSuggestionBody>,
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<BulkSuggestionResponse, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let version = get_if_match_version(&req).or(body.version);
+
+    let res = SuggestionController::bulk(id.clone(), body, version, auth);
+
+    res
+}
+
+#[post("/{id}/suggestions/{suggestion_id}/accept")]
+async fn accept_suggestion(
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<TextAnnotation, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let version = get_if_match_version(&req);
+
+    let res = SuggestionController::accept(params, version, auth);
+
+    res
+}
+
+#[post("/{id}/suggestions/{suggestion_id}/reject")]
+async fn reject_suggestion(
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<TextAnnotation, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let version = get_if_match_version(&req);
+
+    let res = SuggestionController::reject(params, version, auth);
+
+    res
+}
+
+#[post("/{id}/pre-annotate/{backend_id}")]
+async fn pre_annotate(
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<PreAnnotationResponse, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let version = get_if_match_version(&req);
+
+    let res = ModelBackendController::run(params, version, auth);
+
+    res
+}
+
+#[get("/{id}/stats")]
+async fn get_annotation_stats(
+    id: web::Path<String>,
+    query_params: web::Query<StatsQueryParams>,
+    req: HttpRequest,
+) -> Result<AnnotationStats, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = StatsController::get_annotation_stats(id.to_string(), query_params, auth);
+
+    res
+}
+
+#[get("/search")]
+async fn search_annotations(
+    query_params: web::Query<SearchQueryParams>,
+    req: HttpRequest,
+) -> Result<SearchResponse, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = SearchController::search(query_params, auth);
+
+    res
+}
+
+#[get("/spans")]
+async fn search_spans(
+    query_params: web::Query<SpanQueryParams>,
+    req: HttpRequest,
+) -> Result<SpanSearchResponse, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = SearchController::search_spans(query_params, auth);
+
+    res
+}
+
+#[get("/kwic")]
+async fn get_kwic(
+    query_params: web::Query<KwicQueryParams>,
+    req: HttpRequest,
+) -> Result<KwicResponse, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = SearchController::kwic(query_params, auth);
+
+    res
+}
+
+#[get("/kwic/export")]
+async fn export_kwic(
+    query_params: web::Query<KwicQueryParams>,
+    req: HttpRequest,
+) -> Result<CsvFile, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = SearchController::export_kwic(query_params, auth);
 
     res
 }
@@ -118,26 +578,24 @@ async fn get_annotation(
 }
 
 #[get("/")]
-async fn get_annotations_page(
-    query_params: web::Query<PaginationQueryParams>,
+async fn list_annotations(
+    query_params: web::Query<ListAnnotationsQueryParams>,
     req: HttpRequest,
-) -> Result<Json<Vec<TextAnnotation>>, ApiError> {
+) -> Result<TextAnnotationList, ApiError> {
     let auth = get_auth_ctx(&req);
 
-    let res = AnnotationController::get_page(query_params, auth);
+    let res = AnnotationController::list(query_params, auth);
 
-    if res.is_err() {
-        return Err(res.err().unwrap());
-    }
-
-    Ok(Json(res.unwrap()))
+    res
 }
 
 #[delete("/{id}")]
 async fn delete_annotation(id: web::Path<String>, req: HttpRequest) -> Result<Message, ApiError> {
     let auth = get_auth_ctx(&req);
 
-    let res = AnnotationController::delete(id.to_string(), auth);
+    let version = get_if_match_version(&req);
+
+    let res = AnnotationController::delete(id.to_string(), version, auth);
 
     res
 }
@@ -146,9 +604,36 @@ pub fn annotation_routes() -> Scope {
     web::scope("/annotations/text")
         .service(create_annotation)
         .service(update_annotation)
+        .service(update_annotation_status)
+        // search routes must be registered before the annotation's id
+        .service(search_annotations)
+        .service(search_spans)
+        .service(get_kwic)
+        .service(export_kwic)
         .service(get_annotation)
-        .service(get_annotations_page)
+        .service(list_annotations)
         .service(delete_annotation)
+        // real-time collaboration
+        .service(connect_annotation)
+        // grants
+        .service(get_annotation_grants)
+        .service(add_annotation_grant)
+        .service(update_annotation_grant)
+        .service(revoke_annotation_grant)
+        // share links
+        .service(get_share_links)
+        .service(create_share_link)
+        .service(revoke_share_link)
+        // comments
+        .service(get_comment_threads)
+        .service(create_comment_thread)
+        .service(reply_comment_thread)
+        .service(update_comment)
+        .service(delete_comment)
+        .service(resolve_comment_thread)
+        .service(unresolve_comment_thread)
+        // offline sync
+        .service(sync_annotation)
         // labels
         .service(create_label)
         .service(update_label)
@@ -156,4 +641,15 @@ pub fn annotation_routes() -> Scope {
         // tokens
         .service(create_token)
         .service(delete_token)
+        .service(get_annotation_layers)
+        .service(get_adjudication)
+        .service(adjudicate_annotation)
+        // suggestions
+        .service(create_suggestions)
+        .service(bulk_suggestions)
+        .service(accept_suggestion)
+        .service(reject_suggestion)
+        .service(pre_annotate)
+        // statistics
+        .service(get_annotation_stats)
 }
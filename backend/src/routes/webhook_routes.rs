@@ -0,0 +1,100 @@
+use actix_web::{
+    delete, get, post, put,
+    web::{self, Json},
+    HttpRequest, Result, Scope,
+};
+
+use crate::{
+    controllers::webhook_controller::WebhookController,
+    helpers::request_helpers::get_auth_ctx,
+    models::webhook_model::{
+        CreateWebhookBody, DeliveriesQueryParams, UpdateWebhookBody, Webhook, WebhookDelivery,
+    },
+    object::{common::Message, error::ApiError},
+};
+
+#[post("/")]
+async fn create_webhook(
+    body: web::Json<CreateWebhookBody>,
+    req: HttpRequest,
+) -> Result<Webhook, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = WebhookController::create(body, auth);
+
+    res
+}
+
+#[get("/")]
+async fn get_webhooks(req: HttpRequest) -> Result<Json<Vec<Webhook>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = WebhookController::get_all(auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[put("/{id}")]
+async fn update_webhook(
+    body: web::Json<UpdateWebhookBody>,
+    id: web::Path<String>,
+    req: HttpRequest,
+) -> Result<Webhook, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = WebhookController::update(id.clone(), body, auth);
+
+    res
+}
+
+#[delete("/{id}")]
+async fn delete_webhook(id: web::Path<String>, req: HttpRequest) -> Result<Message, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = WebhookController::delete(id.to_string(), auth);
+
+    res
+}
+
+#[get("/{id}/deliveries")]
+async fn get_webhook_deliveries(
+    id: web::Path<String>,
+    query_params: web::Query<DeliveriesQueryParams>,
+    req: HttpRequest,
+) -> Result<Json<Vec<WebhookDelivery>>, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = WebhookController::get_deliveries(id.to_string(), query_params, auth);
+
+    if res.is_err() {
+        return Err(res.err().unwrap());
+    }
+
+    Ok(Json(res.unwrap()))
+}
+
+#[post("/{id}/deliveries/{delivery_id}/redeliver")]
+async fn redeliver_webhook_delivery(
+    params: web::Path<(String, String)>,
+    req: HttpRequest,
+) -> Result<WebhookDelivery, ApiError> {
+    let auth = get_auth_ctx(&req);
+
+    let res = WebhookController::redeliver(params, auth);
+
+    res
+}
+
+pub fn webhook_routes() -> Scope {
+    web::scope("/webhooks")
+        .service(create_webhook)
+        .service(get_webhooks)
+        .service(update_webhook)
+        .service(delete_webhook)
+        .service(get_webhook_deliveries)
+        .service(redeliver_webhook_delivery)
+}
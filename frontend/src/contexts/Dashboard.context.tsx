@@ -6,8 +6,12 @@ import {
   useEffect,
   useState,
 } from 'react';
-import { CreateTextAnnotationBody, TextAnnotation } from '../types/annotations';
-import $api from '../utils/api';
+import {
+  CreateTextAnnotationBody,
+  TextAnnotation,
+  TextAnnotationList,
+} from '../types/annotations';
+import $api, { ifMatch } from '../utils/api';
 import AppContext from './App.context';
 
 export interface DashboardData {
@@ -46,18 +50,23 @@ export const DashboardProvider = ({ children }: PropsWithChildren) => {
     setTextAnnotations((v) => [...v, res.data]);
   }, []);
 
-  const deleteTextAnnotation = useCallback(async (id: string) => {
-    await $api.delete(`/annotations/text/${id}`);
+  const deleteTextAnnotation = useCallback(
+    async (id: string) => {
+      const annotation = textAnnotations.find((it) => it._id.$oid === id);
 
-    setTextAnnotations((v) => v.filter((it) => it._id.$oid !== id));
-  }, []);
+      if (!annotation) return;
+
+      await $api.delete(`/annotations/text/${id}`, ifMatch(annotation.version));
+
+      setTextAnnotations((v) => v.filter((it) => it._id.$oid !== id));
+    },
+    [textAnnotations]
+  );
 
-  const fetchTextAnnotations = useCallback(async (page = 1, count = 10) => {
-    const res = await $api.get<Array<TextAnnotation>>(
-      `/annotations/text/?page=${page}&count=${count}`
-    );
+  const fetchTextAnnotations = useCallback(async (limit = 10) => {
+    const res = await $api.get<TextAnnotationList>(`/annotations/text/?limit=${limit}`);
 
-    setTextAnnotations(res.data);
+    setTextAnnotations(res.data.results);
   }, []);
 
   useEffect(() => {
@@ -1,6 +1,6 @@
 import { PropsWithChildren, createContext, useCallback, useEffect, useMemo, useState } from 'react';
 import { TextAnnotation, Token } from '../types/annotations';
-import $api from '../utils/api';
+import $api, { ifMatch } from '../utils/api';
 import { toast } from 'sonner';
 import { useParams } from 'react-router-dom';
 
@@ -155,7 +155,7 @@ export const TextAnnotationProvider = ({ children }: PropsWithChildren) => {
       if (!annotation || !id) return;
 
       return $api
-        .post<TextAnnotation>(`/annotations/text/${id}/labels`, body)
+        .post<TextAnnotation>(`/annotations/text/${id}/labels`, body, ifMatch(annotation.version))
         .then((it) => setAnnotation(it.data));
     },
     [annotation, id]
@@ -166,7 +166,11 @@ export const TextAnnotationProvider = ({ children }: PropsWithChildren) => {
       if (!annotation || !id) return;
 
       return $api
-        .put<TextAnnotation>(`/annotations/text/${id}/labels/${labelId}`, body)
+        .put<TextAnnotation>(
+          `/annotations/text/${id}/labels/${labelId}`,
+          body,
+          ifMatch(annotation.version)
+        )
         .then((it) => setAnnotation(it.data));
     },
     [annotation, id]
@@ -177,7 +181,10 @@ export const TextAnnotationProvider = ({ children }: PropsWithChildren) => {
       if (!annotation || !id) return;
 
       return $api
-        .delete<TextAnnotation>(`/annotations/text/${id}/labels/${labelId}`)
+        .delete<TextAnnotation>(
+          `/annotations/text/${id}/labels/${labelId}`,
+          ifMatch(annotation.version)
+        )
         .then((it) => setAnnotation(it.data));
     },
     [annotation, id]
@@ -238,7 +245,11 @@ export const TextAnnotationProvider = ({ children }: PropsWithChildren) => {
     const body = { start, end, label: selectedLabel };
 
     $api
-      .post<TextAnnotation>(`/annotations/text/${annotation?._id.$oid}/tokens`, body)
+      .post<TextAnnotation>(
+        `/annotations/text/${annotation._id.$oid}/tokens`,
+        body,
+        ifMatch(annotation.version)
+      )
       .then((it) => setAnnotation(it.data));
   }, [annotation, cursor, selectedLabel]);
 
@@ -247,7 +258,10 @@ export const TextAnnotationProvider = ({ children }: PropsWithChildren) => {
       if (!annotation) return;
 
       $api
-        .delete<TextAnnotation>(`/annotations/text/${annotation._id.$oid}/tokens/${id}`)
+        .delete<TextAnnotation>(
+          `/annotations/text/${annotation._id.$oid}/tokens/${id}`,
+          ifMatch(annotation.version)
+        )
         .then((it) => {
           setAnnotation(it.data);
           toast.info('Token deleted successfully');
@@ -260,10 +274,16 @@ export const TextAnnotationProvider = ({ children }: PropsWithChildren) => {
     async (body) => {
       if (!annotation) return;
 
-      $api.put<TextAnnotation>(`/annotations/text/${annotation._id.$oid}`, body).then((it) => {
-        setAnnotation(it.data);
-        toast.info('Annotation updated successfully');
-      });
+      $api
+        .put<TextAnnotation>(
+          `/annotations/text/${annotation._id.$oid}`,
+          body,
+          ifMatch(annotation.version)
+        )
+        .then((it) => {
+          setAnnotation(it.data);
+          toast.info('Annotation updated successfully');
+        });
     },
     [annotation]
   );
@@ -1,11 +1,11 @@
-import { Base, ObjectId } from './base';
+import { Base, BsonDate, ObjectId, Timestamps } from './base';
 
-export interface Annotation extends Base {
+export interface Annotation extends Base, Timestamps {
   title: string;
   user_id: ObjectId;
 }
 
-export interface Label extends Base {
+export interface Label extends Base, Timestamps {
   name: string;
   color: string;
 }
@@ -14,12 +14,42 @@ export interface Token extends Base {
   start: number;
   end: number;
   label: ObjectId | undefined;
+  created_at?: BsonDate;
+  created_by?: ObjectId;
+}
+
+export interface AnnotationLayer {
+  user_id: ObjectId;
+  tokens: Array<Token>;
+}
+
+export type AnnotationStatus = 'todo' | 'in_progress' | 'done' | 'reviewed';
+
+export type AnnotationRole = 'viewer' | 'annotator' | 'editor' | 'owner';
+
+export interface AccessGrant {
+  user_id: ObjectId;
+  role: AnnotationRole;
+  created_at?: BsonDate;
 }
 
 export interface TextAnnotation extends Annotation {
   content: string;
+  status: AnnotationStatus;
   labels: Array<Label>;
   tokens: Array<Token>;
+  layers: Array<AnnotationLayer>;
+  grants: Array<AccessGrant>;
+  role?: AnnotationRole;
+  open_thread_count?: number;
+  version: number;
+}
+
+export interface TextAnnotationList {
+  total: number;
+  results: Array<TextAnnotation>;
+  next_cursor: string | null;
+  prev_cursor: string | null;
 }
 
 export type CreateTextAnnotationBody = Pick<TextAnnotation, 'content' | 'title'>;
@@ -2,6 +2,15 @@ export interface ObjectId {
   $oid: string;
 }
 
+export interface BsonDate {
+  $date: { $numberLong: string };
+}
+
+export interface Timestamps {
+  created_at?: BsonDate;
+  updated_at?: BsonDate;
+}
+
 export interface Base {
   _id: ObjectId;
 }
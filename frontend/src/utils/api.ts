@@ -30,4 +30,7 @@ $api.interceptors.response.use(
   }
 );
 
+// the server rejects changes made against an outdated annotation
+export const ifMatch = (version: number) => ({ headers: { 'If-Match': `"${version}"` } });
+
 export default $api;